@@ -0,0 +1,922 @@
+//! The interpreter exposed through the EVMC C interface, so it can be loaded as a shared
+//! library by EVMC hosts and test tools.
+//!
+//! The VM is created by `evmc_create_evm_evmc`. Calls and contract creation are made through
+//! the host's `call` callback, and storage, including transient storage, is read and written
+//! through the host.
+
+pub mod sys;
+
+use evm::{
+    bytecode::Bytecode,
+    context::{Block, Transaction},
+    gas_schedule::GasSchedule,
+    host::{CallKind, CallOutcome, Message, StorageStatus},
+    interpreter::{Account, Frame, HaltReason, StackError, Word},
+    primitives::{Address, Log, B256, U256},
+    ExecutionResult, Host, Interpreter, SpecId,
+};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::{c_char, c_int},
+    ptr, slice,
+    sync::Arc,
+};
+use sys::*;
+
+const NAME: &str = "evm\0";
+const VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+
+/// Create an instance of the VM, to be released by its `destroy` function.
+#[no_mangle]
+pub extern "C" fn evmc_create_evm_evmc() -> *mut EvmcVm {
+    Box::into_raw(Box::new(EvmcVm {
+        abi_version: EVMC_ABI_VERSION,
+        name: NAME.as_ptr() as *const c_char,
+        version: VERSION.as_ptr() as *const c_char,
+        destroy: Some(destroy),
+        execute: Some(execute),
+        get_capabilities: Some(get_capabilities),
+        set_option: Some(set_option),
+    }))
+}
+
+/// Returns the fork whose rules apply under the given `evmc_revision`.
+pub fn spec_for_revision(revision: c_int) -> Option<SpecId> {
+    let spec = match revision {
+        0 => SpecId::Frontier,
+        1 => SpecId::Homestead,
+        2 => SpecId::TangerineWhistle,
+        3 => SpecId::SpuriousDragon,
+        4 => SpecId::Byzantium,
+        5 => SpecId::Constantinople,
+        6 => SpecId::Petersburg,
+        7 => SpecId::Istanbul,
+        8 => SpecId::Berlin,
+        9 => SpecId::London,
+        10 => SpecId::Paris,
+        11 => SpecId::Shanghai,
+        12 => SpecId::Cancun,
+        13 => SpecId::Prague,
+        14 => SpecId::Osaka,
+        15 => SpecId::Experimental,
+        _ => return None,
+    };
+    Some(spec)
+}
+
+unsafe extern "C" fn destroy(vm: *mut EvmcVm) {
+    drop(Box::from_raw(vm));
+}
+
+unsafe extern "C" fn get_capabilities(_vm: *mut EvmcVm) -> u32 {
+    EVMC_CAPABILITY_EVM1
+}
+
+unsafe extern "C" fn set_option(
+    _vm: *mut EvmcVm,
+    _name: *const c_char,
+    _value: *const c_char,
+) -> c_int {
+    EVMC_SET_OPTION_INVALID_NAME
+}
+
+unsafe extern "C" fn execute(
+    _vm: *mut EvmcVm,
+    host: *const EvmcHostInterface,
+    context: *mut EvmcHostContext,
+    revision: c_int,
+    message: *const EvmcMessage,
+    code: *const u8,
+    code_size: usize,
+) -> EvmcResult {
+    let Some(spec) = spec_for_revision(revision) else {
+        return EvmcResult::failure(EvmcStatusCode::Rejected);
+    };
+    let message = &*message;
+    let mut host = EvmcHost::new(&*host, context, spec);
+    let account = Arc::new(Account {
+        code: Bytecode::from(bytes(code, code_size)),
+        ..Default::default()
+    });
+    let mut frame = Frame::new(
+        message.recipient.into_address(),
+        message.sender.into_address(),
+        message.value.into_word(),
+        message.gas.max(0) as u64,
+        account,
+        spec,
+    );
+    frame.can_modify_state = message.flags & EVMC_STATIC == 0;
+    frame.depth = message.depth.max(0) as usize;
+    frame.input = bytes(message.input_data, message.input_size).to_vec();
+    match Interpreter::default().execute_with_host(frame, &mut host) {
+        Ok((frame, result)) => EvmcResult::new(&frame, &result),
+        Err(_) => EvmcResult::failure(EvmcStatusCode::InternalError),
+    }
+}
+
+unsafe extern "C" fn release_result(result: *const EvmcResult) {
+    let result = &*result;
+    if !result.output_data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            result.output_data as *mut u8,
+            result.output_size,
+        )));
+    }
+}
+
+// Returns the `size` bytes at `data`, which may be null if `size` is zero.
+unsafe fn bytes<'a>(data: *const u8, size: usize) -> &'a [u8] {
+    if data.is_null() || size == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(data, size)
+    }
+}
+
+impl EvmcResult {
+    fn new(frame: &Frame, result: &ExecutionResult) -> Self {
+        let gas_left = (frame.gas_limit - frame.gas_used) as i64;
+        let (status_code, gas_left, gas_refund) = match result {
+            ExecutionResult::Success { .. } => {
+                (EvmcStatusCode::Success, gas_left, frame.gas_refunded)
+            }
+            ExecutionResult::Revert { .. } => (EvmcStatusCode::Revert, gas_left, 0),
+            ExecutionResult::Halt { reason, .. } => (status_code(reason), 0, 0),
+        };
+        let output = result.output().unwrap_or_default();
+        let (output_data, output_size) = if output.is_empty() {
+            (ptr::null(), 0)
+        } else {
+            let output_size = output.len();
+            let output = Box::<[u8]>::from(output);
+            (Box::into_raw(output) as *const u8, output_size)
+        };
+        Self {
+            status_code,
+            gas_left,
+            gas_refund,
+            output_data,
+            output_size,
+            release: Some(release_result),
+            create_address: Default::default(),
+            padding: Default::default(),
+        }
+    }
+
+    fn failure(status_code: EvmcStatusCode) -> Self {
+        Self {
+            status_code,
+            gas_left: 0,
+            gas_refund: 0,
+            output_data: ptr::null(),
+            output_size: 0,
+            release: None,
+            create_address: Default::default(),
+            padding: Default::default(),
+        }
+    }
+}
+
+fn status_code(reason: &HaltReason) -> EvmcStatusCode {
+    match reason {
+        HaltReason::OutOfGas => EvmcStatusCode::OutOfGas,
+        HaltReason::InvalidOpcode(0xfe) => EvmcStatusCode::InvalidInstruction,
+        HaltReason::InvalidOpcode(_) => EvmcStatusCode::UndefinedInstruction,
+        HaltReason::Stack(StackError::Overflow) | HaltReason::ReturnStackOverflow => {
+            EvmcStatusCode::StackOverflow
+        }
+        HaltReason::Stack(StackError::Underflow) => EvmcStatusCode::StackUnderflow,
+        HaltReason::InvalidJump => EvmcStatusCode::BadJumpDestination,
+        HaltReason::StaticViolation => EvmcStatusCode::StaticModeViolation,
+        HaltReason::CallDepthExceeded => EvmcStatusCode::CallDepthExceeded,
+        HaltReason::ReturnDataOutOfBounds => EvmcStatusCode::InvalidMemoryAccess,
+        HaltReason::CreateCollision
+        | HaltReason::InvalidContractCode
+        | HaltReason::InvalidAddress => EvmcStatusCode::Failure,
+    }
+}
+
+// EVMC has no finer status for failures other than these, and the calling frame cannot tell
+// them apart
+fn halt_reason(status_code: EvmcStatusCode) -> HaltReason {
+    match status_code {
+        EvmcStatusCode::OutOfGas => HaltReason::OutOfGas,
+        EvmcStatusCode::StackOverflow => StackError::Overflow.into(),
+        EvmcStatusCode::StackUnderflow => StackError::Underflow.into(),
+        EvmcStatusCode::BadJumpDestination => HaltReason::InvalidJump,
+        EvmcStatusCode::InvalidMemoryAccess => HaltReason::ReturnDataOutOfBounds,
+        EvmcStatusCode::CallDepthExceeded => HaltReason::CallDepthExceeded,
+        EvmcStatusCode::StaticModeViolation => HaltReason::StaticViolation,
+        _ => HaltReason::InvalidOpcode(0xfe),
+    }
+}
+
+impl EvmcAddress {
+    fn into_address(self) -> Address {
+        Address::from(self.bytes)
+    }
+}
+
+impl From<Address> for EvmcAddress {
+    fn from(address: Address) -> Self {
+        Self {
+            bytes: address.into_array(),
+        }
+    }
+}
+
+impl EvmcBytes32 {
+    fn into_word(self) -> Word {
+        Word::from_be_bytes(self.bytes)
+    }
+}
+
+impl From<Word> for EvmcBytes32 {
+    fn from(word: Word) -> Self {
+        Self {
+            bytes: word.to_be_bytes(),
+        }
+    }
+}
+
+/// A `Host` making callbacks into an EVMC host.
+struct EvmcHost<'a> {
+    host: &'a EvmcHostInterface,
+    context: *mut EvmcHostContext,
+    spec: SpecId,
+    gas_schedule: GasSchedule,
+    chain_id: u64,
+    block: Block,
+    transaction: Transaction,
+    blob_base_fee: U256,
+    accounts: RefCell<HashMap<Address, Arc<Account>>>,
+}
+
+impl<'a> EvmcHost<'a> {
+    unsafe fn new(
+        host: &'a EvmcHostInterface,
+        context: *mut EvmcHostContext,
+        spec: SpecId,
+    ) -> Self {
+        let tx_context = (host.get_tx_context)(context);
+        let blob_hashes = if tx_context.blob_hashes.is_null() {
+            &[]
+        } else {
+            slice::from_raw_parts(tx_context.blob_hashes, tx_context.blob_hashes_count)
+        };
+        let initcodes = if tx_context.initcodes.is_null() {
+            &[]
+        } else {
+            slice::from_raw_parts(tx_context.initcodes, tx_context.initcodes_count)
+        };
+        Self {
+            host,
+            context,
+            spec,
+            gas_schedule: GasSchedule::for_spec(spec),
+            chain_id: u64::try_from(tx_context.chain_id.into_word()).unwrap_or(u64::MAX),
+            block: Block {
+                number: tx_context.block_number.max(0) as u64,
+                coinbase: tx_context.block_coinbase.into_address(),
+                timestamp: tx_context.block_timestamp.max(0) as u64,
+                prev_randao: B256::from(tx_context.block_prev_randao.bytes),
+                gas_limit: tx_context.block_gas_limit.max(0) as u64,
+                base_fee: tx_context.block_base_fee.into_word(),
+                ..Default::default()
+            },
+            transaction: Transaction {
+                gas_price: tx_context.tx_gas_price.into_word(),
+                sender: tx_context.tx_origin.into_address(),
+                blob_versioned_hashes: blob_hashes
+                    .iter()
+                    .map(|hash| B256::from(hash.bytes))
+                    .collect(),
+                initcodes: initcodes
+                    .iter()
+                    .map(|initcode| bytes(initcode.code, initcode.code_size).to_vec())
+                    .collect(),
+                ..Default::default()
+            },
+            blob_base_fee: tx_context.blob_base_fee.into_word(),
+            accounts: Default::default(),
+        }
+    }
+}
+
+impl Host for EvmcHost<'_> {
+    fn spec(&self) -> SpecId {
+        self.spec
+    }
+
+    fn gas_schedule(&self) -> &GasSchedule {
+        &self.gas_schedule
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn block(&self) -> &Block {
+        &self.block
+    }
+
+    fn transaction(&self) -> &Transaction {
+        &self.transaction
+    }
+
+    fn base_fee(&self) -> U256 {
+        self.block.base_fee
+    }
+
+    fn blob_base_fee(&self) -> U256 {
+        self.blob_base_fee
+    }
+
+    fn block_hash(&self, number: u64) -> Result<B256, evm::Error> {
+        let hash = unsafe { (self.host.get_block_hash)(self.context, number as i64) };
+        Ok(B256::from(hash.bytes))
+    }
+
+    fn load_account(&self, address: &Address) -> Result<Arc<Account>, evm::Error> {
+        if let Some(account) = self.accounts.borrow().get(address) {
+            return Ok(account.clone());
+        }
+        let evmc_address = EvmcAddress::from(*address);
+        let account = unsafe {
+            let exists = (self.host.account_exists)(self.context, &evmc_address);
+            let balance = (self.host.get_balance)(self.context, &evmc_address);
+            let code_size = (self.host.get_code_size)(self.context, &evmc_address);
+            let mut code = vec![0; code_size];
+            let copied = (self.host.copy_code)(
+                self.context,
+                &evmc_address,
+                0,
+                code.as_mut_ptr(),
+                code.len(),
+            );
+            code.truncate(copied);
+            Arc::new(Account {
+                balance: balance.into_word(),
+                // EVMC does not expose nonces, but an existing account without balance or
+                // code must have one after EIP-161, so this keeps it from counting as empty
+                nonce: exists as u64,
+                storage: Default::default(),
+                code: Bytecode::from(code.as_slice()),
+            })
+        };
+        self.accounts.borrow_mut().insert(*address, account.clone());
+        Ok(account)
+    }
+
+    fn access_account(&mut self, address: Address) -> bool {
+        let address = EvmcAddress::from(address);
+        unsafe { (self.host.access_account)(self.context, &address) != EVMC_ACCESS_WARM }
+    }
+
+    fn access_storage_key(&mut self, address: Address, key: Word) -> bool {
+        let (address, key) = (EvmcAddress::from(address), EvmcBytes32::from(key));
+        unsafe { (self.host.access_storage)(self.context, &address, &key) != EVMC_ACCESS_WARM }
+    }
+
+    fn load_storage(&self, address: &Address, key: &Word) -> Result<Word, evm::Error> {
+        let (address, key) = (EvmcAddress::from(*address), EvmcBytes32::from(*key));
+        let value = unsafe { (self.host.get_storage)(self.context, &address, &key) };
+        Ok(value.into_word())
+    }
+
+    fn store_storage(
+        &mut self,
+        address: Address,
+        key: Word,
+        value: Word,
+    ) -> Result<StorageStatus, evm::Error> {
+        let (address, key, value) = (
+            EvmcAddress::from(address),
+            EvmcBytes32::from(key),
+            EvmcBytes32::from(value),
+        );
+        let status = unsafe { (self.host.set_storage)(self.context, &address, &key, &value) };
+        Ok(match status {
+            EVMC_STORAGE_ADDED => StorageStatus::Added,
+            EVMC_STORAGE_DELETED => StorageStatus::Deleted,
+            EVMC_STORAGE_MODIFIED => StorageStatus::Modified,
+            EVMC_STORAGE_DELETED_ADDED => StorageStatus::DeletedAdded,
+            EVMC_STORAGE_MODIFIED_DELETED => StorageStatus::ModifiedDeleted,
+            EVMC_STORAGE_DELETED_RESTORED => StorageStatus::DeletedRestored,
+            EVMC_STORAGE_ADDED_DELETED => StorageStatus::AddedDeleted,
+            EVMC_STORAGE_MODIFIED_RESTORED => StorageStatus::ModifiedRestored,
+            // `EVMC_STORAGE_ASSIGNED`, which hosts also return for any status they don't track
+            _ => StorageStatus::Assigned,
+        })
+    }
+
+    fn load_transient_storage(&self, address: &Address, key: &Word) -> Word {
+        let (address, key) = (EvmcAddress::from(*address), EvmcBytes32::from(*key));
+        let value = unsafe { (self.host.get_transient_storage)(self.context, &address, &key) };
+        value.into_word()
+    }
+
+    fn store_transient_storage(&mut self, address: Address, key: Word, value: Word) {
+        let (address, key, value) = (
+            EvmcAddress::from(address),
+            EvmcBytes32::from(key),
+            EvmcBytes32::from(value),
+        );
+        unsafe { (self.host.set_transient_storage)(self.context, &address, &key, &value) };
+    }
+
+    fn log(&mut self, log: &Log) {
+        let address = EvmcAddress::from(log.address);
+        let topics = log
+            .topics()
+            .iter()
+            .map(|topic| EvmcBytes32 { bytes: topic.0 })
+            .collect::<Vec<_>>();
+        let data = &log.data.data;
+        unsafe {
+            (self.host.emit_log)(
+                self.context,
+                &address,
+                data.as_ptr(),
+                data.len(),
+                topics.as_ptr(),
+                topics.len(),
+            )
+        };
+    }
+
+    fn self_destruct(&mut self, address: Address, target: Address) -> Result<bool, evm::Error> {
+        let (address, target) = (EvmcAddress::from(address), EvmcAddress::from(target));
+        Ok(unsafe { (self.host.selfdestruct)(self.context, &address, &target) })
+    }
+
+    fn call(&mut self, message: &Message) -> Result<CallOutcome, evm::Error> {
+        let (kind, salt) = match message.kind {
+            CallKind::Call => (EVMC_CALL, Word::ZERO),
+            CallKind::DelegateCall => (EVMC_DELEGATECALL, Word::ZERO),
+            CallKind::CallCode => (EVMC_CALLCODE, Word::ZERO),
+            CallKind::Create => (EVMC_CREATE, Word::ZERO),
+            CallKind::Create2 { salt } => (EVMC_CREATE2, salt),
+            CallKind::EofCreate { salt } => (EVMC_EOFCREATE, salt),
+        };
+        let evmc_message = EvmcMessage {
+            kind,
+            flags: if message.is_static { EVMC_STATIC } else { 0 },
+            depth: message.depth as i32,
+            gas: i64::try_from(message.gas_limit).unwrap_or(i64::MAX),
+            recipient: message.recipient.into(),
+            sender: message.caller.into(),
+            input_data: message.input.as_ptr(),
+            input_size: message.input.len(),
+            value: message.value.into(),
+            create2_salt: salt.into(),
+            code_address: message.code_address.into(),
+            // only set for EOFCREATE, which passes the initcontainer separately from its input
+            code: if message.code.is_empty() {
+                ptr::null()
+            } else {
+                message.code.as_ptr()
+            },
+            code_size: message.code.len(),
+        };
+        let result = unsafe { (self.host.call)(self.context, &evmc_message) };
+        let output = unsafe { bytes(result.output_data, result.output_size) }.to_vec();
+        if let Some(release) = result.release {
+            unsafe { release(&result) };
+        }
+        // The call may have changed the balance of any account
+        self.accounts.get_mut().clear();
+
+        let created_address = (result.status_code == EvmcStatusCode::Success
+            && message.kind.is_create())
+        .then(|| result.create_address.into_address());
+        let gas_left = (result.gas_left.max(0) as u64).min(message.gas_limit);
+        let gas_used = message.gas_limit - gas_left;
+        let (result, gas_refunded) = match result.status_code {
+            EvmcStatusCode::Success => (
+                ExecutionResult::Success {
+                    output,
+                    // the VM running the frame emits its logs to the host itself
+                    logs: vec![],
+                    gas_used,
+                    gas_refunded: result.gas_refund.max(0) as u64,
+                },
+                result.gas_refund,
+            ),
+            EvmcStatusCode::Revert => (ExecutionResult::Revert { output, gas_used }, 0),
+            status_code => (
+                ExecutionResult::Halt {
+                    reason: halt_reason(status_code),
+                    gas_used,
+                },
+                0,
+            ),
+        };
+        Ok(CallOutcome {
+            result,
+            gas_refunded,
+            created_address,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockHost {
+        storage: HashMap<[u8; 32], [u8; 32]>,
+        transient_storage: HashMap<[u8; 32], [u8; 32]>,
+        // The `evmc_storage_status` returned for every write
+        storage_status: c_int,
+        // The recipient and depth of each message
+        calls: Vec<(Address, i32)>,
+    }
+
+    unsafe fn mock<'a>(context: *mut EvmcHostContext) -> &'a mut MockHost {
+        &mut *(context as *mut MockHost)
+    }
+
+    unsafe extern "C" fn account_exists(_: *mut EvmcHostContext, _: *const EvmcAddress) -> bool {
+        false
+    }
+
+    unsafe extern "C" fn get_storage(
+        context: *mut EvmcHostContext,
+        _: *const EvmcAddress,
+        key: *const EvmcBytes32,
+    ) -> EvmcBytes32 {
+        let bytes = mock(context)
+            .storage
+            .get(&(*key).bytes)
+            .copied()
+            .unwrap_or_default();
+        EvmcBytes32 { bytes }
+    }
+
+    unsafe extern "C" fn set_storage(
+        context: *mut EvmcHostContext,
+        _: *const EvmcAddress,
+        key: *const EvmcBytes32,
+        value: *const EvmcBytes32,
+    ) -> c_int {
+        let mock = mock(context);
+        mock.storage.insert((*key).bytes, (*value).bytes);
+        mock.storage_status
+    }
+
+    unsafe extern "C" fn get_bytes32(
+        _: *mut EvmcHostContext,
+        _: *const EvmcAddress,
+    ) -> EvmcBytes32 {
+        Default::default()
+    }
+
+    unsafe extern "C" fn get_code_size(_: *mut EvmcHostContext, _: *const EvmcAddress) -> usize {
+        0
+    }
+
+    unsafe extern "C" fn copy_code(
+        _: *mut EvmcHostContext,
+        _: *const EvmcAddress,
+        _: usize,
+        _: *mut u8,
+        _: usize,
+    ) -> usize {
+        0
+    }
+
+    unsafe extern "C" fn selfdestruct(
+        _: *mut EvmcHostContext,
+        _: *const EvmcAddress,
+        _: *const EvmcAddress,
+    ) -> bool {
+        false
+    }
+
+    // Every call reverts without using any gas
+    unsafe extern "C" fn call(
+        context: *mut EvmcHostContext,
+        message: *const EvmcMessage,
+    ) -> EvmcResult {
+        let message = &*message;
+        mock(context)
+            .calls
+            .push((message.recipient.into_address(), message.depth));
+        EvmcResult {
+            gas_left: message.gas,
+            ..EvmcResult::failure(EvmcStatusCode::Revert)
+        }
+    }
+
+    unsafe extern "C" fn get_tx_context(_: *mut EvmcHostContext) -> EvmcTxContext {
+        EvmcTxContext {
+            tx_gas_price: Default::default(),
+            tx_origin: Default::default(),
+            block_coinbase: Default::default(),
+            block_number: 1,
+            block_timestamp: 100,
+            block_gas_limit: 30_000_000,
+            block_prev_randao: Default::default(),
+            chain_id: EvmcBytes32::from(Word::from(1)),
+            block_base_fee: Default::default(),
+            blob_base_fee: Default::default(),
+            blob_hashes: ptr::null(),
+            blob_hashes_count: 0,
+            initcodes: ptr::null(),
+            initcodes_count: 0,
+        }
+    }
+
+    unsafe extern "C" fn get_block_hash(_: *mut EvmcHostContext, _: i64) -> EvmcBytes32 {
+        Default::default()
+    }
+
+    unsafe extern "C" fn emit_log(
+        _: *mut EvmcHostContext,
+        _: *const EvmcAddress,
+        _: *const u8,
+        _: usize,
+        _: *const EvmcBytes32,
+        _: usize,
+    ) {
+    }
+
+    unsafe extern "C" fn access_account(_: *mut EvmcHostContext, _: *const EvmcAddress) -> c_int {
+        0
+    }
+
+    unsafe extern "C" fn access_storage(
+        _: *mut EvmcHostContext,
+        _: *const EvmcAddress,
+        _: *const EvmcBytes32,
+    ) -> c_int {
+        0
+    }
+
+    unsafe extern "C" fn get_transient_storage(
+        context: *mut EvmcHostContext,
+        _: *const EvmcAddress,
+        key: *const EvmcBytes32,
+    ) -> EvmcBytes32 {
+        let bytes = mock(context)
+            .transient_storage
+            .get(&(*key).bytes)
+            .copied()
+            .unwrap_or_default();
+        EvmcBytes32 { bytes }
+    }
+
+    unsafe extern "C" fn set_transient_storage(
+        context: *mut EvmcHostContext,
+        _: *const EvmcAddress,
+        key: *const EvmcBytes32,
+        value: *const EvmcBytes32,
+    ) {
+        mock(context)
+            .transient_storage
+            .insert((*key).bytes, (*value).bytes);
+    }
+
+    const HOST_INTERFACE: EvmcHostInterface = EvmcHostInterface {
+        account_exists,
+        get_storage,
+        set_storage,
+        get_balance: get_bytes32,
+        get_code_size,
+        get_code_hash: get_bytes32,
+        copy_code,
+        selfdestruct,
+        call,
+        get_tx_context,
+        get_block_hash,
+        emit_log,
+        access_account,
+        access_storage,
+        get_transient_storage,
+        set_transient_storage,
+    };
+
+    #[test]
+    fn test_execute() {
+        let mut host = MockHost::default();
+        host.storage
+            .insert(Default::default(), EvmcBytes32::from(Word::from(5)).bytes);
+        let message = EvmcMessage {
+            kind: 0,
+            flags: 0,
+            depth: 0,
+            gas: 100_000,
+            recipient: Address::repeat_byte(0x11).into(),
+            sender: Address::repeat_byte(0x22).into(),
+            input_data: ptr::null(),
+            input_size: 0,
+            value: Default::default(),
+            create2_salt: Default::default(),
+            code_address: Address::repeat_byte(0x11).into(),
+            code: ptr::null(),
+            code_size: 0,
+        };
+        // SSTORE(0, SLOAD(0) + 1), then return a single byte 0x2a
+        let code = hex::decode("600054600101600055602a60005360016000f3").unwrap();
+
+        unsafe {
+            let vm = evmc_create_evm_evmc();
+            assert_eq!((*vm).abi_version, EVMC_ABI_VERSION);
+            let execute = (*vm).execute.unwrap();
+            let context = &mut host as *mut MockHost as *mut EvmcHostContext;
+
+            let result = execute(
+                vm,
+                &HOST_INTERFACE,
+                context,
+                12,
+                &message,
+                code.as_ptr(),
+                code.len(),
+            );
+            assert_eq!(result.status_code, EvmcStatusCode::Success);
+            assert!(result.gas_left > 0 && result.gas_left < message.gas);
+            assert_eq!(bytes(result.output_data, result.output_size), &[0x2a]);
+            (result.release.unwrap())(&result);
+
+            let result = execute(
+                vm,
+                &HOST_INTERFACE,
+                context,
+                99,
+                &message,
+                code.as_ptr(),
+                code.len(),
+            );
+            assert_eq!(result.status_code, EvmcStatusCode::Rejected);
+            assert!(result.release.is_none());
+
+            ((*vm).destroy.unwrap())(vm);
+        }
+        assert_eq!(
+            host.storage[&[0; 32]],
+            EvmcBytes32::from(Word::from(6)).bytes
+        );
+    }
+
+    #[test]
+    fn test_storage_status() {
+        let message = EvmcMessage {
+            kind: EVMC_CALL,
+            flags: 0,
+            depth: 0,
+            gas: 100_000,
+            recipient: Address::repeat_byte(0x11).into(),
+            sender: Address::repeat_byte(0x22).into(),
+            input_data: ptr::null(),
+            input_size: 0,
+            value: Default::default(),
+            create2_salt: Default::default(),
+            code_address: Address::repeat_byte(0x11).into(),
+            code: ptr::null(),
+            code_size: 0,
+        };
+        // SSTORE(0, 1) to a cold slot
+        let code = hex::decode("60015f55").unwrap();
+
+        // The slot's history is known only to the host, so the status it returns prices the
+        // write
+        for (storage_status, gas_used, gas_refund) in [
+            (EVMC_STORAGE_ASSIGNED, 3 + 2 + 2100 + 100, 0),
+            (EVMC_STORAGE_ADDED, 3 + 2 + 2100 + 20000, 0),
+            (EVMC_STORAGE_DELETED_ADDED, 3 + 2 + 2100 + 100, -4800),
+            (EVMC_STORAGE_MODIFIED_RESTORED, 3 + 2 + 2100 + 100, 2800),
+        ] {
+            let mut host = MockHost {
+                storage_status,
+                ..Default::default()
+            };
+            unsafe {
+                let vm = evmc_create_evm_evmc();
+                let execute = (*vm).execute.unwrap();
+                let context = &mut host as *mut MockHost as *mut EvmcHostContext;
+                let result = execute(
+                    vm,
+                    &HOST_INTERFACE,
+                    context,
+                    12,
+                    &message,
+                    code.as_ptr(),
+                    code.len(),
+                );
+                assert_eq!(result.status_code, EvmcStatusCode::Success);
+                assert_eq!(message.gas - result.gas_left, gas_used);
+                assert_eq!(result.gas_refund, gas_refund);
+                (result.release.unwrap())(&result);
+                ((*vm).destroy.unwrap())(vm);
+            }
+        }
+    }
+
+    #[test]
+    fn test_input_and_transient_storage() {
+        let mut host = MockHost::default();
+        host.transient_storage
+            .insert(Default::default(), EvmcBytes32::from(Word::from(5)).bytes);
+        let input = EvmcBytes32::from(Word::from(2)).bytes;
+        let message = EvmcMessage {
+            kind: EVMC_CALL,
+            flags: 0,
+            depth: 0,
+            gas: 100_000,
+            recipient: Address::repeat_byte(0x11).into(),
+            sender: Address::repeat_byte(0x22).into(),
+            input_data: input.as_ptr(),
+            input_size: input.len(),
+            value: Default::default(),
+            create2_salt: Default::default(),
+            code_address: Address::repeat_byte(0x11).into(),
+            code: ptr::null(),
+            code_size: 0,
+        };
+        // TSTORE(0, CALLDATALOAD(0) + TLOAD(0)), then return TLOAD(0) + TIMESTAMP
+        let code = hex::decode("5f355f5c015f5d5f5c42015f5260205ff3").unwrap();
+
+        unsafe {
+            let vm = evmc_create_evm_evmc();
+            let execute = (*vm).execute.unwrap();
+            let context = &mut host as *mut MockHost as *mut EvmcHostContext;
+            let result = execute(
+                vm,
+                &HOST_INTERFACE,
+                context,
+                12,
+                &message,
+                code.as_ptr(),
+                code.len(),
+            );
+            assert_eq!(result.status_code, EvmcStatusCode::Success);
+            assert_eq!(
+                bytes(result.output_data, result.output_size),
+                &EvmcBytes32::from(Word::from(107)).bytes
+            );
+            (result.release.unwrap())(&result);
+            ((*vm).destroy.unwrap())(vm);
+        }
+        assert_eq!(
+            host.transient_storage[&[0; 32]],
+            EvmcBytes32::from(Word::from(7)).bytes
+        );
+    }
+
+    #[test]
+    fn test_call() {
+        let mut host = MockHost::default();
+        let message = EvmcMessage {
+            kind: EVMC_CALL,
+            flags: 0,
+            depth: 3,
+            gas: 100_000,
+            recipient: Address::repeat_byte(0x11).into(),
+            sender: Address::repeat_byte(0x22).into(),
+            input_data: ptr::null(),
+            input_size: 0,
+            value: Default::default(),
+            create2_salt: Default::default(),
+            code_address: Address::repeat_byte(0x11).into(),
+            code: ptr::null(),
+            code_size: 0,
+        };
+        // CALL 0x3333.. with 0xffff gas, then return its status as a single byte
+        let code = hex::decode(format!(
+            "600060006000600060007333{}61fffff160005360016000f3",
+            "33".repeat(19)
+        ))
+        .unwrap();
+
+        unsafe {
+            let vm = evmc_create_evm_evmc();
+            let execute = (*vm).execute.unwrap();
+            let context = &mut host as *mut MockHost as *mut EvmcHostContext;
+            let result = execute(
+                vm,
+                &HOST_INTERFACE,
+                context,
+                12,
+                &message,
+                code.as_ptr(),
+                code.len(),
+            );
+            assert_eq!(result.status_code, EvmcStatusCode::Success);
+            assert_eq!(bytes(result.output_data, result.output_size), &[0]);
+            // the pushes, a cold CALL returning all of its gas, MSTORE8 and RETURN
+            assert_eq!(
+                result.gas_left,
+                message.gas - (7 * 3 + 2600 + 3 + 3 + 3 + 3 + 3)
+            );
+            (result.release.unwrap())(&result);
+            ((*vm).destroy.unwrap())(vm);
+        }
+        assert_eq!(host.calls, vec![(Address::repeat_byte(0x33), 4)]);
+    }
+}
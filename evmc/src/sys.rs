@@ -0,0 +1,248 @@
+//! Types of the EVMC C interface, mirroring `evmc.h` at ABI version 12.
+
+use std::ffi::{c_char, c_int};
+
+pub const EVMC_ABI_VERSION: c_int = 12;
+
+// `evmc_flags`
+pub const EVMC_STATIC: u32 = 1;
+
+// `evmc_call_kind`
+pub const EVMC_CALL: c_int = 0;
+pub const EVMC_DELEGATECALL: c_int = 1;
+pub const EVMC_CALLCODE: c_int = 2;
+pub const EVMC_CREATE: c_int = 3;
+pub const EVMC_CREATE2: c_int = 4;
+pub const EVMC_EOFCREATE: c_int = 5;
+
+// `evmc_capabilities`
+pub const EVMC_CAPABILITY_EVM1: u32 = 1;
+
+// `evmc_access_status`
+pub const EVMC_ACCESS_WARM: c_int = 1;
+
+// `evmc_storage_status`
+pub const EVMC_STORAGE_ASSIGNED: c_int = 0;
+pub const EVMC_STORAGE_ADDED: c_int = 1;
+pub const EVMC_STORAGE_DELETED: c_int = 2;
+pub const EVMC_STORAGE_MODIFIED: c_int = 3;
+pub const EVMC_STORAGE_DELETED_ADDED: c_int = 4;
+pub const EVMC_STORAGE_MODIFIED_DELETED: c_int = 5;
+pub const EVMC_STORAGE_DELETED_RESTORED: c_int = 6;
+pub const EVMC_STORAGE_ADDED_DELETED: c_int = 7;
+pub const EVMC_STORAGE_MODIFIED_RESTORED: c_int = 8;
+
+// `evmc_set_option_result`
+pub const EVMC_SET_OPTION_INVALID_NAME: c_int = 1;
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EvmcBytes32 {
+    pub bytes: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EvmcAddress {
+    pub bytes: [u8; 20],
+}
+
+/// `evmc_status_code`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvmcStatusCode {
+    Success = 0,
+    Failure = 1,
+    Revert = 2,
+    OutOfGas = 3,
+    InvalidInstruction = 4,
+    UndefinedInstruction = 5,
+    StackOverflow = 6,
+    StackUnderflow = 7,
+    BadJumpDestination = 8,
+    InvalidMemoryAccess = 9,
+    CallDepthExceeded = 10,
+    StaticModeViolation = 11,
+    InternalError = -1,
+    Rejected = -2,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct EvmcMessage {
+    /// `evmc_call_kind`
+    pub kind: c_int,
+    pub flags: u32,
+    pub depth: i32,
+    pub gas: i64,
+    pub recipient: EvmcAddress,
+    pub sender: EvmcAddress,
+    pub input_data: *const u8,
+    pub input_size: usize,
+    pub value: EvmcBytes32,
+    pub create2_salt: EvmcBytes32,
+    pub code_address: EvmcAddress,
+    pub code: *const u8,
+    pub code_size: usize,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct EvmcTxInitcode {
+    pub hash: EvmcBytes32,
+    pub code: *const u8,
+    pub code_size: usize,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct EvmcTxContext {
+    pub tx_gas_price: EvmcBytes32,
+    pub tx_origin: EvmcAddress,
+    pub block_coinbase: EvmcAddress,
+    pub block_number: i64,
+    pub block_timestamp: i64,
+    pub block_gas_limit: i64,
+    pub block_prev_randao: EvmcBytes32,
+    pub chain_id: EvmcBytes32,
+    pub block_base_fee: EvmcBytes32,
+    pub blob_base_fee: EvmcBytes32,
+    pub blob_hashes: *const EvmcBytes32,
+    pub blob_hashes_count: usize,
+    pub initcodes: *const EvmcTxInitcode,
+    pub initcodes_count: usize,
+}
+
+pub type EvmcReleaseResultFn = unsafe extern "C" fn(result: *const EvmcResult);
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct EvmcResult {
+    pub status_code: EvmcStatusCode,
+    pub gas_left: i64,
+    pub gas_refund: i64,
+    pub output_data: *const u8,
+    pub output_size: usize,
+    pub release: Option<EvmcReleaseResultFn>,
+    pub create_address: EvmcAddress,
+    pub padding: [u8; 4],
+}
+
+/// Opaque context of the host, passed back to each of its callbacks.
+#[repr(C)]
+pub struct EvmcHostContext {
+    _private: [u8; 0],
+}
+
+pub type EvmcAccountExistsFn =
+    unsafe extern "C" fn(context: *mut EvmcHostContext, address: *const EvmcAddress) -> bool;
+pub type EvmcGetStorageFn = unsafe extern "C" fn(
+    context: *mut EvmcHostContext,
+    address: *const EvmcAddress,
+    key: *const EvmcBytes32,
+) -> EvmcBytes32;
+/// Returns an `evmc_storage_status`.
+pub type EvmcSetStorageFn = unsafe extern "C" fn(
+    context: *mut EvmcHostContext,
+    address: *const EvmcAddress,
+    key: *const EvmcBytes32,
+    value: *const EvmcBytes32,
+) -> c_int;
+pub type EvmcGetBalanceFn =
+    unsafe extern "C" fn(context: *mut EvmcHostContext, address: *const EvmcAddress) -> EvmcBytes32;
+pub type EvmcGetCodeSizeFn =
+    unsafe extern "C" fn(context: *mut EvmcHostContext, address: *const EvmcAddress) -> usize;
+pub type EvmcGetCodeHashFn =
+    unsafe extern "C" fn(context: *mut EvmcHostContext, address: *const EvmcAddress) -> EvmcBytes32;
+pub type EvmcCopyCodeFn = unsafe extern "C" fn(
+    context: *mut EvmcHostContext,
+    address: *const EvmcAddress,
+    code_offset: usize,
+    buffer_data: *mut u8,
+    buffer_size: usize,
+) -> usize;
+pub type EvmcSelfDestructFn = unsafe extern "C" fn(
+    context: *mut EvmcHostContext,
+    address: *const EvmcAddress,
+    beneficiary: *const EvmcAddress,
+) -> bool;
+pub type EvmcCallFn =
+    unsafe extern "C" fn(context: *mut EvmcHostContext, message: *const EvmcMessage) -> EvmcResult;
+pub type EvmcGetTxContextFn = unsafe extern "C" fn(context: *mut EvmcHostContext) -> EvmcTxContext;
+pub type EvmcGetBlockHashFn =
+    unsafe extern "C" fn(context: *mut EvmcHostContext, number: i64) -> EvmcBytes32;
+pub type EvmcEmitLogFn = unsafe extern "C" fn(
+    context: *mut EvmcHostContext,
+    address: *const EvmcAddress,
+    data: *const u8,
+    data_size: usize,
+    topics: *const EvmcBytes32,
+    topics_count: usize,
+);
+/// Returns an `evmc_access_status`.
+pub type EvmcAccessAccountFn =
+    unsafe extern "C" fn(context: *mut EvmcHostContext, address: *const EvmcAddress) -> c_int;
+/// Returns an `evmc_access_status`.
+pub type EvmcAccessStorageFn = unsafe extern "C" fn(
+    context: *mut EvmcHostContext,
+    address: *const EvmcAddress,
+    key: *const EvmcBytes32,
+) -> c_int;
+pub type EvmcGetTransientStorageFn = unsafe extern "C" fn(
+    context: *mut EvmcHostContext,
+    address: *const EvmcAddress,
+    key: *const EvmcBytes32,
+) -> EvmcBytes32;
+pub type EvmcSetTransientStorageFn = unsafe extern "C" fn(
+    context: *mut EvmcHostContext,
+    address: *const EvmcAddress,
+    key: *const EvmcBytes32,
+    value: *const EvmcBytes32,
+);
+
+#[repr(C)]
+pub struct EvmcHostInterface {
+    pub account_exists: EvmcAccountExistsFn,
+    pub get_storage: EvmcGetStorageFn,
+    pub set_storage: EvmcSetStorageFn,
+    pub get_balance: EvmcGetBalanceFn,
+    pub get_code_size: EvmcGetCodeSizeFn,
+    pub get_code_hash: EvmcGetCodeHashFn,
+    pub copy_code: EvmcCopyCodeFn,
+    pub selfdestruct: EvmcSelfDestructFn,
+    pub call: EvmcCallFn,
+    pub get_tx_context: EvmcGetTxContextFn,
+    pub get_block_hash: EvmcGetBlockHashFn,
+    pub emit_log: EvmcEmitLogFn,
+    pub access_account: EvmcAccessAccountFn,
+    pub access_storage: EvmcAccessStorageFn,
+    pub get_transient_storage: EvmcGetTransientStorageFn,
+    pub set_transient_storage: EvmcSetTransientStorageFn,
+}
+
+pub type EvmcDestroyFn = unsafe extern "C" fn(vm: *mut EvmcVm);
+pub type EvmcExecuteFn = unsafe extern "C" fn(
+    vm: *mut EvmcVm,
+    host: *const EvmcHostInterface,
+    context: *mut EvmcHostContext,
+    // `evmc_revision`
+    revision: c_int,
+    message: *const EvmcMessage,
+    code: *const u8,
+    code_size: usize,
+) -> EvmcResult;
+pub type EvmcGetCapabilitiesFn = unsafe extern "C" fn(vm: *mut EvmcVm) -> u32;
+/// Returns an `evmc_set_option_result`.
+pub type EvmcSetOptionFn =
+    unsafe extern "C" fn(vm: *mut EvmcVm, name: *const c_char, value: *const c_char) -> c_int;
+
+#[repr(C)]
+pub struct EvmcVm {
+    pub abi_version: c_int,
+    pub name: *const c_char,
+    pub version: *const c_char,
+    pub destroy: Option<EvmcDestroyFn>,
+    pub execute: Option<EvmcExecuteFn>,
+    pub get_capabilities: Option<EvmcGetCapabilitiesFn>,
+    pub set_option: Option<EvmcSetOptionFn>,
+}
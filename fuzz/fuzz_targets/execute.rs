@@ -0,0 +1,45 @@
+#![no_main]
+
+use evm::{
+    bytecode::Bytecode,
+    context::Transaction,
+    interpreter::Account,
+    primitives::{Address, TxKind},
+    Context, Error, Interpreter,
+};
+use libfuzzer_sys::fuzz_target;
+
+const GAS_LIMIT: u64 = 1_000_000;
+const RECIPIENT: Address = Address::ZERO;
+
+// The first byte selects how much of the input is code; the remainder is calldata.
+fuzz_target!(|data: &[u8]| {
+    let Some((split, data)) = data.split_first() else {
+        return;
+    };
+    let (code, input) = data.split_at((*split as usize).min(data.len()));
+
+    let hex_code = format!("0x{}", hex::encode(code));
+    let bytecode = Bytecode::try_from(hex_code.as_str()).expect("hex encoding is valid");
+    assert_eq!(bytecode.len(), Bytecode::from(code).len());
+
+    let transaction = Transaction {
+        to: TxKind::Call(RECIPIENT),
+        gas_limit: GAS_LIMIT,
+        input: input.to_vec(),
+        ..Default::default()
+    };
+    let account = Account {
+        code: bytecode,
+        ..Default::default()
+    };
+    let mut context = Context::default();
+    context.state = [(RECIPIENT, account)].into_iter().into();
+    context.transaction = transaction;
+    // Every operation is implemented, so only the state may fail to execute against
+    match Interpreter::default().execute(context) {
+        Ok(execution) => assert!(execution.result.gas_used() <= GAS_LIMIT),
+        Err(Error::Database(_)) => {}
+        Err(err) => panic!("execution failed: {err}"),
+    }
+});
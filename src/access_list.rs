@@ -0,0 +1,135 @@
+use crate::{
+    context::Substate,
+    interpreter::Interpreter,
+    primitives::{AccessList, AccessListItem, Address, B256},
+    Context, Error,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// An access list generated for a transaction, along with the gas it saves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedAccessList {
+    pub access_list: AccessList,
+    /// Gas used by the transaction with `access_list`.
+    pub gas_used: u64,
+    /// Gas used by the transaction without any access list.
+    pub gas_used_without_access_list: u64,
+}
+
+/// Run `context.transaction` against `context.state` and return the access list which
+/// minimises its gas, in the manner of `eth_createAccessList`.
+///
+/// Any access list already on the transaction is ignored. Every account and storage slot
+/// touched during execution is considered, except those only touched by frames which failed,
+/// and entries which cost more in intrinsic gas than they save in cold access charges are
+/// left out.
+pub fn create_access_list(mut context: Context) -> Result<GeneratedAccessList, Error> {
+    context.transaction.access_list = Default::default();
+    let warm = Substate::for_transaction(&context)?;
+    let execution = Interpreter::default().transact(context.clone())?;
+    let gas_used_without_access_list = execution.result.gas_used();
+
+    let mut touched = BTreeMap::<Address, BTreeSet<B256>>::new();
+    let substate = &execution.context.substate;
+    for address in &substate.accessed_addresses {
+        touched.entry(*address).or_default();
+    }
+    for (address, key) in &substate.accessed_storage_keys {
+        touched
+            .entry(*address)
+            .or_default()
+            .insert(B256::from(*key));
+    }
+
+    let schedule = context.gas_schedule();
+    let (account_saving, slot_saving) = if context.spec().has_eip2929() {
+        (
+            schedule.cold_account_access - schedule.warm_access,
+            // NOTE: a cold `SSTORE` is charged slightly more than a cold `SLOAD`, so this
+            // slightly understates the saving for slots which are only written
+            schedule.cold_sload - schedule.warm_access,
+        )
+    } else {
+        (0, 0)
+    };
+    let access_list = touched
+        .into_iter()
+        .filter(|(address, storage_keys)| {
+            let mut saving = slot_saving * storage_keys.len() as u64;
+            if !warm.accessed_addresses.contains(address) {
+                saving += account_saving;
+            }
+            let cost = schedule.access_list_address
+                + schedule.access_list_storage * storage_keys.len() as u64;
+            saving > cost
+        })
+        .map(|(address, storage_keys)| AccessListItem {
+            address,
+            storage_keys: storage_keys.into_iter().collect(),
+        })
+        .collect::<Vec<_>>();
+    if access_list.is_empty() {
+        return Ok(GeneratedAccessList {
+            access_list: Default::default(),
+            gas_used: gas_used_without_access_list,
+            gas_used_without_access_list,
+        });
+    }
+
+    let access_list = AccessList::from(access_list);
+    context.transaction.access_list = access_list.clone();
+    let execution = Interpreter::default().transact(context)?;
+    Ok(GeneratedAccessList {
+        access_list,
+        gas_used: execution.result.gas_used(),
+        gas_used_without_access_list,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::{State, Transaction},
+        interpreter::Account,
+    };
+
+    const SENDER: Address = Address::repeat_byte(0x11);
+    const RECIPIENT: Address = Address::repeat_byte(0x22);
+    const OTHER: Address = Address::repeat_byte(0x44);
+
+    #[test]
+    fn test_create_access_list() {
+        // PUSH1 1, SLOAD, PUSH1 2, SLOAD, PUSH20 0x4444.., BALANCE
+        let code = format!("0x60015460025473{}31", "44".repeat(20));
+        let recipient = Account {
+            code: code.as_str().try_into().unwrap(),
+            ..Default::default()
+        };
+        let mut context = Context::default();
+        context.block.gas_limit = 30_000_000;
+        context.transaction = Transaction {
+            sender: SENDER,
+            to: RECIPIENT.into(),
+            gas_limit: 100_000,
+            ..Default::default()
+        };
+        context.state = State::from([(RECIPIENT, recipient)].into_iter());
+        let generated = create_access_list(context).unwrap();
+
+        // two slots of the already warm recipient are not worth an entry
+        let expected = AccessList::from(vec![AccessListItem {
+            address: OTHER,
+            storage_keys: vec![],
+        }]);
+        assert_eq!(generated.access_list, expected);
+        assert_eq!(
+            generated.gas_used_without_access_list,
+            21000 + 3 + 2100 + 3 + 2100 + 3 + 2600
+        );
+        assert_eq!(
+            generated.gas_used,
+            21000 + 2400 + 3 + 2100 + 3 + 2100 + 3 + 100
+        );
+    }
+}
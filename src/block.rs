@@ -0,0 +1,475 @@
+use crate::{
+    bytecode::Bytecode,
+    context::{State, StateChanges, Transaction},
+    database::{AccountInfo, Database},
+    interpreter::{Account, ExecutionResult, Interpreter, Word},
+    primitives::{Address, B256, U256},
+    Context, Error,
+};
+use std::{
+    collections::HashMap,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    thread,
+};
+
+/// The outcome of executing the transactions of a block.
+#[derive(Debug)]
+pub struct BlockExecution {
+    /// The state after the last transaction.
+    pub state: State,
+    /// The result of each transaction, in order.
+    pub results: Vec<ExecutionResult>,
+}
+
+/// Execute `transactions` in order against `context.state`, each in turn taking the place of
+/// `context.transaction`.
+///
+/// Fails if any transaction is invalid, as would the block containing it.
+pub fn execute_block(
+    mut context: Context,
+    transactions: Vec<Transaction>,
+) -> Result<BlockExecution, Error> {
+    let mut results = Vec::with_capacity(transactions.len());
+    for transaction in transactions {
+        context.transaction = transaction;
+        let execution = Interpreter::default().transact(context)?;
+        results.push(execution.result);
+        context = execution.context;
+    }
+    Ok(BlockExecution {
+        state: context.state,
+        results,
+    })
+}
+
+/// Execute `transactions` as `execute_block` does, running them concurrently on up to
+/// `threads` threads.
+///
+/// Transactions are executed speculatively in rounds, each against the state left by the
+/// latest executions of the transactions before it, recording the accounts and storage slots
+/// it reads. At the start of a round, those whose reads no longer match that state are
+/// executed again. The lowest such transaction has then seen the final effects of all before
+/// it, so every round fixes at least one more transaction, and the outcome is identical to
+/// executing in order. Transactions touching disjoint accounts and slots finish in a single
+/// round.
+///
+/// The coinbase is credited with each transaction's fee once it completes rather than during
+/// execution, so transactions only conflict over the coinbase if they read its balance.
+pub fn execute_block_parallel(
+    mut context: Context,
+    transactions: Vec<Transaction>,
+    threads: NonZeroUsize,
+) -> Result<BlockExecution, Error> {
+    let base = Arc::new(std::mem::take(&mut context.state));
+    let coinbase = context.block.coinbase;
+    let count = transactions.len();
+    let mut writes = (0..count).map(|_| Writes::default()).collect::<Vec<_>>();
+    let mut reads = vec![vec![]; count];
+    let mut results = (0..count).map(|_| None).collect::<Vec<_>>();
+    // Transactions below this index have seen the final effects of those before them
+    let mut committed = 0;
+    loop {
+        let memory = Arc::new(Memory::new(base.clone(), coinbase, writes));
+        let pending = (committed..count).collect::<Vec<_>>();
+        let mut invalid = parallel_map(&pending, threads, |index| {
+            (results[index].is_none() || !memory.validate(index, &reads[index])).then_some(index)
+        })
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        invalid.sort_unstable();
+        let Some(&lowest) = invalid.first() else {
+            writes = memory.into_writes();
+            break;
+        };
+        committed = lowest + 1;
+
+        let speculations = parallel_map(&invalid, threads, |index| {
+            let transaction = transactions[index].clone();
+            (index, speculate(&context, transaction, &memory, index))
+        });
+        writes = memory.into_writes();
+        for (index, speculation) in speculations {
+            writes[index] = speculation.writes;
+            reads[index] = speculation.reads;
+            results[index] = Some(speculation.result);
+        }
+    }
+
+    let mut state = Arc::into_inner(base).expect("speculative states outlive their round");
+    let mut execution_results = Vec::with_capacity(count);
+    for (writes, result) in writes.iter().zip(results) {
+        execution_results.push(result.expect("every transaction is executed")?);
+        state.apply(&writes.changes)?;
+        if let Some(coinbase_fee) = writes.coinbase_fee.filter(|fee| !fee.is_zero()) {
+            state.account_mut(coinbase)?.balance += coinbase_fee;
+        }
+    }
+    Ok(BlockExecution {
+        state,
+        results: execution_results,
+    })
+}
+
+// The changes made by the latest execution of a transaction
+#[derive(Debug, Default)]
+struct Writes {
+    changes: StateChanges,
+    // The fee owed to the coinbase, `None` if the transaction was invalid
+    coinbase_fee: Option<U256>,
+}
+
+// A value read by a transaction from outside its own writes
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Read {
+    Account(Address, Option<AccountInfo>),
+    Storage(Address, Word, Word),
+}
+
+struct Speculation {
+    writes: Writes,
+    reads: Vec<Read>,
+    result: Result<ExecutionResult, Error>,
+}
+
+// Execute `transaction` at `index` against `memory`, recording what it reads
+fn speculate(
+    context: &Context,
+    transaction: Transaction,
+    memory: &Arc<Memory>,
+    index: usize,
+) -> Speculation {
+    let reads = Arc::new(Mutex::new(vec![]));
+    let database = SpeculativeDatabase {
+        memory: memory.clone(),
+        index,
+        reads: reads.clone(),
+        code: Default::default(),
+    };
+    let mut context = context.clone();
+    context.transaction = transaction;
+    context.state = State::new(database);
+    let (writes, result) = match Interpreter::default().transact_deferring_coinbase_fee(context) {
+        Ok((execution, coinbase_fee)) => (
+            Writes {
+                changes: execution.context.state.changes(),
+                coinbase_fee: Some(coinbase_fee),
+            },
+            Ok(execution.result),
+        ),
+        Err(error) => (Writes::default(), Err(error)),
+    };
+    let reads = std::mem::take(&mut *reads.lock().unwrap_or_else(PoisonError::into_inner));
+    Speculation {
+        writes,
+        reads,
+        result,
+    }
+}
+
+// The state at the start of each transaction: `base` with the writes of the latest executions
+// of every transaction before it applied
+struct Memory {
+    base: Arc<State>,
+    coinbase: Address,
+    writes: Vec<Writes>,
+    // The indices of the transactions writing each account, slot or removing storage, ascending
+    accounts: HashMap<Address, Vec<usize>>,
+    storage: HashMap<(Address, Word), Vec<usize>>,
+    removed: HashMap<Address, Vec<usize>>,
+}
+
+impl Memory {
+    fn new(base: Arc<State>, coinbase: Address, writes: Vec<Writes>) -> Self {
+        let mut memory = Self {
+            base,
+            coinbase,
+            writes: vec![],
+            accounts: Default::default(),
+            storage: Default::default(),
+            removed: Default::default(),
+        };
+        for (index, writes) in writes.iter().enumerate() {
+            for (address, account) in &writes.changes.accounts {
+                memory.accounts.entry(*address).or_default().push(index);
+                for key in account.iter().flat_map(|account| account.storage.keys()) {
+                    memory
+                        .storage
+                        .entry((*address, *key))
+                        .or_default()
+                        .push(index);
+                }
+            }
+            for address in &writes.changes.removed {
+                memory.removed.entry(*address).or_default().push(index);
+            }
+        }
+        memory.writes = writes;
+        memory
+    }
+
+    fn into_writes(self: Arc<Self>) -> Vec<Writes> {
+        Arc::into_inner(self)
+            .expect("speculative states outlive their round")
+            .writes
+    }
+
+    // Returns the account at `address` as seen by the transaction at `index`
+    fn account(&self, index: usize, address: &Address) -> Result<Option<Arc<Account>>, Error> {
+        let (mut account, unpaid) = match latest(self.accounts.get(address), index) {
+            Some(writer) => (
+                self.writes[writer].changes.accounts[address].clone(),
+                writer,
+            ),
+            None => (self.base.get(address)?, 0),
+        };
+        if *address == self.coinbase {
+            // The fees of the writer onwards are yet to be credited
+            for coinbase_fee in self.writes[unpaid..index]
+                .iter()
+                .filter_map(|writes| writes.coinbase_fee)
+                .filter(|fee| !fee.is_zero())
+            {
+                Arc::make_mut(account.get_or_insert_with(Default::default)).balance += coinbase_fee;
+            }
+        }
+        Ok(account)
+    }
+
+    // Returns the value at `key` in the storage of `address` as seen by the transaction at
+    // `index`
+    fn storage(&self, index: usize, address: &Address, key: &Word) -> Result<Word, Error> {
+        let writer = latest(self.storage.get(&(*address, *key)), index);
+        let remover = latest(self.removed.get(address), index);
+        match (writer, remover) {
+            // Slots written by a transaction which removed the account were written after
+            (Some(writer), remover) if remover <= Some(writer) => {
+                Ok(self.writes[writer].changes.accounts[address]
+                    .as_ref()
+                    .map(|account| account.storage[key])
+                    .unwrap_or_default())
+            }
+            (_, Some(_)) => Ok(Word::ZERO),
+            _ => self.base.storage(address, key),
+        }
+    }
+
+    // Returns `true` if everything read by the transaction at `index` is unchanged
+    fn validate(&self, index: usize, reads: &[Read]) -> bool {
+        reads.iter().all(|read| match read {
+            Read::Account(address, info) => self
+                .account(index, address)
+                .is_ok_and(|account| account.as_deref().map(AccountInfo::from) == *info),
+            Read::Storage(address, key, value) => self
+                .storage(index, address, key)
+                .is_ok_and(|current| current == *value),
+        })
+    }
+}
+
+// Returns the highest of the ascending `indices` below `index`
+fn latest(indices: Option<&Vec<usize>>, index: usize) -> Option<usize> {
+    let indices = indices?;
+    let position = indices.partition_point(|writer| *writer < index);
+    position.checked_sub(1).map(|position| indices[position])
+}
+
+// The `Database` a transaction is speculatively executed against
+struct SpeculativeDatabase {
+    memory: Arc<Memory>,
+    index: usize,
+    reads: Arc<Mutex<Vec<Read>>>,
+    // The code of each account read, by hash
+    code: Mutex<HashMap<B256, Bytecode>>,
+}
+
+impl SpeculativeDatabase {
+    fn record(&self, read: Read) {
+        self.reads
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(read);
+    }
+}
+
+impl Database for SpeculativeDatabase {
+    fn basic(&self, address: &Address) -> Result<Option<AccountInfo>, Error> {
+        let account = self.memory.account(self.index, address)?;
+        let info = account.as_deref().map(AccountInfo::from);
+        if let (Some(account), Some(info)) = (account, info) {
+            self.code
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(info.code_hash, account.code.clone());
+        }
+        self.record(Read::Account(*address, info));
+        Ok(info)
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Bytecode, Error> {
+        self.code
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(code_hash)
+            .cloned()
+            .ok_or_else(|| Error::Database(format!("missing code with hash {code_hash}").into()))
+    }
+
+    fn storage(&self, address: &Address, key: &Word) -> Result<Word, Error> {
+        let value = self.memory.storage(self.index, address, key)?;
+        self.record(Read::Storage(*address, *key, value));
+        Ok(value)
+    }
+
+    fn block_hash(&self, number: u64) -> Result<B256, Error> {
+        self.memory.base.block_hash(number)
+    }
+}
+
+// Returns the result of `f` for each of `indices` along with its index, in no particular
+// order, spread across up to `threads` threads
+fn parallel_map<T: Send>(
+    indices: &[usize],
+    threads: NonZeroUsize,
+    f: impl Fn(usize) -> T + Sync,
+) -> Vec<T> {
+    let next = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        let workers = (0..threads.get().min(indices.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut outputs = vec![];
+                    while let Some(index) = indices.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        outputs.push(f(*index));
+                    }
+                    outputs
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{context::Block, processor::InvalidTransaction};
+
+    const COINBASE: Address = Address::repeat_byte(0x33);
+    const COUNTER: Address = Address::repeat_byte(0x44);
+    const COINBASE_READER: Address = Address::repeat_byte(0x55);
+    const SENDERS: [Address; 3] = [
+        Address::repeat_byte(0x11),
+        Address::repeat_byte(0x12),
+        Address::repeat_byte(0x13),
+    ];
+
+    fn build_context() -> Context {
+        let sender = || Account {
+            balance: U256::from(1_000_000_000),
+            ..Default::default()
+        };
+        // PUSH1 0, SLOAD, PUSH1 1, ADD, PUSH1 0, SSTORE
+        let counter = Account {
+            code: "0x600054600101600055".try_into().unwrap(),
+            ..Default::default()
+        };
+        // PUSH20 COINBASE, BALANCE, PUSH1 0, SSTORE
+        let coinbase_reader = Account {
+            code: format!("0x73{COINBASE:x}31600055")
+                .as_str()
+                .try_into()
+                .unwrap(),
+            ..Default::default()
+        };
+        let accounts = SENDERS
+            .into_iter()
+            .map(|address| (address, sender()))
+            .chain([(COUNTER, counter), (COINBASE_READER, coinbase_reader)]);
+        let mut context = Context::default();
+        context.block = Block {
+            coinbase: COINBASE,
+            gas_limit: 30_000_000,
+            ..Default::default()
+        };
+        context.state = State::from(accounts);
+        context
+    }
+
+    fn transaction(sender: usize, nonce: u64, recipient: Address, value: u64) -> Transaction {
+        Transaction {
+            sender: SENDERS[sender],
+            nonce,
+            to: recipient.into(),
+            value: U256::from(value),
+            gas_limit: 100_000,
+            gas_price: U256::from(10),
+            ..Default::default()
+        }
+    }
+
+    fn transactions() -> Vec<Transaction> {
+        vec![
+            transaction(0, 0, COUNTER, 0),
+            transaction(1, 0, Address::repeat_byte(0x66), 5),
+            transaction(0, 1, COUNTER, 0),
+            transaction(2, 0, COINBASE_READER, 0),
+            transaction(2, 1, COUNTER, 3),
+            transaction(1, 1, Address::repeat_byte(0x77), 7),
+        ]
+    }
+
+    #[test]
+    fn test_execute_block_parallel() {
+        let expected = execute_block(build_context(), transactions()).unwrap();
+        let state = &expected.state;
+        assert_eq!(state.storage(&COUNTER, &U256::ZERO).unwrap(), U256::from(3));
+        let coinbase_balance = state.storage(&COINBASE_READER, &U256::ZERO).unwrap();
+        assert!(!coinbase_balance.is_zero());
+
+        for threads in [1, 4] {
+            let threads = NonZeroUsize::new(threads).unwrap();
+            let execution =
+                execute_block_parallel(build_context(), transactions(), threads).unwrap();
+            assert_eq!(execution.results, expected.results);
+
+            let changes = execution.state.changes();
+            let expected_changes = expected.state.changes();
+            assert_eq!(changes.removed, expected_changes.removed);
+            assert_eq!(changes.accounts.len(), expected_changes.accounts.len());
+            for (address, expected_account) in expected_changes.accounts {
+                let account = changes.accounts[&address].as_deref().unwrap();
+                let expected_account = expected_account.as_deref().unwrap();
+                assert_eq!(account.balance, expected_account.balance);
+                assert_eq!(account.nonce, expected_account.nonce);
+                assert_eq!(account.storage, expected_account.storage);
+            }
+        }
+    }
+
+    #[test]
+    fn test_execute_block_parallel_invalid_transaction() {
+        let mut transactions = transactions();
+        transactions[2].nonce = 2;
+        let threads = NonZeroUsize::new(4).unwrap();
+        let error = execute_block_parallel(build_context(), transactions, threads).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::InvalidTransaction(InvalidTransaction::NonceMismatch {
+                expected: 1,
+                actual: 2
+            })
+        ));
+    }
+}
@@ -1,15 +1,62 @@
-use crate::{operations::Operation, parse_hex, Error};
-use std::ops::{Deref, DerefMut};
+use crate::{
+    eof::Eof,
+    operations::Operation,
+    parse_hex,
+    primitives::{Address, B256},
+    Error,
+};
+use alloy::{consensus::constants::KECCAK_EMPTY, primitives::keccak256};
+use std::ops::Deref;
 
+// EIP-7702: prefix of the code installed on an account delegating to another
+const DELEGATION_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
+/// Code of an account, as both its raw bytes and the operations parsed from them.
+///
+/// Code beginning with the EOF magic is also decoded as an EOF container, if valid.
 #[derive(Debug, Default, Clone)]
-pub struct Bytecode(Vec<Operation>);
+pub struct Bytecode {
+    operations: Vec<Operation>,
+    // byte offset of each of `operations` within `bytes`
+    offsets: Vec<usize>,
+    bytes: Vec<u8>,
+    eof: Option<Eof>,
+}
 
 impl TryFrom<&str> for Bytecode {
     type Error = Error;
 
     fn try_from(value: &str) -> Result<Self, Error> {
         let input = parse_hex(value)?;
-        parse(&input).map(Self)
+        Ok(Self::from(input.as_slice()))
+    }
+}
+
+impl From<&[u8]> for Bytecode {
+    fn from(value: &[u8]) -> Self {
+        let (operations, offsets) = parse(value);
+        Self {
+            operations,
+            offsets,
+            bytes: value.to_vec(),
+            eof: Eof::is_eof(value)
+                .then(|| Eof::decode(value).ok())
+                .flatten(),
+        }
+    }
+}
+
+impl From<Eof> for Bytecode {
+    /// Returns the code of an already decoded container, e.g. initcode, which is not decoded
+    /// as deployed code is.
+    fn from(eof: Eof) -> Self {
+        let (operations, offsets) = parse(eof.bytes());
+        Self {
+            operations,
+            offsets,
+            bytes: eof.bytes().to_vec(),
+            eof: Some(eof),
+        }
     }
 }
 
@@ -17,183 +64,237 @@ impl Deref for Bytecode {
     type Target = Vec<Operation>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.operations
     }
 }
 
-impl DerefMut for Bytecode {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+impl Bytecode {
+    /// Returns the EIP-7702 delegation designator installing a delegation to `address`.
+    pub fn delegation_to(address: Address) -> Self {
+        Self::from(
+            [DELEGATION_PREFIX.as_slice(), address.as_slice()]
+                .concat()
+                .as_slice(),
+        )
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
     }
-}
 
-fn parse_push<'a>(opcode: u8, input: &mut impl Iterator<Item = &'a u8>) -> Operation {
-    let immediate_size = (opcode - Operation::PUSH1_VALUE + 1) as usize;
-    let mut immediate = input.take(immediate_size).cloned().collect::<Vec<_>>();
-    immediate.resize(immediate_size, 0);
+    /// Returns the keccak256 hash of the code, as recorded in its account.
+    pub fn hash(&self) -> B256 {
+        if self.bytes.is_empty() {
+            KECCAK_EMPTY
+        } else {
+            keccak256(&self.bytes)
+        }
+    }
 
-    use Operation::*;
-    match immediate_size {
-        1 => Push1(immediate[0]),
-        2 => Push2(immediate.try_into().unwrap()),
-        3 => Push3(immediate.try_into().unwrap()),
-        4 => Push4(immediate.try_into().unwrap()),
-        5 => Push5(immediate.try_into().unwrap()),
-        6 => Push6(immediate.try_into().unwrap()),
-        7 => Push7(immediate.try_into().unwrap()),
-        8 => Push8(immediate.try_into().unwrap()),
-        9 => Push9(immediate.try_into().unwrap()),
-        10 => Push10(immediate.try_into().unwrap()),
-        11 => Push11(immediate.try_into().unwrap()),
-        12 => Push12(immediate.try_into().unwrap()),
-        13 => Push13(immediate.try_into().unwrap()),
-        14 => Push14(immediate.try_into().unwrap()),
-        15 => Push15(immediate.try_into().unwrap()),
-        16 => Push16(immediate.try_into().unwrap()),
-        17 => Push17(immediate.try_into().unwrap()),
-        18 => Push18(immediate.try_into().unwrap()),
-        19 => Push19(immediate.try_into().unwrap()),
-        21 => Push21(immediate.try_into().unwrap()),
-        22 => Push22(immediate.try_into().unwrap()),
-        23 => Push23(immediate.try_into().unwrap()),
-        24 => Push24(immediate.try_into().unwrap()),
-        25 => Push25(immediate.try_into().unwrap()),
-        27 => Push27(immediate.try_into().unwrap()),
-        28 => Push28(immediate.try_into().unwrap()),
-        29 => Push29(immediate.try_into().unwrap()),
-        30 => Push30(immediate.try_into().unwrap()),
-        31 => Push31(immediate.try_into().unwrap()),
-        32 => Push32(immediate.try_into().unwrap()),
-        _ => unreachable!("push size not supported"),
+    /// Returns the index of the `JUMPDEST` at byte `offset`, if there is one outside of the
+    /// immediate of another operation.
+    pub fn jump_destination(&self, offset: usize) -> Option<usize> {
+        self.offsets
+            .binary_search(&offset)
+            .ok()
+            .filter(|index| self.operations[*index] == Operation::JumpDest)
+    }
+
+    pub fn eof(&self) -> Option<&Eof> {
+        self.eof.as_ref()
+    }
+
+    /// Returns the address this code delegates to, if it is an EIP-7702 delegation designator.
+    pub fn delegation(&self) -> Option<Address> {
+        self.bytes
+            .strip_prefix(DELEGATION_PREFIX.as_slice())
+            .filter(|address| address.len() == Address::len_bytes())
+            .map(Address::from_slice)
+    }
+}
+
+// Immediates running past the end of the code are padded with zeros.
+fn parse_immediate<'a, const N: usize>(input: &mut impl Iterator<Item = &'a u8>) -> [u8; N] {
+    let mut immediate = [0u8; N];
+    for (byte, value) in immediate.iter_mut().zip(input) {
+        *byte = *value;
     }
+    immediate
 }
 
-fn parse(input: &[u8]) -> Result<Vec<Operation>, Error> {
+fn parse(input: &[u8]) -> (Vec<Operation>, Vec<usize>) {
     let mut operations = vec![];
+    let mut offsets = vec![];
+    let mut offset = 0;
     let mut input = input.iter();
     while let Some(byte) = input.next() {
-        use Operation::*;
-        let operation = match byte {
-            0x00 => Stop,
-            0x01 => Add,
-            0x02 => Add,
-            0x03 => Sub,
-            0x04 => Div,
-            0x05 => Sdiv,
-            0x06 => Mod,
-            0x07 => SMod,
-            0x08 => Addmod,
-            0x09 => Mulmod,
-            0x0a => Exp,
-            0x0b => SignExtend,
-            0x10 => Lt,
-            0x11 => Gt,
-            0x12 => Slt,
-            0x13 => Sgt,
-            0x14 => Eq,
-            0x15 => IsZero,
-            0x16 => And,
-            0x17 => Or,
-            0x18 => Xor,
-            0x19 => Not,
-            0x1a => Byte,
-            0x1b => Shl,
-            0x1c => Shr,
-            0x1d => Sar,
-            0x20 => Keccack256,
-            0x30 => Address,
-            0x31 => Balance,
-            0x32 => Origin,
-            0x33 => Caller,
-            0x34 => CallValue,
-            0x35 => CallDataLoad,
-            0x36 => CallDataSize,
-            0x37 => CallDataCopy,
-            0x38 => CodeSize,
-            0x39 => CodeCopy,
-            0x3a => GasPrice,
-            0x3b => ExtCodeSize,
-            0x3c => ExtCodeCopy,
-            0x3d => ReturnDataSize,
-            0x3e => ReturnDataCopy,
-            0x3f => ExtCodeHash,
-            0x40 => Blockhash,
-            0x41 => Coinbase,
-            0x42 => Timestamp,
-            0x43 => Number,
-            0x44 => PrevRandao,
-            0x45 => GasLimit,
-            0x46 => ChainId,
-            0x47 => SelfBalance,
-            0x48 => BaseFee,
-            0x50 => Pop,
-            0x51 => Mload,
-            0x52 => Mstore,
-            0x53 => MStore8,
-            0x54 => Sload,
-            0x55 => SStore,
-            0x56 => Jump,
-            0x57 => JumpI,
-            0x58 => Pc,
-            0x59 => MSize,
-            0x5a => Gas,
-            0x5b => JumpDest,
-            0x5f => Push0,
-            &push if push >= Operation::PUSH1_VALUE && push <= Operation::PUSH32_VALUE => {
-                parse_push(push, &mut input)
-            }
-            0x80 => Dup1,
-            0x81 => Dup2,
-            0x82 => Dup3,
-            0x83 => Dup4,
-            0x84 => Dup5,
-            0x85 => Dup6,
-            0x86 => Dup7,
-            0x87 => Dup8,
-            0x88 => Dup9,
-            0x89 => Dup10,
-            0x8a => Dup11,
-            0x8b => Dup12,
-            0x8c => Dup13,
-            0x8d => Dup14,
-            0x8e => Dup15,
-            0x8f => Dup16,
-            0x90 => Swap1,
-            0x91 => Swap2,
-            0x92 => Swap3,
-            0x93 => Swap4,
-            0x94 => Swap5,
-            0x95 => Swap6,
-            0x96 => Swap7,
-            0x97 => Swap8,
-            0x98 => Swap9,
-            0x99 => Swap10,
-            0x9a => Swap11,
-            0x9b => Swap12,
-            0x9c => Swap13,
-            0x9d => Swap14,
-            0x9e => Swap15,
-            0x9f => Swap16,
-            0xa0 => Log0,
-            0xa1 => Log1,
-            0xa2 => Log2,
-            0xa3 => Log3,
-            0xa4 => Log4,
-            0xf0 => Create,
-            0xf1 => Call,
-            0xf2 => CallCode,
-            0xf3 => Return,
-            0xf4 => DelegateCall,
-            0xf5 => Create2,
-            0xfa => StaticCall,
-            0xfd => Revert,
-            0xfe => Invalid,
-            0xff => SelfDestruct,
-            other => return Err(Error::InvalidOpcode(*other)),
-        };
+        let operation = parse_operation(*byte, &mut input);
+        offsets.push(offset);
+        offset += operation.size();
         operations.push(operation);
     }
-    Ok(operations)
+    (operations, offsets)
+}
+
+/// Parses the legacy operation with `opcode`, consuming any immediate from `input`.
+pub(crate) fn parse_operation<'a>(
+    opcode: u8,
+    input: &mut impl Iterator<Item = &'a u8>,
+) -> Operation {
+    use Operation::*;
+
+    match opcode {
+        0x00 => Stop,
+        0x01 => Add,
+        0x02 => Mul,
+        0x03 => Sub,
+        0x04 => Div,
+        0x05 => Sdiv,
+        0x06 => Mod,
+        0x07 => SMod,
+        0x08 => Addmod,
+        0x09 => Mulmod,
+        0x0a => Exp,
+        0x0b => SignExtend,
+        0x10 => Lt,
+        0x11 => Gt,
+        0x12 => Slt,
+        0x13 => Sgt,
+        0x14 => Eq,
+        0x15 => IsZero,
+        0x16 => And,
+        0x17 => Or,
+        0x18 => Xor,
+        0x19 => Not,
+        0x1a => Byte,
+        0x1b => Shl,
+        0x1c => Shr,
+        0x1d => Sar,
+        0x20 => Keccack256,
+        0x30 => Address,
+        0x31 => Balance,
+        0x32 => Origin,
+        0x33 => Caller,
+        0x34 => CallValue,
+        0x35 => CallDataLoad,
+        0x36 => CallDataSize,
+        0x37 => CallDataCopy,
+        0x38 => CodeSize,
+        0x39 => CodeCopy,
+        0x3a => GasPrice,
+        0x3b => ExtCodeSize,
+        0x3c => ExtCodeCopy,
+        0x3d => ReturnDataSize,
+        0x3e => ReturnDataCopy,
+        0x3f => ExtCodeHash,
+        0x40 => Blockhash,
+        0x41 => Coinbase,
+        0x42 => Timestamp,
+        0x43 => Number,
+        0x44 => PrevRandao,
+        0x45 => GasLimit,
+        0x46 => ChainId,
+        0x47 => SelfBalance,
+        0x48 => BaseFee,
+        0x49 => BlobHash,
+        0x4a => BlobBaseFee,
+        0x50 => Pop,
+        0x51 => Mload,
+        0x52 => Mstore,
+        0x53 => MStore8,
+        0x54 => Sload,
+        0x55 => SStore,
+        0x56 => Jump,
+        0x57 => JumpI,
+        0x58 => Pc,
+        0x59 => MSize,
+        0x5a => Gas,
+        0x5b => JumpDest,
+        0x5c => Tload,
+        0x5d => TStore,
+        0x5e => MCopy,
+        0x5f => Push0,
+        0x60 => Push1(parse_immediate::<1>(input)[0]),
+        0x61 => Push2(parse_immediate(input)),
+        0x62 => Push3(parse_immediate(input)),
+        0x63 => Push4(parse_immediate(input)),
+        0x64 => Push5(parse_immediate(input)),
+        0x65 => Push6(parse_immediate(input)),
+        0x66 => Push7(parse_immediate(input)),
+        0x67 => Push8(parse_immediate(input)),
+        0x68 => Push9(parse_immediate(input)),
+        0x69 => Push10(parse_immediate(input)),
+        0x6a => Push11(parse_immediate(input)),
+        0x6b => Push12(parse_immediate(input)),
+        0x6c => Push13(parse_immediate(input)),
+        0x6d => Push14(parse_immediate(input)),
+        0x6e => Push15(parse_immediate(input)),
+        0x6f => Push16(parse_immediate(input)),
+        0x70 => Push17(parse_immediate(input)),
+        0x71 => Push18(parse_immediate(input)),
+        0x72 => Push19(parse_immediate(input)),
+        0x73 => Push20(parse_immediate(input)),
+        0x74 => Push21(parse_immediate(input)),
+        0x75 => Push22(parse_immediate(input)),
+        0x76 => Push23(parse_immediate(input)),
+        0x77 => Push24(parse_immediate(input)),
+        0x78 => Push25(parse_immediate(input)),
+        0x79 => Push26(parse_immediate(input)),
+        0x7a => Push27(parse_immediate(input)),
+        0x7b => Push28(parse_immediate(input)),
+        0x7c => Push29(parse_immediate(input)),
+        0x7d => Push30(parse_immediate(input)),
+        0x7e => Push31(parse_immediate(input)),
+        0x7f => Push32(parse_immediate(input)),
+        0x80 => Dup1,
+        0x81 => Dup2,
+        0x82 => Dup3,
+        0x83 => Dup4,
+        0x84 => Dup5,
+        0x85 => Dup6,
+        0x86 => Dup7,
+        0x87 => Dup8,
+        0x88 => Dup9,
+        0x89 => Dup10,
+        0x8a => Dup11,
+        0x8b => Dup12,
+        0x8c => Dup13,
+        0x8d => Dup14,
+        0x8e => Dup15,
+        0x8f => Dup16,
+        0x90 => Swap1,
+        0x91 => Swap2,
+        0x92 => Swap3,
+        0x93 => Swap4,
+        0x94 => Swap5,
+        0x95 => Swap6,
+        0x96 => Swap7,
+        0x97 => Swap8,
+        0x98 => Swap9,
+        0x99 => Swap10,
+        0x9a => Swap11,
+        0x9b => Swap12,
+        0x9c => Swap13,
+        0x9d => Swap14,
+        0x9e => Swap15,
+        0x9f => Swap16,
+        0xa0 => Log0,
+        0xa1 => Log1,
+        0xa2 => Log2,
+        0xa3 => Log3,
+        0xa4 => Log4,
+        0xf0 => Create,
+        0xf1 => Call,
+        0xf2 => CallCode,
+        0xf3 => Return,
+        0xf4 => DelegateCall,
+        0xf5 => Create2,
+        0xfa => StaticCall,
+        0xfd => Revert,
+        0xfe => Invalid,
+        0xff => SelfDestruct,
+        other => Unknown(other),
+    }
 }
 
 #[cfg(test)]
@@ -206,4 +307,50 @@ mod test {
         let bytecode = Bytecode::try_from(input).unwrap();
         dbg!(bytecode);
     }
+
+    #[test]
+    fn test_truncated_push_and_unknown_opcodes() {
+        let bytecode = Bytecode::from([0x0c, 0xfe, 0x62, 0xaa].as_slice());
+        assert_eq!(
+            bytecode.as_slice(),
+            &[
+                Operation::Unknown(0x0c),
+                Operation::Invalid,
+                Operation::Push3([0xaa, 0, 0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_jump_destinations() {
+        // JUMPDEST, PUSH2 0x5b5b, JUMPDEST
+        let bytecode = Bytecode::try_from("0x5b615b5b5b").unwrap();
+        assert_eq!(bytecode.jump_destination(0), Some(0));
+        assert_eq!(bytecode.jump_destination(1), None);
+        assert_eq!(bytecode.jump_destination(2), None);
+        assert_eq!(bytecode.jump_destination(4), Some(2));
+        assert_eq!(bytecode.jump_destination(5), None);
+    }
+
+    #[test]
+    fn test_eof() {
+        let bytecode = Bytecode::try_from("0xef00010100040200010001ff00000000800000fe").unwrap();
+        let eof = bytecode.eof().unwrap();
+        assert_eq!(eof.code_sections[0].operations, vec![Operation::Invalid]);
+
+        let bytecode = Bytecode::try_from("0xef00010100040200010001ff00000000800000").unwrap();
+        assert!(bytecode.eof().is_none());
+        assert!(Bytecode::try_from("0x00").unwrap().eof().is_none());
+    }
+
+    #[test]
+    fn test_delegation() {
+        let address = Address::repeat_byte(0x42);
+        let bytecode = Bytecode::delegation_to(address);
+        assert_eq!(bytecode.bytes().len(), 23);
+        assert_eq!(bytecode.delegation(), Some(address));
+
+        let bytecode = Bytecode::from([0xef, 0x01, 0x00, 0x42].as_slice());
+        assert_eq!(bytecode.delegation(), None);
+    }
 }
@@ -1,63 +1,1599 @@
 use crate::{
-    interpreter::Account,
-    primitives::{Address, U256},
+    database::{Database, InMemoryDatabase},
+    gas_schedule::GasSchedule,
+    interpreter::{Account, Word},
+    primitives::{AccessList, Address, Bytes, Signature, SignedAuthorization, TxKind, B256, U256},
+    processor::InvalidTransaction,
+    proof::{AccountProof, StorageProof, EMPTY_ACCOUNT},
+    spec::SpecId,
+    trie::Trie,
+    Error,
+};
+use alloy::{
+    consensus::{
+        Account as TrieAccount, SignableTransaction, TxEip1559, TxEip2930, TxEip4844,
+        TxEip4844Variant, TxEip7702, TxEnvelope, TxLegacy,
+    },
+    eips::eip2718::{Decodable2718, Encodable2718},
+    primitives::{address, keccak256},
+    rlp,
 };
 use std::{
-    collections::HashMap,
-    ops::{Deref, DerefMut},
-    rc::Rc,
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
 };
 
-type StateBacking = HashMap<Address, Rc<Account>>;
+const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+const ELASTICITY_MULTIPLIER: u64 = 2;
+const MIN_BLOB_BASE_FEE: u64 = 1;
+// EIP-7951: the secp256r1 signature verification precompile, placed apart from the others
+const P256VERIFY: Address = address!("0000000000000000000000000000000000000100");
+// EIP-7623: calldata tokens counted for each nonzero byte
+const NONZERO_BYTE_TOKENS: u64 = 4;
 
-#[derive(Debug, Default)]
-pub struct State(StateBacking);
+/// The state execution runs against: a cache of the accounts and storage read from a
+/// `Database`, along with any changes made to them.
+///
+/// The `storage` of a cached account holds only the slots written since it was loaded,
+/// including those cleared to zero.
+///
+/// Reads fill the caches through a shared reference, so a `State` can be shared between
+/// threads executing against the same snapshot.
+pub struct State {
+    database: Arc<dyn Database>,
+    // `None` records an account known not to exist
+    accounts: RwLock<HashMap<Address, Option<Arc<Account>>>>,
+    storage: RwLock<HashMap<(Address, Word), Word>>,
+    // Accounts removed along with their storage in the database
+    removed: HashSet<Address>,
+    // Accounts changed since the state was created
+    modified: HashSet<Address>,
+}
 
-impl Deref for State {
-    type Target = StateBacking;
+/// The changes made to a `State` since it was created.
+#[derive(Debug, Default, Clone)]
+pub struct StateChanges {
+    /// Each modified account with the storage slots written to it, or `None` if it was
+    /// removed.
+    pub accounts: HashMap<Address, Option<Arc<Account>>>,
+    /// Accounts whose storage was removed before any slots in `accounts` were written.
+    pub removed: HashSet<Address>,
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+impl Default for State {
+    fn default() -> Self {
+        Self::new(InMemoryDatabase::default())
     }
 }
 
-impl DerefMut for State {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+impl Clone for State {
+    fn clone(&self) -> Self {
+        Self {
+            database: self.database.clone(),
+            accounts: RwLock::new(read(&self.accounts).clone()),
+            storage: RwLock::new(read(&self.storage).clone()),
+            removed: self.removed.clone(),
+            modified: self.modified.clone(),
+        }
     }
 }
 
+impl fmt::Debug for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("State")
+            .field("accounts", &*read(&self.accounts))
+            .field("storage", &*read(&self.storage))
+            .field("removed", &self.removed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl State {
+    pub fn new(database: impl Database + 'static) -> Self {
+        Self {
+            database: Arc::new(database),
+            accounts: Default::default(),
+            storage: Default::default(),
+            removed: Default::default(),
+            modified: Default::default(),
+        }
+    }
+
+    /// Returns the account at `address`, or `None` if it does not exist.
+    pub fn get(&self, address: &Address) -> Result<Option<Arc<Account>>, Error> {
+        if let Some(account) = read(&self.accounts).get(address) {
+            return Ok(account.clone());
+        }
+        let account = match self.database.basic(address)? {
+            Some(info) => Some(Arc::new(Account {
+                balance: info.balance,
+                nonce: info.nonce,
+                storage: Default::default(),
+                code: self.database.code_by_hash(&info.code_hash)?,
+            })),
+            None => None,
+        };
+        write(&self.accounts).insert(*address, account.clone());
+        Ok(account)
+    }
+
+    /// Returns the account at `address`, which is empty if it does not exist.
+    pub fn account(&self, address: &Address) -> Result<Arc<Account>, Error> {
+        Ok(self.get(address)?.unwrap_or_default())
+    }
+
+    /// Returns a mutable reference to the account at `address`, creating an empty one if needed.
+    pub fn account_mut(&mut self, address: Address) -> Result<&mut Account, Error> {
+        self.get(&address)?;
+        self.modified.insert(address);
+        let account = self
+            .accounts
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(address)
+            .or_default()
+            .get_or_insert_with(Default::default);
+        Ok(Arc::make_mut(account))
+    }
+
+    /// Replace the account at `address`, removing it if `account` is `None`.
+    pub fn set(&mut self, address: Address, account: Option<Arc<Account>>) {
+        self.modified.insert(address);
+        self.accounts
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(address, account);
+    }
+
+    /// Remove the account at `address` along with all of its storage.
+    pub fn remove(&mut self, address: Address) {
+        self.set(address, None);
+        self.removed.insert(address);
+    }
+
+    /// Returns the account whose code runs when `address` is called, following an EIP-7702
+    /// delegation, along with the address delegated to if there is one.
+    pub fn code_account(
+        &self,
+        address: &Address,
+    ) -> Result<(Arc<Account>, Option<Address>), Error> {
+        let account = self.account(address)?;
+        match account.code.delegation() {
+            Some(delegate) => Ok((self.account(&delegate)?, Some(delegate))),
+            None => Ok((account, None)),
+        }
+    }
+
+    /// Returns the value stored at `key` by the account at `address`, zero if unset.
+    pub fn storage(&self, address: &Address, key: &Word) -> Result<Word, Error> {
+        if let Some(value) = self
+            .get(address)?
+            .and_then(|account| account.storage.get(key).copied())
+        {
+            return Ok(value);
+        }
+        if self.removed.contains(address) {
+            return Ok(Word::ZERO);
+        }
+        if let Some(value) = read(&self.storage).get(&(*address, *key)) {
+            return Ok(*value);
+        }
+        let value = self.database.storage(address, key)?;
+        write(&self.storage).insert((*address, *key), value);
+        Ok(value)
+    }
+
+    /// Returns the hash of the block with the given `number`.
+    pub fn block_hash(&self, number: u64) -> Result<B256, Error> {
+        self.database.block_hash(number)
+    }
+
+    /// Returns the root of the storage trie of the account at `address`, holding its nonzero
+    /// slots keyed by the keccak256 hashes of their keys.
+    pub fn storage_root(&self, address: &Address) -> Result<B256, Error> {
+        Ok(self.storage_trie(address)?.root())
+    }
+
+    /// Returns the root of the state trie, holding every account keyed by the keccak256 hash
+    /// of its address, as committed to by block headers.
+    ///
+    /// Every account and storage slot is read, so the database must be able to enumerate
+    /// its contents.
+    pub fn state_root(&self) -> Result<B256, Error> {
+        Ok(self.state_trie()?.root())
+    }
+
+    fn storage_trie(&self, address: &Address) -> Result<Trie, Error> {
+        let mut keys = if self.removed.contains(address) {
+            vec![]
+        } else {
+            self.database.storage_keys(address)?
+        };
+        if let Some(account) = self.get(address)? {
+            keys.extend(account.storage.keys());
+        }
+        let mut trie = Trie::default();
+        for key in keys {
+            let value = self.storage(address, &key)?;
+            if !value.is_zero() {
+                trie.insert(keccak256(B256::from(key)).as_slice(), rlp::encode(value));
+            }
+        }
+        Ok(trie)
+    }
+
+    fn state_trie(&self) -> Result<Trie, Error> {
+        let mut addresses = self
+            .database
+            .addresses()?
+            .into_iter()
+            .collect::<HashSet<_>>();
+        addresses.extend(read(&self.accounts).keys());
+        let mut trie = Trie::default();
+        for address in addresses {
+            let Some(account) = self.get(&address)? else {
+                continue;
+            };
+            let account = TrieAccount {
+                nonce: account.nonce,
+                balance: account.balance,
+                storage_root: self.storage_root(&address)?,
+                code_hash: account.code.hash(),
+            };
+            trie.insert(keccak256(address).as_slice(), rlp::encode(account));
+        }
+        Ok(trie)
+    }
+
+    /// Returns the account at `address` and the given storage slots, with Merkle proofs of
+    /// each against the state root, in the manner of `eth_getProof`.
+    ///
+    /// As with `state_root`, the database must be able to enumerate its contents.
+    pub fn proof(&self, address: &Address, keys: &[Word]) -> Result<AccountProof, Error> {
+        let storage_trie = self.storage_trie(address)?;
+        let storage_proof = keys
+            .iter()
+            .map(|key| {
+                let proof = storage_trie.proof(keccak256(B256::from(*key)).as_slice());
+                Ok(StorageProof {
+                    key: *key,
+                    value: self.storage(address, key)?,
+                    proof: proof.into_iter().map(Bytes::from).collect(),
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        let account = match self.get(address)? {
+            Some(account) => TrieAccount {
+                nonce: account.nonce,
+                balance: account.balance,
+                storage_root: storage_trie.root(),
+                code_hash: account.code.hash(),
+            },
+            None => EMPTY_ACCOUNT,
+        };
+        let account_proof = self.state_trie()?.proof(keccak256(address).as_slice());
+        Ok(AccountProof {
+            address: *address,
+            balance: account.balance,
+            nonce: account.nonce,
+            code_hash: account.code_hash,
+            storage_hash: account.storage_root,
+            account_proof: account_proof.into_iter().map(Bytes::from).collect(),
+            storage_proof,
+        })
+    }
+
+    /// Returns the changes made to this state since it was created.
+    pub fn changes(&self) -> StateChanges {
+        let accounts = read(&self.accounts);
+        StateChanges {
+            accounts: self
+                .modified
+                .iter()
+                .map(|address| (*address, accounts[address].clone()))
+                .collect(),
+            removed: self.removed.clone(),
+        }
+    }
+
+    /// Apply `changes` made to another state on top of this one, e.g. by a transaction
+    /// executed against a state holding this one's accounts.
+    pub fn apply(&mut self, changes: &StateChanges) -> Result<(), Error> {
+        for address in &changes.removed {
+            self.remove(*address);
+        }
+        for (address, account) in &changes.accounts {
+            let Some(account) = account else {
+                self.set(*address, None);
+                continue;
+            };
+            let existing = self.account_mut(*address)?;
+            existing.balance = account.balance;
+            existing.nonce = account.nonce;
+            existing.code = account.code.clone();
+            existing.storage.extend(&account.storage);
+        }
+        Ok(())
+    }
+}
+
+// The caches only ever gain entries, each inserted whole, so they remain consistent even if
+// a thread panicked while holding the lock
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
 impl<T> From<T> for State
 where
     T: Iterator<Item = (Address, Account)>,
 {
     fn from(values: T) -> Self {
-        Self(Iterator::collect(
-            values.map(|(address, account)| (address, Rc::new(account))),
-        ))
+        Self::new(InMemoryDatabase::from(values))
     }
 }
 
-#[derive(Debug, Default)]
-pub struct Block {}
+#[derive(Debug, Default, Clone)]
+pub struct Block {
+    pub number: u64,
+    pub coinbase: Address,
+    pub timestamp: u64,
+    /// The RANDAO mix of the beacon chain, or the difficulty before the merge.
+    pub prev_randao: B256,
+    pub gas_limit: u64,
+    pub base_fee: U256,
+    pub excess_blob_gas: u64,
+}
+
+impl Block {
+    /// Returns the base fee of the child of this block, given the gas used by this block.
+    pub fn next_base_fee(&self, gas_used: u64) -> U256 {
+        let gas_target = self.gas_limit / ELASTICITY_MULTIPLIER;
+        if gas_target == 0 || gas_used == gas_target {
+            return self.base_fee;
+        }
+        let scale = |delta: u64| {
+            self.base_fee * U256::from(delta)
+                / U256::from(gas_target)
+                / U256::from(BASE_FEE_MAX_CHANGE_DENOMINATOR)
+        };
+        if gas_used > gas_target {
+            let delta = scale(gas_used - gas_target).max(U256::from(1));
+            self.base_fee.saturating_add(delta)
+        } else {
+            self.base_fee - scale(gas_target - gas_used)
+        }
+    }
+
+    /// Returns the price of blob gas in this block, given the fork's `schedule`.
+    pub fn blob_base_fee(&self, schedule: &GasSchedule) -> U256 {
+        fake_exponential(
+            U256::from(MIN_BLOB_BASE_FEE),
+            U256::from(self.excess_blob_gas),
+            U256::from(schedule.blob_base_fee_update_fraction),
+        )
+    }
+}
 
-#[derive(Debug, Default)]
+/// Approximates `factor * e ** (numerator / denominator)` using a Taylor expansion, as
+/// specified by EIP-4844.
+fn fake_exponential(factor: U256, numerator: U256, denominator: U256) -> U256 {
+    if denominator.is_zero() {
+        return factor;
+    }
+    let mut output = U256::ZERO;
+    let mut accumulator = factor * denominator;
+    let mut i = U256::from(1);
+    while !accumulator.is_zero() {
+        output = output.saturating_add(accumulator);
+        accumulator = accumulator.saturating_mul(numerator) / (denominator * i);
+        i += U256::from(1);
+    }
+    output / denominator
+}
+
+/// EIP-2718 transaction types.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionType {
+    #[default]
+    Legacy,
+    Eip2930,
+    Eip1559,
+    Eip4844,
+    Eip7702,
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct Transaction {
-    pub gas_limit: U256,
+    pub transaction_type: TransactionType,
+    pub chain_id: Option<u64>,
+    pub nonce: u64,
+    pub gas_limit: u64,
+    /// Price paid per unit of gas by transactions predating EIP-1559.
     pub gas_price: U256,
+    /// Set for EIP-1559 transactions, in which case `gas_price` is ignored.
+    pub max_fee_per_gas: Option<U256>,
+    pub max_priority_fee_per_gas: U256,
     pub sender: Address,
-    pub recipient: Address,
+    /// The account called, or `TxKind::Create` to deploy a contract running `input` as its
+    /// initcode.
+    pub to: TxKind,
     pub value: U256,
     pub input: Vec<u8>,
+    pub access_list: AccessList,
+    pub blob_versioned_hashes: Vec<B256>,
+    /// Set for EIP-4844 transactions, which pay for `blob_versioned_hashes` in blob gas.
+    pub max_fee_per_blob_gas: Option<U256>,
+    pub authorization_list: Vec<SignedAuthorization>,
+    /// Initcontainers carried by an EIP-7873 transaction, which no decoded transaction type
+    /// carries yet, so only set by EVMC hosts.
+    pub initcodes: Vec<Vec<u8>>,
+    pub signature: Option<Signature>,
+}
+
+fn to_u128(value: U256, field: &'static str) -> Result<u128, Error> {
+    u128::try_from(value).map_err(|_| Error::UnencodableTransaction(field))
+}
+
+impl TryFrom<TxEnvelope> for Transaction {
+    type Error = Error;
+
+    fn try_from(envelope: TxEnvelope) -> Result<Self, Error> {
+        let transaction = match envelope {
+            TxEnvelope::Legacy(signed) => {
+                let (tx, signature, _) = signed.into_parts();
+                Self {
+                    transaction_type: TransactionType::Legacy,
+                    chain_id: tx.chain_id,
+                    nonce: tx.nonce,
+                    gas_limit: tx.gas_limit,
+                    gas_price: U256::from(tx.gas_price),
+                    to: tx.to,
+                    value: tx.value,
+                    input: tx.input.to_vec(),
+                    signature: Some(signature),
+                    ..Default::default()
+                }
+            }
+            TxEnvelope::Eip2930(signed) => {
+                let (tx, signature, _) = signed.into_parts();
+                Self {
+                    transaction_type: TransactionType::Eip2930,
+                    chain_id: Some(tx.chain_id),
+                    nonce: tx.nonce,
+                    gas_limit: tx.gas_limit,
+                    gas_price: U256::from(tx.gas_price),
+                    to: tx.to,
+                    value: tx.value,
+                    input: tx.input.to_vec(),
+                    access_list: tx.access_list,
+                    signature: Some(signature),
+                    ..Default::default()
+                }
+            }
+            TxEnvelope::Eip1559(signed) => {
+                let (tx, signature, _) = signed.into_parts();
+                Self {
+                    transaction_type: TransactionType::Eip1559,
+                    chain_id: Some(tx.chain_id),
+                    nonce: tx.nonce,
+                    gas_limit: tx.gas_limit,
+                    max_fee_per_gas: Some(U256::from(tx.max_fee_per_gas)),
+                    max_priority_fee_per_gas: U256::from(tx.max_priority_fee_per_gas),
+                    to: tx.to,
+                    value: tx.value,
+                    input: tx.input.to_vec(),
+                    access_list: tx.access_list,
+                    signature: Some(signature),
+                    ..Default::default()
+                }
+            }
+            TxEnvelope::Eip4844(signed) => {
+                let (tx, signature, _) = signed.into_parts();
+                // The blob sidecar is only relevant to the network, not to execution
+                let tx = match tx {
+                    TxEip4844Variant::TxEip4844(tx) => tx,
+                    TxEip4844Variant::TxEip4844WithSidecar(tx) => tx.tx,
+                };
+                Self {
+                    transaction_type: TransactionType::Eip4844,
+                    chain_id: Some(tx.chain_id),
+                    nonce: tx.nonce,
+                    gas_limit: tx.gas_limit,
+                    max_fee_per_gas: Some(U256::from(tx.max_fee_per_gas)),
+                    max_priority_fee_per_gas: U256::from(tx.max_priority_fee_per_gas),
+                    to: tx.to.into(),
+                    value: tx.value,
+                    input: tx.input.to_vec(),
+                    access_list: tx.access_list,
+                    blob_versioned_hashes: tx.blob_versioned_hashes,
+                    max_fee_per_blob_gas: Some(U256::from(tx.max_fee_per_blob_gas)),
+                    signature: Some(signature),
+                    ..Default::default()
+                }
+            }
+            TxEnvelope::Eip7702(signed) => {
+                let (tx, signature, _) = signed.into_parts();
+                Self {
+                    transaction_type: TransactionType::Eip7702,
+                    chain_id: Some(tx.chain_id),
+                    nonce: tx.nonce,
+                    gas_limit: tx.gas_limit,
+                    max_fee_per_gas: Some(U256::from(tx.max_fee_per_gas)),
+                    max_priority_fee_per_gas: U256::from(tx.max_priority_fee_per_gas),
+                    to: tx.to.into(),
+                    value: tx.value,
+                    input: tx.input.to_vec(),
+                    access_list: tx.access_list,
+                    authorization_list: tx.authorization_list,
+                    signature: Some(signature),
+                    ..Default::default()
+                }
+            }
+            _ => return Err(Error::UnsupportedTransactionType),
+        };
+        Ok(transaction)
+    }
+}
+
+impl Transaction {
+    /// Decode a signed transaction from its EIP-2718 encoding, as found in blocks,
+    /// recovering the `sender` from its signature.
+    pub fn decode(raw: &[u8]) -> Result<Self, Error> {
+        let mut transaction = Self::decode_unrecovered(raw)?;
+        transaction.sender = transaction.recover_sender()?;
+        Ok(transaction)
+    }
+
+    /// Decode a transaction without recovering the `sender`, e.g. to simulate it from a
+    /// different account.
+    pub fn decode_unrecovered(raw: &[u8]) -> Result<Self, Error> {
+        let envelope = TxEnvelope::decode_2718(&mut &raw[..])?;
+        Self::try_from(envelope)
+    }
+
+    /// Recover the address which signed this transaction.
+    ///
+    /// Signatures with a high `s` value are rejected, per EIP-2.
+    pub fn recover_sender(&self) -> Result<Address, Error> {
+        let signature = self
+            .signature
+            .ok_or(Error::UnencodableTransaction("signature"))?;
+        if signature.normalize_s().is_some() {
+            return Err(InvalidTransaction::InvalidSignature.into());
+        }
+        self.to_envelope()?
+            .recover_signer()
+            .map_err(|_| InvalidTransaction::InvalidSignature.into())
+    }
+
+    /// Returns the EIP-2718 encoding of this transaction, which must be signed.
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.to_envelope()?.encoded_2718())
+    }
+
+    /// Returns the hash of the signed transaction.
+    pub fn hash(&self) -> Result<B256, Error> {
+        Ok(*self.to_envelope()?.tx_hash())
+    }
+
+    fn to_envelope(&self) -> Result<TxEnvelope, Error> {
+        let signature = self
+            .signature
+            .ok_or(Error::UnencodableTransaction("signature"))?;
+        let chain_id = || {
+            self.chain_id
+                .ok_or(Error::UnencodableTransaction("chain_id"))
+        };
+        let max_fee_per_gas = || {
+            self.max_fee_per_gas
+                .ok_or(Error::UnencodableTransaction("max_fee_per_gas"))
+                .and_then(|fee| to_u128(fee, "max_fee_per_gas"))
+        };
+        let max_priority_fee_per_gas =
+            || to_u128(self.max_priority_fee_per_gas, "max_priority_fee_per_gas");
+        // Blob and set code transactions cannot create contracts
+        let call_target = || {
+            self.to
+                .to()
+                .copied()
+                .ok_or(Error::UnencodableTransaction("to"))
+        };
+        let envelope = match self.transaction_type {
+            TransactionType::Legacy => TxLegacy {
+                chain_id: self.chain_id,
+                nonce: self.nonce,
+                gas_price: to_u128(self.gas_price, "gas_price")?,
+                gas_limit: self.gas_limit,
+                to: self.to,
+                value: self.value,
+                input: self.input.clone().into(),
+            }
+            .into_signed(signature)
+            .into(),
+            TransactionType::Eip2930 => TxEip2930 {
+                chain_id: chain_id()?,
+                nonce: self.nonce,
+                gas_price: to_u128(self.gas_price, "gas_price")?,
+                gas_limit: self.gas_limit,
+                to: self.to,
+                value: self.value,
+                access_list: self.access_list.clone(),
+                input: self.input.clone().into(),
+            }
+            .into_signed(signature)
+            .into(),
+            TransactionType::Eip1559 => TxEip1559 {
+                chain_id: chain_id()?,
+                nonce: self.nonce,
+                gas_limit: self.gas_limit,
+                max_fee_per_gas: max_fee_per_gas()?,
+                max_priority_fee_per_gas: max_priority_fee_per_gas()?,
+                to: self.to,
+                value: self.value,
+                access_list: self.access_list.clone(),
+                input: self.input.clone().into(),
+            }
+            .into_signed(signature)
+            .into(),
+            TransactionType::Eip4844 => TxEip4844Variant::TxEip4844(TxEip4844 {
+                chain_id: chain_id()?,
+                nonce: self.nonce,
+                gas_limit: self.gas_limit,
+                max_fee_per_gas: max_fee_per_gas()?,
+                max_priority_fee_per_gas: max_priority_fee_per_gas()?,
+                to: call_target()?,
+                value: self.value,
+                access_list: self.access_list.clone(),
+                blob_versioned_hashes: self.blob_versioned_hashes.clone(),
+                max_fee_per_blob_gas: self
+                    .max_fee_per_blob_gas
+                    .ok_or(Error::UnencodableTransaction("max_fee_per_blob_gas"))
+                    .and_then(|fee| to_u128(fee, "max_fee_per_blob_gas"))?,
+                input: self.input.clone().into(),
+            })
+            .into_signed(signature)
+            .into(),
+            TransactionType::Eip7702 => TxEip7702 {
+                chain_id: chain_id()?,
+                nonce: self.nonce,
+                gas_limit: self.gas_limit,
+                max_fee_per_gas: max_fee_per_gas()?,
+                max_priority_fee_per_gas: max_priority_fee_per_gas()?,
+                to: call_target()?,
+                value: self.value,
+                access_list: self.access_list.clone(),
+                authorization_list: self.authorization_list.clone(),
+                input: self.input.clone().into(),
+            }
+            .into_signed(signature)
+            .into(),
+        };
+        Ok(envelope)
+    }
+
+    /// Returns the gas charged before any code is executed.
+    pub fn intrinsic_gas(&self, schedule: &GasSchedule) -> u64 {
+        let data_cost = self
+            .input
+            .iter()
+            .map(|byte| {
+                if *byte == 0 {
+                    schedule.tx_data_zero
+                } else {
+                    schedule.tx_data_nonzero
+                }
+            })
+            .sum::<u64>();
+        let access_list_cost = self
+            .access_list
+            .iter()
+            .map(|item| {
+                schedule.access_list_address
+                    + schedule.access_list_storage * item.storage_keys.len() as u64
+            })
+            .sum::<u64>();
+        let authorization_cost = schedule.authorization * self.authorization_list.len() as u64;
+        // EIP-2 and EIP-3860
+        let create_cost = if self.to.is_create() {
+            schedule.tx_create + schedule.init_code_word * self.input.len().div_ceil(32) as u64
+        } else {
+            0
+        };
+        schedule.transaction + data_cost + access_list_cost + authorization_cost + create_cost
+    }
+
+    /// Returns the least gas the transaction may be charged under EIP-7623, which prices its
+    /// calldata by the token with zero bytes counting as one token and others as four.
+    pub fn floor_data_gas(&self, schedule: &GasSchedule) -> u64 {
+        let tokens = self
+            .input
+            .iter()
+            .map(|byte| if *byte == 0 { 1 } else { NONZERO_BYTE_TOKENS })
+            .sum::<u64>();
+        schedule.transaction + schedule.floor_data_gas * tokens
+    }
+
+    /// Returns the blob gas consumed by the blobs of this transaction.
+    pub fn blob_gas(&self, schedule: &GasSchedule) -> u64 {
+        schedule.blob_gas_per_blob * self.blob_versioned_hashes.len() as u64
+    }
+
+    /// Returns the most the sender may pay per unit of gas.
+    pub fn max_gas_price(&self) -> U256 {
+        self.max_fee_per_gas.unwrap_or(self.gas_price)
+    }
+
+    /// Returns the price actually paid per unit of gas in a block with the given `base_fee`.
+    pub fn effective_gas_price(&self, base_fee: U256) -> U256 {
+        match self.max_fee_per_gas {
+            Some(max_fee_per_gas) => {
+                max_fee_per_gas.min(base_fee.saturating_add(self.max_priority_fee_per_gas))
+            }
+            None => self.gas_price,
+        }
+    }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Precompiles {}
 
-#[derive(Debug, Default)]
+impl Precompiles {
+    /// Returns the addresses of the precompiled contracts available under `spec`.
+    pub fn addresses(&self, spec: SpecId) -> impl Iterator<Item = Address> {
+        let count = if spec.is_enabled_in(SpecId::Prague) {
+            0x11
+        } else if spec.is_enabled_in(SpecId::Cancun) {
+            0x0a
+        } else if spec.is_enabled_in(SpecId::Istanbul) {
+            0x09
+        } else if spec.is_enabled_in(SpecId::Byzantium) {
+            0x08
+        } else {
+            0x04
+        };
+        (1..=count)
+            .map(Address::with_last_byte)
+            .chain(spec.is_enabled_in(SpecId::Osaka).then_some(P256VERIFY))
+    }
+}
+
+/// Changes accumulated over a transaction which are undone when the frame making them fails.
+///
+/// Each change is recorded in a journal, so everything since a `Checkpoint` can be reverted.
+#[derive(Debug, Default, Clone)]
+pub struct Substate {
+    /// Accounts and storage slots accessed so far, used to price state access per EIP-2929.
+    pub accessed_addresses: HashSet<Address>,
+    pub accessed_storage_keys: HashSet<(Address, Word)>,
+    /// EIP-1153 transient storage, which is never committed and so discarded with the
+    /// transaction.
+    pub transient_storage: HashMap<(Address, Word), Word>,
+    /// Accounts which self-destructed, removed at the end of the transaction.
+    pub self_destructs: HashSet<Address>,
+    /// Accounts created in the transaction, the only ones self-destruction removes after
+    /// EIP-6780.
+    pub created: HashSet<Address>,
+    /// Accounts touched in the transaction, removed at its end if empty per EIP-161.
+    pub touched: HashSet<Address>,
+    // Values of the storage slots written so far at the start of the transaction, per EIP-2200
+    original_storage: HashMap<(Address, Word), Word>,
+    journal: Vec<JournalEntry>,
+}
+
+/// A point in a transaction to which its `Substate` and state changes can be reverted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint(usize);
+
+// A change to undo when reverting, holding what it replaced
+#[derive(Debug, Clone)]
+enum JournalEntry {
+    AccountAccessed(Address),
+    StorageKeyAccessed(Address, Word),
+    TransientStorage {
+        address: Address,
+        key: Word,
+        previous: Word,
+    },
+    // `None` if the slot had not been written since the account was loaded
+    Storage {
+        address: Address,
+        key: Word,
+        previous: Option<Word>,
+    },
+    Account {
+        address: Address,
+        previous: Option<Arc<Account>>,
+    },
+    SelfDestruct(Address),
+    AccountCreated(Address),
+    AccountTouched(Address),
+}
+
+impl Substate {
+    /// Returns the substate at the start of the transaction in `context`, with the accounts and
+    /// storage slots warmed by the transaction itself already accessed.
+    pub fn for_transaction(context: &Context) -> Result<Self, Error> {
+        let transaction = &context.transaction;
+        let mut substate = Self::default();
+        // The address of a contract created by the transaction is warmed on creation
+        substate.accessed_addresses.extend(
+            [Some(transaction.sender), transaction.to.to().copied()]
+                .into_iter()
+                .flatten()
+                .chain(context.precompiles.addresses(context.spec())),
+        );
+        // EIP-3651
+        if context.spec().is_enabled_in(SpecId::Shanghai) {
+            substate.access_address(context.block.coinbase);
+        }
+        for item in transaction.access_list.iter() {
+            substate.access_address(item.address);
+            for key in &item.storage_keys {
+                substate.access_storage_key(item.address, Word::from_be_bytes(key.0));
+            }
+        }
+        // The delegate of the recipient is warmed without charge
+        if let TxKind::Call(recipient) = transaction.to {
+            if let (_, Some(delegate)) = context.state.code_account(&recipient)? {
+                substate.access_address(delegate);
+            }
+        }
+        Ok(substate)
+    }
+
+    /// Marks `address` as accessed, returning `true` if it was cold.
+    pub fn access_address(&mut self, address: Address) -> bool {
+        let cold = self.accessed_addresses.insert(address);
+        if cold {
+            self.journal.push(JournalEntry::AccountAccessed(address));
+        }
+        cold
+    }
+
+    /// Marks `key` in the storage of `address` as accessed, returning `true` if it was cold.
+    pub fn access_storage_key(&mut self, address: Address, key: Word) -> bool {
+        let cold = self.accessed_storage_keys.insert((address, key));
+        if cold {
+            self.journal
+                .push(JournalEntry::StorageKeyAccessed(address, key));
+        }
+        cold
+    }
+
+    pub fn transient_storage(&self, address: &Address, key: &Word) -> Word {
+        self.transient_storage
+            .get(&(*address, *key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn set_transient_storage(&mut self, address: Address, key: Word, value: Word) {
+        let previous = self
+            .transient_storage
+            .insert((address, key), value)
+            .unwrap_or_default();
+        self.journal.push(JournalEntry::TransientStorage {
+            address,
+            key,
+            previous,
+        });
+    }
+
+    /// Records that `address` self-destructed, returning `true` if it had not already.
+    pub fn self_destruct(&mut self, address: Address) -> bool {
+        let first = self.self_destructs.insert(address);
+        if first {
+            self.journal.push(JournalEntry::SelfDestruct(address));
+        }
+        first
+    }
+
+    /// Records that the account at `address` was created.
+    pub fn create_account(&mut self, address: Address) {
+        if self.created.insert(address) {
+            self.journal.push(JournalEntry::AccountCreated(address));
+        }
+    }
+
+    /// Records that the account at `address` was touched, as the recipient of a call or of
+    /// value.
+    pub fn touch(&mut self, address: Address) {
+        if self.touched.insert(address) {
+            self.journal.push(JournalEntry::AccountTouched(address));
+        }
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.journal.len())
+    }
+}
+
+/// The environment a transaction executes in.
+///
+/// The gas schedule follows the spec unless overridden with `with_gas_schedule`, so the two
+/// are only set through `new`, `set_spec` and `with_gas_schedule`.
+#[derive(Debug, Clone)]
 pub struct Context {
+    spec: SpecId,
+    pub chain_id: u64,
+    gas_schedule: GasSchedule,
     pub block: Block,
     pub transaction: Transaction,
     pub precompiles: Precompiles,
     pub state: State,
+    pub substate: Substate,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new(SpecId::default())
+    }
+}
+
+impl Context {
+    /// Returns an empty context executing under the rules and gas schedule of `spec`.
+    pub fn new(spec: SpecId) -> Self {
+        Self {
+            spec,
+            chain_id: Default::default(),
+            gas_schedule: GasSchedule::for_spec(spec),
+            block: Default::default(),
+            transaction: Default::default(),
+            precompiles: Default::default(),
+            state: Default::default(),
+            substate: Default::default(),
+        }
+    }
+
+    pub fn spec(&self) -> SpecId {
+        self.spec
+    }
+
+    pub fn gas_schedule(&self) -> &GasSchedule {
+        &self.gas_schedule
+    }
+
+    /// Execute under the rules of `spec`, replacing the gas schedule with that of `spec`.
+    pub fn set_spec(&mut self, spec: SpecId) {
+        self.spec = spec;
+        self.gas_schedule = GasSchedule::for_spec(spec);
+    }
+
+    /// Override the gas schedule of the spec, e.g. to experiment with repricings, failing if
+    /// `gas_schedule` could not be executed against.
+    pub fn with_gas_schedule(mut self, gas_schedule: GasSchedule) -> Result<Self, Error> {
+        gas_schedule.validate()?;
+        self.gas_schedule = gas_schedule;
+        Ok(self)
+    }
+
+    /// Returns the block base fee, or zero before EIP-1559 was activated.
+    pub fn base_fee(&self) -> U256 {
+        if self.spec.is_enabled_in(SpecId::London) {
+            self.block.base_fee
+        } else {
+            U256::ZERO
+        }
+    }
+
+    /// Returns the block blob base fee, or zero before EIP-4844 was activated.
+    pub fn blob_base_fee(&self) -> U256 {
+        if self.spec.is_enabled_in(SpecId::Cancun) {
+            self.block.blob_base_fee(&self.gas_schedule)
+        } else {
+            U256::ZERO
+        }
+    }
+
+    /// Returns a checkpoint to which the changes made to the substate and state from now on
+    /// can be reverted.
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.substate.checkpoint()
+    }
+
+    /// Undo every change to the substate and state made since `checkpoint`.
+    pub fn revert(&mut self, checkpoint: Checkpoint) -> Result<(), Error> {
+        while self.substate.journal.len() > checkpoint.0 {
+            let substate = &mut self.substate;
+            match substate
+                .journal
+                .pop()
+                .expect("journal is longer than checkpoint")
+            {
+                JournalEntry::AccountAccessed(address) => {
+                    substate.accessed_addresses.remove(&address);
+                }
+                JournalEntry::StorageKeyAccessed(address, key) => {
+                    substate.accessed_storage_keys.remove(&(address, key));
+                }
+                JournalEntry::TransientStorage {
+                    address,
+                    key,
+                    previous,
+                } if previous.is_zero() => {
+                    substate.transient_storage.remove(&(address, key));
+                }
+                JournalEntry::TransientStorage {
+                    address,
+                    key,
+                    previous,
+                } => {
+                    substate.transient_storage.insert((address, key), previous);
+                }
+                JournalEntry::Storage {
+                    address,
+                    key,
+                    previous,
+                } => {
+                    let storage = &mut self.state.account_mut(address)?.storage;
+                    match previous {
+                        Some(previous) => storage.insert(key, previous),
+                        None => storage.remove(&key),
+                    };
+                }
+                JournalEntry::Account { address, previous } => self.state.set(address, previous),
+                JournalEntry::SelfDestruct(address) => {
+                    substate.self_destructs.remove(&address);
+                }
+                JournalEntry::AccountCreated(address) => {
+                    substate.created.remove(&address);
+                }
+                JournalEntry::AccountTouched(address) => {
+                    substate.touched.remove(&address);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the value of `key` in the storage of `address` at the start of the transaction.
+    pub fn original_storage(&self, address: &Address, key: &Word) -> Result<Word, Error> {
+        match self.substate.original_storage.get(&(*address, *key)) {
+            Some(value) => Ok(*value),
+            None => self.state.storage(address, key),
+        }
+    }
+
+    /// Set the value of `key` in the storage of `address`, to be undone on revert.
+    pub fn set_storage(&mut self, address: Address, key: Word, value: Word) -> Result<(), Error> {
+        let current = self.state.storage(&address, &key)?;
+        self.substate
+            .original_storage
+            .entry((address, key))
+            .or_insert(current);
+        let previous = self.state.account_mut(address)?.storage.insert(key, value);
+        self.substate.journal.push(JournalEntry::Storage {
+            address,
+            key,
+            previous,
+        });
+        Ok(())
+    }
+
+    /// Returns a mutable reference to the account at `address` as `State::account_mut` does,
+    /// recording the account first so that any change to it is undone on revert.
+    pub fn journaled_account_mut(&mut self, address: Address) -> Result<&mut Account, Error> {
+        let previous = self.state.get(&address)?;
+        self.substate
+            .journal
+            .push(JournalEntry::Account { address, previous });
+        self.state.account_mut(address)
+    }
+
+    /// Move `value` from the balance of `from` to that of `to`, to be undone on revert.
+    pub fn transfer(&mut self, from: Address, to: Address, value: U256) -> Result<(), Error> {
+        // Even a transfer of no value touches the recipient
+        self.substate.touch(to);
+        if value.is_zero() {
+            return Ok(());
+        }
+        self.journaled_account_mut(from)?.balance -= value;
+        self.journaled_account_mut(to)?.balance += value;
+        Ok(())
+    }
+
+    /// Remove the accounts which self-destructed in the transaction, along with their storage.
+    pub(crate) fn remove_self_destructs(&mut self) {
+        for address in &self.substate.self_destructs {
+            // EIP-6780: only accounts created in the same transaction are removed
+            if !self.spec.has_eip6780() || self.substate.created.contains(address) {
+                self.state.remove(*address);
+            }
+        }
+    }
+
+    /// Remove the accounts touched in the transaction which are empty, per EIP-161.
+    pub(crate) fn remove_touched_empty_accounts(&mut self) -> Result<(), Error> {
+        if !self.spec.is_enabled_in(SpecId::SpuriousDragon) {
+            return Ok(());
+        }
+        for address in &self.substate.touched {
+            if self
+                .state
+                .get(address)?
+                .is_some_and(|account| account.is_empty())
+            {
+                self.state.remove(*address);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{bytecode::Bytecode, database::AccountInfo, parse_hex};
+    use alloy::{
+        consensus::constants::EMPTY_ROOT_HASH,
+        primitives::{address, b256},
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // https://etherscan.io/tx/0x280cde7cdefe4b188750e76c888f13bd05ce9a4d7767730feefe8a0e50ca6fc4
+    const LEGACY_TRANSACTION: &str = "0xf9015482078b8505d21dba0083022ef1947a250d5630b4cf539739df2c5dacb4c659f2488d880c46549a521b13d8b8e47ff36ab50000000000000000000000000000000000000000000066ab5a608bd00a23f2fe000000000000000000000000000000000000000000000000000000000000008000000000000000000000000048c04ed5691981c42154c6167398f95e8f38a7ff00000000000000000000000000000000000000000000000000000000632ceac70000000000000000000000000000000000000000000000000000000000000002000000000000000000000000c02aaa39b223fe8d0a0e5c4f27ead9083c756cc20000000000000000000000006c6ee5e31d828de241282b9606c8e98ea48526e225a0c9077369501641a92ef7399ff81c21639ed4fd8fc69cb793cfa1dbfab342e10aa0615facb2f1bcf3274a354cfe384a38d0cc008a11c2dd23a69111bc6930ba27a8";
+    // https://etherscan.io/tx/0xce4dc6d7a7549a98ee3b071b67e970879ff51b5b95d1c340bacd80fa1e1aab31
+    const EIP1559_TRANSACTION: &str = "0x02f86f0102843b9aca0085029e7822d68298f094d9e1459a7a482635700cbc20bbaf52d495ab9c9680841b55ba3ac080a0c199674fcb29f353693dd779c017823b954b3c69dffa3cd6b2a6ff7888798039a028ca912de909e7e6cdef9cdcaf24c54dd8c1032946dfa1d85c206b32a9064fe8";
+    // https://sepolia.etherscan.io/tx/0x9a22ccb0029bc8b0ddd073be1a1d923b7ae2b2ea52100bae0db4424f9107e9c0
+    const SECP256K1N: &str = "0xfffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+    const EIP4844_TRANSACTION: &str = "0x03f9011d83aa36a7820fa28477359400852e90edd0008252089411e9ca82a3a762b4b5bd264d4173a242e7a770648080c08504a817c800f8a5a0012ec3d6f66766bedb002a190126b3549fce0047de0d4c25cffce0dc1c57921aa00152d8e24762ff22b1cfd9f8c0683786a7ca63ba49973818b3d1e9512cd2cec4a0013b98c6c83e066d5b14af2b85199e3d4fc7d1e778dd53130d180f5077e2d1c7a001148b495d6e859114e670ca54fb6e2657f0cbae5b08063605093a4b3dc9f8f1a0011ac212f13c5dff2b2c6b600a79635103d6f580a4221079951181b25c7e654901a0c8de4cced43169f9aa3d36506363b2d2c44f6c49fc1fd91ea114c86f3757077ea01e11fdd0d1934eda0492606ee0bb80a7bf8f35cc5f86ec60fe5031ba48bfd544";
+
+    #[test]
+    fn test_decode_and_encode_transactions() {
+        for raw in [LEGACY_TRANSACTION, EIP1559_TRANSACTION, EIP4844_TRANSACTION] {
+            let raw = parse_hex(raw).unwrap();
+            let transaction = Transaction::decode(&raw).unwrap();
+            assert_eq!(transaction.encode().unwrap(), raw);
+        }
+
+        let transaction = Transaction::decode(&parse_hex(LEGACY_TRANSACTION).unwrap()).unwrap();
+        assert_eq!(transaction.transaction_type, TransactionType::Legacy);
+        assert_eq!(transaction.chain_id, Some(1));
+        assert_eq!(transaction.nonce, 1931);
+        assert_eq!(
+            transaction.to,
+            TxKind::Call(address!("7a250d5630B4cF539739dF2C5dAcb4c659F2488D"))
+        );
+        assert_eq!(
+            transaction.hash().unwrap(),
+            b256!("280cde7cdefe4b188750e76c888f13bd05ce9a4d7767730feefe8a0e50ca6fc4")
+        );
+
+        let transaction = Transaction::decode(&parse_hex(EIP1559_TRANSACTION).unwrap()).unwrap();
+        assert_eq!(transaction.transaction_type, TransactionType::Eip1559);
+        assert_eq!(
+            transaction.max_fee_per_gas,
+            Some(U256::from(11_248_607_958u64))
+        );
+        assert_eq!(
+            transaction.max_priority_fee_per_gas,
+            U256::from(1_000_000_000)
+        );
+
+        let transaction = Transaction::decode(&parse_hex(EIP4844_TRANSACTION).unwrap()).unwrap();
+        assert_eq!(transaction.transaction_type, TransactionType::Eip4844);
+        assert_eq!(transaction.blob_versioned_hashes.len(), 5);
+        assert_eq!(
+            transaction.max_fee_per_blob_gas,
+            Some(U256::from(20_000_000_000u64))
+        );
+
+        // a contract creation, re-signed with the legacy transaction's signature
+        let mut transaction = Transaction::decode(&parse_hex(LEGACY_TRANSACTION).unwrap()).unwrap();
+        transaction.to = TxKind::Create;
+        let raw = transaction.encode().unwrap();
+        let decoded = Transaction::decode(&raw).unwrap();
+        assert_eq!(decoded.to, TxKind::Create);
+        assert_eq!(decoded.input, transaction.input);
+
+        // but blob transactions cannot create contracts
+        let mut transaction =
+            Transaction::decode(&parse_hex(EIP4844_TRANSACTION).unwrap()).unwrap();
+        transaction.to = TxKind::Create;
+        assert!(matches!(
+            transaction.encode(),
+            Err(Error::UnencodableTransaction("to"))
+        ));
+    }
+
+    #[test]
+    fn test_recover_sender() {
+        let cases = [
+            (
+                LEGACY_TRANSACTION,
+                address!("a12e1462d0ceD572f396F58B6E2D03894cD7C8a4"),
+            ),
+            (
+                EIP1559_TRANSACTION,
+                address!("001e2b7dE757bA469a57bF6b23d982458a07eFcE"),
+            ),
+            (
+                EIP4844_TRANSACTION,
+                address!("A83C816D4f9b2783761a22BA6FADB0eB0606D7B2"),
+            ),
+        ];
+        for (raw, sender) in cases {
+            let raw = parse_hex(raw).unwrap();
+            assert_eq!(Transaction::decode(&raw).unwrap().sender, sender);
+            assert_eq!(
+                Transaction::decode_unrecovered(&raw).unwrap().sender,
+                Address::ZERO
+            );
+        }
+    }
+
+    #[test]
+    fn test_reject_high_s() {
+        let raw = parse_hex(EIP1559_TRANSACTION).unwrap();
+        let mut transaction = Transaction::decode(&raw).unwrap();
+        let signature = transaction.signature.unwrap();
+        let secp256k1n = U256::from_be_slice(&parse_hex(SECP256K1N).unwrap());
+        transaction.signature = Some(Signature::new(
+            signature.r(),
+            secp256k1n - signature.s(),
+            signature.v().inverted(),
+        ));
+        assert!(matches!(
+            transaction.recover_sender(),
+            Err(Error::InvalidTransaction(
+                InvalidTransaction::InvalidSignature
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_encode_requires_signature() {
+        let transaction = Transaction::default();
+        assert!(matches!(
+            transaction.encode(),
+            Err(Error::UnencodableTransaction("signature"))
+        ));
+    }
+
+    #[test]
+    fn test_blob_base_fee() {
+        assert_eq!(
+            fake_exponential(U256::from(1), U256::from(2), U256::from(1)),
+            U256::from(6)
+        );
+        assert_eq!(
+            fake_exponential(U256::from(2), U256::from(5), U256::from(2)),
+            U256::from(23)
+        );
+        assert_eq!(
+            fake_exponential(U256::from(1), U256::from(50_000_000), U256::from(2_225_652)),
+            U256::from(5_709_098_764u64)
+        );
+
+        let block = Block {
+            excess_blob_gas: 10_000_000,
+            ..Default::default()
+        };
+        let blob_base_fee = |spec| block.blob_base_fee(&GasSchedule::for_spec(spec));
+        assert_eq!(blob_base_fee(SpecId::Cancun), U256::from(19));
+        assert_eq!(blob_base_fee(SpecId::Prague), U256::from(7));
+        assert_eq!(
+            Block::default().blob_base_fee(&GasSchedule::default()),
+            U256::from(1)
+        );
+    }
+
+    #[test]
+    fn test_next_base_fee() {
+        let block = Block {
+            gas_limit: 30_000_000,
+            base_fee: U256::from(1_000_000_000),
+            ..Default::default()
+        };
+        assert_eq!(block.next_base_fee(15_000_000), U256::from(1_000_000_000));
+        assert_eq!(block.next_base_fee(30_000_000), U256::from(1_125_000_000));
+        assert_eq!(block.next_base_fee(0), U256::from(875_000_000));
+        assert_eq!(block.next_base_fee(20_000_000), U256::from(1_041_666_666));
+
+        let block = Block {
+            gas_limit: 30_000_000,
+            base_fee: U256::from(7),
+            ..Default::default()
+        };
+        assert_eq!(block.next_base_fee(15_000_001), U256::from(8));
+    }
+
+    #[test]
+    fn test_gas_schedule_follows_spec() {
+        let mut context = Context::new(SpecId::Berlin);
+        assert_eq!(
+            context.gas_schedule(),
+            &GasSchedule::for_spec(SpecId::Berlin)
+        );
+        context.set_spec(SpecId::Byzantium);
+        assert_eq!(context.gas_schedule().sload, 200);
+
+        let schedule = GasSchedule {
+            sload: 1,
+            ..GasSchedule::for_spec(SpecId::Byzantium)
+        };
+        let context = context.with_gas_schedule(schedule.clone()).unwrap();
+        assert_eq!(context.spec(), SpecId::Byzantium);
+        assert_eq!(context.gas_schedule(), &schedule);
+
+        let schedule = GasSchedule {
+            memory_quadratic_divisor: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            Context::default().with_gas_schedule(schedule),
+            Err(Error::InvalidGasSchedule(_))
+        ));
+    }
+
+    #[test]
+    fn test_precompile_addresses() {
+        let precompiles = Precompiles::default();
+        assert_eq!(precompiles.addresses(SpecId::Frontier).count(), 4);
+        assert_eq!(
+            precompiles.addresses(SpecId::Prague).last(),
+            Some(Address::with_last_byte(0x11))
+        );
+        let osaka = precompiles.addresses(SpecId::Osaka).collect::<Vec<_>>();
+        assert_eq!(osaka.len(), 0x12);
+        assert_eq!(osaka.last(), Some(&P256VERIFY));
+    }
+
+    #[test]
+    fn test_effective_gas_price() {
+        let legacy = Transaction {
+            gas_price: U256::from(30),
+            ..Default::default()
+        };
+        assert_eq!(legacy.effective_gas_price(U256::from(10)), U256::from(30));
+
+        let transaction = Transaction {
+            max_fee_per_gas: Some(U256::from(30)),
+            max_priority_fee_per_gas: U256::from(5),
+            ..Default::default()
+        };
+        assert_eq!(
+            transaction.effective_gas_price(U256::from(10)),
+            U256::from(15)
+        );
+        assert_eq!(
+            transaction.effective_gas_price(U256::from(28)),
+            U256::from(30)
+        );
+    }
+
+    // Counts the queries made of the database it wraps
+    #[derive(Default)]
+    struct CountingDatabase {
+        database: InMemoryDatabase,
+        queries: Arc<AtomicUsize>,
+    }
+
+    impl Database for CountingDatabase {
+        fn basic(&self, address: &Address) -> Result<Option<AccountInfo>, Error> {
+            self.queries.fetch_add(1, Ordering::Relaxed);
+            self.database.basic(address)
+        }
+
+        fn code_by_hash(&self, code_hash: &B256) -> Result<Bytecode, Error> {
+            self.queries.fetch_add(1, Ordering::Relaxed);
+            self.database.code_by_hash(code_hash)
+        }
+
+        fn storage(&self, address: &Address, key: &Word) -> Result<Word, Error> {
+            self.queries.fetch_add(1, Ordering::Relaxed);
+            self.database.storage(address, key)
+        }
+
+        fn block_hash(&self, number: u64) -> Result<B256, Error> {
+            self.database.block_hash(number)
+        }
+    }
+
+    #[test]
+    fn test_state_caches_database() {
+        let address = Address::repeat_byte(0x11);
+        let account = Account {
+            balance: U256::from(1),
+            storage: [(U256::from(1), U256::from(2))].into_iter().collect(),
+            ..Default::default()
+        };
+        let database = CountingDatabase {
+            database: InMemoryDatabase::from([(address, account)].into_iter()),
+            ..Default::default()
+        };
+        let queries = database.queries.clone();
+        let mut state = State::new(database);
+        assert_eq!(queries.load(Ordering::Relaxed), 0);
+
+        for _ in 0..2 {
+            assert_eq!(state.account(&address).unwrap().balance, U256::from(1));
+            assert_eq!(
+                state.storage(&address, &U256::from(1)).unwrap(),
+                U256::from(2)
+            );
+            assert!(state.get(&Address::ZERO).unwrap().is_none());
+        }
+        // account and code, one slot and a missing account
+        assert_eq!(queries.load(Ordering::Relaxed), 4);
+
+        // writes shadow the database, even when they clear a slot
+        state
+            .account_mut(address)
+            .unwrap()
+            .storage
+            .insert(U256::from(1), U256::ZERO);
+        assert_eq!(state.storage(&address, &U256::from(1)).unwrap(), U256::ZERO);
+        state.account_mut(Address::ZERO).unwrap().balance = U256::from(3);
+        assert_eq!(
+            state.account(&Address::ZERO).unwrap().balance,
+            U256::from(3)
+        );
+        assert_eq!(queries.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn test_state_shared_between_threads() {
+        let address = Address::repeat_byte(0x11);
+        let account = Account {
+            balance: U256::from(1),
+            storage: [(U256::from(1), U256::from(2))].into_iter().collect(),
+            ..Default::default()
+        };
+        let state = State::from([(address, account)].into_iter());
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    assert_eq!(state.account(&address).unwrap().balance, U256::from(1));
+                    assert_eq!(
+                        state.storage(&address, &U256::from(1)).unwrap(),
+                        U256::from(2)
+                    );
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_state_root() {
+        assert_eq!(State::default().state_root().unwrap(), EMPTY_ROOT_HASH);
+
+        let first = Address::repeat_byte(0x11);
+        let second = Address::repeat_byte(0x22);
+        let account = |balance: u64, storage: &[(u64, u64)]| Account {
+            balance: U256::from(balance),
+            storage: storage
+                .iter()
+                .map(|(key, value)| (U256::from(*key), U256::from(*value)))
+                .collect(),
+            ..Default::default()
+        };
+        let mut state = State::from(
+            [
+                (first, account(1, &[(1, 2), (3, 4)])),
+                (second, account(5, &[])),
+            ]
+            .into_iter(),
+        );
+        assert_eq!(state.storage_root(&second).unwrap(), EMPTY_ROOT_HASH);
+        assert_ne!(state.storage_root(&first).unwrap(), EMPTY_ROOT_HASH);
+        let root = state.state_root().unwrap();
+
+        // Changes are reflected as if the state had been built with them, with cleared slots
+        // and removed accounts left out
+        let storage = &mut state.account_mut(first).unwrap().storage;
+        storage.insert(U256::from(3), U256::ZERO);
+        storage.insert(U256::from(5), U256::from(6));
+        state.remove(second);
+        state.account_mut(Address::ZERO).unwrap().balance = U256::from(7);
+        assert_ne!(state.state_root().unwrap(), root);
+        let expected = State::from(
+            [
+                (first, account(1, &[(1, 2), (5, 6)])),
+                (Address::ZERO, account(7, &[])),
+            ]
+            .into_iter(),
+        );
+        assert_eq!(
+            state.storage_root(&first).unwrap(),
+            expected.storage_root(&first).unwrap()
+        );
+        assert_eq!(state.state_root().unwrap(), expected.state_root().unwrap());
+    }
+
+    #[test]
+    fn test_proof() {
+        let address = Address::repeat_byte(0x11);
+        let storage = (1..20)
+            .map(|key| (U256::from(key), U256::from(key * 100)))
+            .collect();
+        let account = Account {
+            balance: U256::from(1),
+            nonce: 2,
+            storage,
+            code: "0x6001".try_into().unwrap(),
+        };
+        let others = (0..20).map(|i| {
+            let account = Account {
+                balance: U256::from(i),
+                ..Default::default()
+            };
+            (Address::with_last_byte(i), account)
+        });
+        let state = State::from([(address, account)].into_iter().chain(others));
+        let state_root = state.state_root().unwrap();
+
+        let keys = [U256::from(3), U256::from(50)];
+        let proof = state.proof(&address, &keys).unwrap();
+        assert_eq!(proof.balance, U256::from(1));
+        assert_eq!(proof.nonce, 2);
+        assert_eq!(proof.storage_hash, state.storage_root(&address).unwrap());
+        assert_eq!(proof.storage_proof[0].value, U256::from(300));
+        assert_eq!(proof.storage_proof[1].value, U256::ZERO);
+        proof.verify(state_root).unwrap();
+
+        let mut forged = proof.clone();
+        forged.balance = U256::from(2);
+        assert!(forged.verify(state_root).is_err());
+        let mut forged = proof.clone();
+        forged.storage_proof[1].value = U256::from(1);
+        assert!(forged.verify(state_root).is_err());
+
+        // A missing account is proven absent
+        let missing = state.proof(&Address::repeat_byte(0x99), &keys).unwrap();
+        assert_eq!(missing.storage_hash, EMPTY_ROOT_HASH);
+        missing.verify(state_root).unwrap();
+    }
+
+    #[test]
+    fn test_revert_to_checkpoint() {
+        let address = Address::repeat_byte(0x11);
+        let other = Address::repeat_byte(0x22);
+        let (key, one, two) = (Word::ZERO, Word::from(1), Word::from(2));
+        let mut context = Context::new(SpecId::default());
+        context.state = State::from(
+            [(
+                address,
+                Account {
+                    balance: U256::from(10),
+                    storage: [(key, one)].into_iter().collect(),
+                    ..Default::default()
+                },
+            )]
+            .into_iter(),
+        );
+        context.substate.set_transient_storage(address, key, one);
+        let outer = context.checkpoint();
+        context.set_storage(address, key, two).unwrap();
+        context.transfer(address, other, U256::from(3)).unwrap();
+
+        // a nested frame which fails
+        let inner = context.checkpoint();
+        context.substate.set_transient_storage(address, key, two);
+        context.substate.access_address(other);
+        context.set_storage(address, Word::from(1), two).unwrap();
+        context.revert(inner).unwrap();
+        assert_eq!(context.substate.transient_storage(&address, &key), one);
+        assert!(!context.substate.accessed_addresses.contains(&other));
+        assert_eq!(
+            context.state.storage(&address, &Word::from(1)).unwrap(),
+            Word::ZERO
+        );
+        assert_eq!(context.state.storage(&address, &key).unwrap(), two);
+        assert_eq!(context.original_storage(&address, &key).unwrap(), one);
+
+        context.revert(outer).unwrap();
+        assert_eq!(context.state.storage(&address, &key).unwrap(), one);
+        assert_eq!(
+            context.state.account(&address).unwrap().balance,
+            U256::from(10)
+        );
+        assert!(context.state.get(&other).unwrap().is_none());
+        assert_eq!(context.substate.transient_storage(&address, &key), one);
+    }
 }
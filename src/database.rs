@@ -0,0 +1,181 @@
+use crate::{
+    bytecode::Bytecode,
+    interpreter::{Account, Word},
+    primitives::{Address, B256, U256},
+    Error,
+};
+use alloy::consensus::constants::KECCAK_EMPTY;
+use std::collections::HashMap;
+
+/// The fields of an account other than its code and storage, which are looked up separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountInfo {
+    pub balance: U256,
+    pub nonce: u64,
+    pub code_hash: B256,
+}
+
+impl Default for AccountInfo {
+    fn default() -> Self {
+        Self {
+            balance: Default::default(),
+            nonce: Default::default(),
+            code_hash: KECCAK_EMPTY,
+        }
+    }
+}
+
+impl From<&Account> for AccountInfo {
+    fn from(account: &Account) -> Self {
+        Self {
+            balance: account.balance,
+            nonce: account.nonce,
+            code_hash: account.code.hash(),
+        }
+    }
+}
+
+/// Storage backing the state that execution runs against.
+///
+/// Queries are made lazily as execution touches each account and storage slot, and the
+/// results are cached by `context::State` for the remainder of its lifetime. Databases are
+/// shared between threads executing against the same state.
+pub trait Database: Send + Sync {
+    /// Returns the account at `address`, or `None` if it does not exist.
+    fn basic(&self, address: &Address) -> Result<Option<AccountInfo>, Error>;
+
+    /// Returns the code whose keccak256 hash is `code_hash`.
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Bytecode, Error>;
+
+    /// Returns the value stored at `key` by the account at `address`, zero if unset.
+    fn storage(&self, address: &Address, key: &Word) -> Result<Word, Error>;
+
+    /// Returns the hash of the block with the given `number`.
+    fn block_hash(&self, number: u64) -> Result<B256, Error>;
+
+    /// Returns the address of every account, as needed to compute the state root.
+    ///
+    /// Fails unless the database is able to enumerate its contents.
+    fn addresses(&self) -> Result<Vec<Address>, Error> {
+        Err(Error::Database("database cannot enumerate accounts".into()))
+    }
+
+    /// Returns the keys of the nonzero slots in the storage of the account at `address`.
+    ///
+    /// Fails unless the database is able to enumerate its contents.
+    fn storage_keys(&self, _address: &Address) -> Result<Vec<Word>, Error> {
+        Err(Error::Database("database cannot enumerate storage".into()))
+    }
+}
+
+/// A `Database` holding its entire state in memory.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryDatabase {
+    accounts: HashMap<Address, AccountInfo>,
+    code: HashMap<B256, Bytecode>,
+    storage: HashMap<Address, HashMap<Word, Word>>,
+    block_hashes: HashMap<u64, B256>,
+}
+
+impl InMemoryDatabase {
+    /// Insert `account` at `address`, replacing any existing account and its storage.
+    pub fn insert_account(&mut self, address: Address, account: Account) {
+        let info = AccountInfo::from(&account);
+        self.accounts.insert(address, info);
+        self.code.insert(info.code_hash, account.code);
+        self.storage.insert(address, account.storage);
+    }
+
+    pub fn insert_block_hash(&mut self, number: u64, hash: B256) {
+        self.block_hashes.insert(number, hash);
+    }
+}
+
+impl Database for InMemoryDatabase {
+    fn basic(&self, address: &Address) -> Result<Option<AccountInfo>, Error> {
+        Ok(self.accounts.get(address).copied())
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Bytecode, Error> {
+        if *code_hash == KECCAK_EMPTY {
+            return Ok(Default::default());
+        }
+        self.code
+            .get(code_hash)
+            .cloned()
+            .ok_or_else(|| Error::Database(format!("missing code with hash {code_hash}").into()))
+    }
+
+    fn storage(&self, address: &Address, key: &Word) -> Result<Word, Error> {
+        Ok(self
+            .storage
+            .get(address)
+            .and_then(|storage| storage.get(key))
+            .copied()
+            .unwrap_or_default())
+    }
+
+    // Blocks which were never inserted hash to zero
+    fn block_hash(&self, number: u64) -> Result<B256, Error> {
+        Ok(self.block_hashes.get(&number).copied().unwrap_or_default())
+    }
+
+    fn addresses(&self) -> Result<Vec<Address>, Error> {
+        Ok(self.accounts.keys().copied().collect())
+    }
+
+    fn storage_keys(&self, address: &Address) -> Result<Vec<Word>, Error> {
+        Ok(self
+            .storage
+            .get(address)
+            .into_iter()
+            .flatten()
+            .filter(|(_, value)| !value.is_zero())
+            .map(|(key, _)| *key)
+            .collect())
+    }
+}
+
+impl<T> From<T> for InMemoryDatabase
+where
+    T: Iterator<Item = (Address, Account)>,
+{
+    fn from(values: T) -> Self {
+        let mut database = Self::default();
+        for (address, account) in values {
+            database.insert_account(address, account);
+        }
+        database
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_in_memory_database() {
+        let address = Address::repeat_byte(0x11);
+        let account = Account {
+            balance: U256::from(1),
+            nonce: 2,
+            storage: [(U256::from(3), U256::from(4))].into_iter().collect(),
+            code: "0x6001".try_into().unwrap(),
+        };
+        let database = InMemoryDatabase::from([(address, account)].into_iter());
+
+        let info = database.basic(&address).unwrap().unwrap();
+        assert_eq!(info.balance, U256::from(1));
+        assert_eq!(info.nonce, 2);
+        let code = database.code_by_hash(&info.code_hash).unwrap();
+        assert_eq!(code.bytes(), &[0x60, 0x01]);
+        assert_eq!(
+            database.storage(&address, &U256::from(3)).unwrap(),
+            U256::from(4)
+        );
+        assert_eq!(database.storage(&address, &U256::ZERO).unwrap(), U256::ZERO);
+
+        assert!(database.basic(&Address::ZERO).unwrap().is_none());
+        assert!(database.code_by_hash(&B256::repeat_byte(1)).is_err());
+    }
+}
@@ -0,0 +1,912 @@
+use crate::{bytecode::parse_operation, operations::Operation};
+use std::collections::VecDeque;
+use thiserror::Error;
+
+const MAGIC: [u8; 2] = [0xef, 0x00];
+const VERSION: u8 = 0x01;
+const KIND_TYPE: u8 = 0x01;
+const KIND_CODE: u8 = 0x02;
+const KIND_CONTAINER: u8 = 0x03;
+const KIND_DATA: u8 = 0xff;
+const TERMINATOR: u8 = 0x00;
+const TYPE_SIZE: usize = 4;
+const MAXIMUM_CODE_SECTIONS: usize = 1024;
+const MAXIMUM_CONTAINER_SECTIONS: usize = 256;
+const MAXIMUM_INPUTS_OR_OUTPUTS: u8 = 0x7f;
+const NON_RETURNING: u8 = 0x80;
+const MAXIMUM_STACK_HEIGHT: usize = 1023;
+const MAXIMUM_STACK_SIZE: usize = 1024;
+// Bytes read by `DATALOADN`
+const DATA_WORD_SIZE: usize = 32;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum EofError {
+    #[error("container is truncated")]
+    Truncated,
+    #[error("container does not begin with the EOF magic")]
+    InvalidMagic,
+    #[error("unsupported EOF version {0}")]
+    UnsupportedVersion(u8),
+    #[error("invalid section headers")]
+    InvalidSectionHeaders,
+    #[error("invalid type of code section {0}")]
+    InvalidType(usize),
+    #[error("container has bytes after the data section")]
+    TrailingBytes,
+    #[error("undefined instruction at offset {offset} of code section {section}")]
+    UndefinedInstruction { section: usize, offset: usize },
+    #[error("truncated immediate at offset {offset} of code section {section}")]
+    TruncatedImmediate { section: usize, offset: usize },
+    #[error("invalid jump destination at offset {offset} of code section {section}")]
+    InvalidJumpDestination { section: usize, offset: usize },
+    #[error("invalid code section index at offset {offset} of code section {section}")]
+    InvalidCodeSectionIndex { section: usize, offset: usize },
+    #[error("invalid container section index at offset {offset} of code section {section}")]
+    InvalidContainerSectionIndex { section: usize, offset: usize },
+    #[error("data read out of bounds at offset {offset} of code section {section}")]
+    InvalidDataOffset { section: usize, offset: usize },
+    #[error("call to a non-returning section at offset {offset} of code section {section}")]
+    CallToNonReturning { section: usize, offset: usize },
+    #[error("code section {0} does not match its declared returning status")]
+    InvalidNonReturning(usize),
+    #[error("instruction at offset {offset} of code section {section} is not allowed in this kind of container")]
+    InvalidContainerKind { section: usize, offset: usize },
+    #[error("stack underflow at offset {offset} of code section {section}")]
+    StackUnderflow { section: usize, offset: usize },
+    #[error("stack overflow at offset {offset} of code section {section}")]
+    StackOverflow { section: usize, offset: usize },
+    #[error("inconsistent stack height at offset {offset} of code section {section}")]
+    InvalidStackHeight { section: usize, offset: usize },
+    #[error("maximum stack height of code section {0} does not match its type")]
+    MaxStackHeightMismatch(usize),
+    #[error("unreachable code at offset {offset} of code section {section}")]
+    UnreachableCode { section: usize, offset: usize },
+    #[error("code section {0} does not end in a terminating instruction")]
+    NoTerminatingInstruction(usize),
+    #[error("code section {0} is unreachable")]
+    UnreachableCodeSection(usize),
+    #[error("container section {0} is not referenced")]
+    UnreferencedContainer(usize),
+    #[error("container section {0} is referenced as both initcode and runtime code")]
+    AmbiguousContainer(usize),
+}
+
+/// Inputs, outputs and stack usage of a code section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypeSection {
+    pub inputs: u8,
+    /// `0x80` if the section never returns to its caller.
+    pub outputs: u8,
+    /// Most the stack grows beyond the section's inputs.
+    pub max_stack_increase: u16,
+}
+
+impl TypeSection {
+    pub fn is_returning(&self) -> bool {
+        self.outputs != NON_RETURNING
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeSection {
+    pub types: TypeSection,
+    pub code: Vec<u8>,
+    pub operations: Vec<Operation>,
+    // byte offset of each of `operations` within `code`
+    offsets: Vec<usize>,
+}
+
+impl CodeSection {
+    /// Returns the index into `operations` of the instruction starting at byte `offset`.
+    pub fn operation_index(&self, offset: usize) -> Option<usize> {
+        self.offsets.binary_search(&offset).ok()
+    }
+}
+
+/// A validated EVM Object Format container, per EIP-3540 and the EIPs extending it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Eof {
+    pub code_sections: Vec<CodeSection>,
+    pub container_sections: Vec<Eof>,
+    pub data: Vec<u8>,
+    /// The data size declared in the header, which exceeds the length of `data` in a container
+    /// yet to be deployed with auxiliary data.
+    pub data_size: u16,
+    bytes: Vec<u8>,
+}
+
+/// Containers are validated differently depending on how they are used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ContainerKind {
+    // Deployed code, which may not deploy other code
+    Runtime,
+    // Code run to deploy a container, which must do so rather than return
+    Initcode,
+}
+
+// Use of container sections by a code section.
+#[derive(Debug, Default)]
+struct References {
+    code_sections: Vec<usize>,
+    initcode: Vec<usize>,
+    runtime: Vec<usize>,
+    returns: bool,
+}
+
+struct Header {
+    code_sizes: Vec<usize>,
+    container_sizes: Vec<usize>,
+    data_size: u16,
+}
+
+impl Eof {
+    /// Returns `true` if `code` should be interpreted as an EOF container.
+    pub fn is_eof(code: &[u8]) -> bool {
+        code.starts_with(&MAGIC)
+    }
+
+    /// Decode and validate deployed code, as is done when it is deployed.
+    pub fn decode(input: &[u8]) -> Result<Self, EofError> {
+        Self::decode_as(input, ContainerKind::Runtime, false)
+    }
+
+    /// Decode and validate code run to deploy a container.
+    pub fn decode_initcode(input: &[u8]) -> Result<Self, EofError> {
+        Self::decode_as(input, ContainerKind::Initcode, false)
+    }
+
+    fn decode_as(
+        input: &[u8],
+        kind: ContainerKind,
+        allow_truncated_data: bool,
+    ) -> Result<Self, EofError> {
+        let mut reader = Reader { input, position: 0 };
+        let header = reader.header()?;
+
+        let mut types = vec![];
+        for section in 0..header.code_sizes.len() {
+            let types_section = TypeSection {
+                inputs: reader.u8()?,
+                outputs: reader.u8()?,
+                max_stack_increase: reader.u16()?,
+            };
+            let is_valid = types_section.inputs <= MAXIMUM_INPUTS_OR_OUTPUTS
+                && (types_section.outputs <= MAXIMUM_INPUTS_OR_OUTPUTS
+                    || !types_section.is_returning())
+                && types_section.max_stack_increase as usize <= MAXIMUM_STACK_HEIGHT
+                && (section != 0 || (types_section.inputs == 0 && !types_section.is_returning()));
+            if !is_valid {
+                return Err(EofError::InvalidType(section));
+            }
+            types.push(types_section);
+        }
+        let code = header
+            .code_sizes
+            .iter()
+            .map(|size| reader.bytes(*size))
+            .collect::<Result<Vec<_>, _>>()?;
+        let containers = header
+            .container_sizes
+            .iter()
+            .map(|size| reader.bytes(*size))
+            .collect::<Result<Vec<_>, _>>()?;
+        let data = reader.rest();
+        let data_size = header.data_size as usize;
+        if data.len() > data_size {
+            return Err(EofError::TrailingBytes);
+        }
+        if data.len() < data_size && !allow_truncated_data {
+            return Err(EofError::Truncated);
+        }
+
+        let validator = Validator {
+            types: &types,
+            container_count: containers.len(),
+            data_size,
+            kind,
+        };
+        let mut code_sections = vec![];
+        let mut references = vec![];
+        for (section, code) in code.into_iter().enumerate() {
+            let (operations, offsets, section_references) =
+                validator.validate_code(section, code)?;
+            code_sections.push(CodeSection {
+                types: types[section],
+                code: code.to_vec(),
+                operations,
+                offsets,
+            });
+            references.push(section_references);
+        }
+        validator.validate_references(&references)?;
+
+        let mut container_sections = vec![];
+        for (index, container) in containers.into_iter().enumerate() {
+            let as_initcode = references.iter().any(|r| r.initcode.contains(&index));
+            let as_runtime = references.iter().any(|r| r.runtime.contains(&index));
+            let container = match (as_initcode, as_runtime) {
+                (true, true) => return Err(EofError::AmbiguousContainer(index)),
+                (false, false) => return Err(EofError::UnreferencedContainer(index)),
+                (true, false) => Self::decode_as(container, ContainerKind::Initcode, false)?,
+                // Runtime code is deployed with auxiliary data appended to its data section
+                (false, true) => Self::decode_as(container, ContainerKind::Runtime, true)?,
+            };
+            container_sections.push(container);
+        }
+
+        Ok(Self {
+            code_sections,
+            container_sections,
+            data: data.to_vec(),
+            data_size: header.data_size,
+            bytes: input.to_vec(),
+        })
+    }
+
+    /// Returns the encoded container.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the encoding of this container with `aux_data` appended to its data section, as
+    /// deployed by `RETURNCONTRACT`, or `None` if the data section would then be shorter than
+    /// declared or longer than can be declared.
+    pub fn with_aux_data(&self, aux_data: &[u8]) -> Option<Vec<u8>> {
+        let data_size = self.data.len() + aux_data.len();
+        if data_size < self.data_size as usize {
+            return None;
+        }
+        let data_size = u16::try_from(data_size).ok()?;
+        // The data size is the last field of the header before its terminator
+        let mut header_size = 3 + 3 + 3 + 2 * self.code_sections.len() + 3 + 1;
+        if !self.container_sections.is_empty() {
+            header_size += 3 + 4 * self.container_sections.len();
+        }
+        let mut bytes = self.bytes.clone();
+        bytes[header_size - 3..header_size - 1].copy_from_slice(&data_size.to_be_bytes());
+        bytes.extend_from_slice(aux_data);
+        Some(bytes)
+    }
+}
+
+struct Reader<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, size: usize) -> Result<&'a [u8], EofError> {
+        let end = self.position + size;
+        let bytes = self
+            .input
+            .get(self.position..end)
+            .ok_or(EofError::Truncated)?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.input[self.position..];
+        self.position = self.input.len();
+        rest
+    }
+
+    fn u8(&mut self) -> Result<u8, EofError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, EofError> {
+        Ok(u16::from_be_bytes(
+            self.bytes(2)?.try_into().expect("2 bytes"),
+        ))
+    }
+
+    fn u32(&mut self) -> Result<u32, EofError> {
+        Ok(u32::from_be_bytes(
+            self.bytes(4)?.try_into().expect("4 bytes"),
+        ))
+    }
+
+    fn expect(&mut self, value: u8) -> Result<(), EofError> {
+        if self.u8()? != value {
+            return Err(EofError::InvalidSectionHeaders);
+        }
+        Ok(())
+    }
+
+    fn header(&mut self) -> Result<Header, EofError> {
+        if self.bytes(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+            return Err(EofError::InvalidMagic);
+        }
+        let version = self.u8()?;
+        if version != VERSION {
+            return Err(EofError::UnsupportedVersion(version));
+        }
+
+        self.expect(KIND_TYPE)?;
+        let types_size = self.u16()? as usize;
+        self.expect(KIND_CODE)?;
+        let code_count = self.u16()? as usize;
+        if code_count == 0
+            || code_count > MAXIMUM_CODE_SECTIONS
+            || types_size != code_count * TYPE_SIZE
+        {
+            return Err(EofError::InvalidSectionHeaders);
+        }
+        let code_sizes = (0..code_count)
+            .map(|_| self.u16().map(usize::from))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut container_sizes = vec![];
+        let mut kind = self.u8()?;
+        if kind == KIND_CONTAINER {
+            let container_count = self.u16()? as usize;
+            if container_count == 0 || container_count > MAXIMUM_CONTAINER_SECTIONS {
+                return Err(EofError::InvalidSectionHeaders);
+            }
+            for _ in 0..container_count {
+                container_sizes.push(self.u32()? as usize);
+            }
+            kind = self.u8()?;
+        }
+        if kind != KIND_DATA {
+            return Err(EofError::InvalidSectionHeaders);
+        }
+        let data_size = self.u16()?;
+        self.expect(TERMINATOR)?;
+
+        if code_sizes.contains(&0) || container_sizes.contains(&0) {
+            return Err(EofError::InvalidSectionHeaders);
+        }
+        Ok(Header {
+            code_sizes,
+            container_sizes,
+            data_size,
+        })
+    }
+}
+
+struct Validator<'a> {
+    types: &'a [TypeSection],
+    container_count: usize,
+    data_size: usize,
+    kind: ContainerKind,
+}
+
+impl Validator<'_> {
+    /// Decode the instructions of a code section, validating them and the stack heights
+    /// they produce.
+    fn validate_code(
+        &self,
+        section: usize,
+        code: &[u8],
+    ) -> Result<(Vec<Operation>, Vec<usize>, References), EofError> {
+        let mut operations = vec![];
+        let mut offsets = vec![];
+        let mut offset = 0;
+        while offset < code.len() {
+            let operation = decode_operation(&code[offset..])
+                .ok_or(EofError::UndefinedInstruction { section, offset })?;
+            if offset + operation.size() > code.len() {
+                return Err(EofError::TruncatedImmediate { section, offset });
+            }
+            operations.push(operation);
+            offsets.push(offset);
+            offset += operation.size();
+        }
+
+        let mut references = References::default();
+        let mut targets = vec![];
+        for (index, operation) in operations.iter().enumerate() {
+            let offset = offsets[index];
+            targets.push(self.validate_operation(
+                section,
+                code,
+                &offsets,
+                offset,
+                operation,
+                &mut references,
+            )?);
+        }
+        if self.types[section].is_returning() != references.returns {
+            return Err(EofError::InvalidNonReturning(section));
+        }
+        self.validate_stack(section, &operations, &offsets, &targets)?;
+        Ok((operations, offsets, references))
+    }
+
+    // Returns the indices of the operations `operation` may jump to.
+    fn validate_operation(
+        &self,
+        section: usize,
+        code: &[u8],
+        offsets: &[usize],
+        offset: usize,
+        operation: &Operation,
+        references: &mut References,
+    ) -> Result<Vec<usize>, EofError> {
+        use Operation::*;
+
+        let next = offset + operation.size();
+        let jump_target = |relative: i16| {
+            let target = next as isize + relative as isize;
+            usize::try_from(target)
+                .ok()
+                .and_then(|target| offsets.binary_search(&target).ok())
+                .ok_or(EofError::InvalidJumpDestination { section, offset })
+        };
+        let code_section = |index: u16| {
+            let index = index as usize;
+            self.types
+                .get(index)
+                .map(|types| (index, *types))
+                .ok_or(EofError::InvalidCodeSectionIndex { section, offset })
+        };
+        let container = |index: u8| {
+            let index = index as usize;
+            if index < self.container_count {
+                Ok(index)
+            } else {
+                Err(EofError::InvalidContainerSectionIndex { section, offset })
+            }
+        };
+        let invalid_kind = EofError::InvalidContainerKind { section, offset };
+
+        let targets = match operation {
+            RJump(relative) | RJumpI(relative) => vec![jump_target(*relative)?],
+            RJumpV(max_index) => (0..=*max_index as usize)
+                .map(|i| {
+                    let at = offset + 2 + 2 * i;
+                    jump_target(i16::from_be_bytes([code[at], code[at + 1]]))
+                })
+                .collect::<Result<_, _>>()?,
+            CallF(index) => {
+                let (index, types) = code_section(*index)?;
+                if !types.is_returning() {
+                    return Err(EofError::CallToNonReturning { section, offset });
+                }
+                references.code_sections.push(index);
+                vec![]
+            }
+            JumpF(index) => {
+                let (index, types) = code_section(*index)?;
+                if types.is_returning() {
+                    references.returns = true;
+                }
+                references.code_sections.push(index);
+                vec![]
+            }
+            RetF => {
+                references.returns = true;
+                vec![]
+            }
+            DataLoadN(data_offset) => {
+                if *data_offset as usize + DATA_WORD_SIZE > self.data_size {
+                    return Err(EofError::InvalidDataOffset { section, offset });
+                }
+                vec![]
+            }
+            EofCreate(index) => {
+                references.initcode.push(container(*index)?);
+                vec![]
+            }
+            ReturnContract(index) => {
+                if self.kind == ContainerKind::Runtime {
+                    return Err(invalid_kind);
+                }
+                references.runtime.push(container(*index)?);
+                vec![]
+            }
+            Stop | Return if self.kind == ContainerKind::Initcode => return Err(invalid_kind),
+            _ => vec![],
+        };
+        Ok(targets)
+    }
+
+    /// Validate stack heights per EIP-5450, tracking the range of heights possible at each
+    /// instruction in a single pass, as code can only be reached by forward jumps or by
+    /// backward jumps to instructions already visited.
+    fn validate_stack(
+        &self,
+        section: usize,
+        operations: &[Operation],
+        offsets: &[usize],
+        targets: &[Vec<usize>],
+    ) -> Result<(), EofError> {
+        use Operation::*;
+
+        let types = self.types[section];
+        let mut heights = vec![None; operations.len()];
+        heights[0] = Some((types.inputs as usize, types.inputs as usize));
+        let mut maximum_height = types.inputs as usize;
+
+        for (index, operation) in operations.iter().enumerate() {
+            let offset = offsets[index];
+            let (minimum, maximum) =
+                heights[index].ok_or(EofError::UnreachableCode { section, offset })?;
+
+            let (inputs, outputs) = match operation {
+                CallF(target) | JumpF(target) => {
+                    let target_types = self.types[*target as usize];
+                    if maximum + target_types.max_stack_increase as usize > MAXIMUM_STACK_SIZE {
+                        return Err(EofError::StackOverflow { section, offset });
+                    }
+                    let inputs = target_types.inputs as usize;
+                    if matches!(operation, JumpF(_)) && target_types.is_returning() {
+                        // The target returns to this section's caller in its place
+                        let outputs = target_types.outputs as usize;
+                        if types.outputs < target_types.outputs
+                            || minimum != maximum
+                            || maximum != types.outputs as usize + inputs - outputs
+                        {
+                            return Err(EofError::InvalidStackHeight { section, offset });
+                        }
+                    }
+                    let outputs = if target_types.is_returning() {
+                        target_types.outputs as usize
+                    } else {
+                        0
+                    };
+                    (inputs, outputs)
+                }
+                RetF => {
+                    let outputs = types.outputs as usize;
+                    if minimum != maximum || maximum != outputs {
+                        return Err(EofError::InvalidStackHeight { section, offset });
+                    }
+                    (outputs, 0)
+                }
+                operation => operation.stack_requirements(),
+            };
+            if minimum < inputs {
+                return Err(EofError::StackUnderflow { section, offset });
+            }
+            let height = (minimum - inputs + outputs, maximum - inputs + outputs);
+            maximum_height = maximum_height.max(height.1);
+            if maximum_height > MAXIMUM_STACK_HEIGHT {
+                return Err(EofError::StackOverflow { section, offset });
+            }
+
+            let mut successors = targets[index].clone();
+            if !is_terminating(operation) && !matches!(operation, RJump(_)) {
+                if index + 1 == operations.len() {
+                    return Err(EofError::NoTerminatingInstruction(section));
+                }
+                successors.push(index + 1);
+            }
+            for successor in successors {
+                match heights[successor] {
+                    // Backward jumps must agree exactly with the heights already visited
+                    _ if successor <= index => {
+                        if heights[successor] != Some(height) {
+                            return Err(EofError::InvalidStackHeight { section, offset });
+                        }
+                    }
+                    Some((minimum, maximum)) => {
+                        heights[successor] = Some((minimum.min(height.0), maximum.max(height.1)));
+                    }
+                    None => heights[successor] = Some(height),
+                }
+            }
+        }
+
+        if maximum_height - types.inputs as usize != types.max_stack_increase as usize {
+            return Err(EofError::MaxStackHeightMismatch(section));
+        }
+        Ok(())
+    }
+
+    // Every code section must be reachable from the first through `CALLF` and `JUMPF`.
+    fn validate_references(&self, references: &[References]) -> Result<(), EofError> {
+        let mut reached = vec![false; references.len()];
+        reached[0] = true;
+        let mut queue = VecDeque::from([0]);
+        while let Some(section) = queue.pop_front() {
+            for target in &references[section].code_sections {
+                if !reached[*target] {
+                    reached[*target] = true;
+                    queue.push_back(*target);
+                }
+            }
+        }
+        match reached.iter().position(|reached| !reached) {
+            Some(section) => Err(EofError::UnreachableCodeSection(section)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn is_terminating(operation: &Operation) -> bool {
+    use Operation::*;
+
+    matches!(
+        operation,
+        Stop | Return | Revert | Invalid | RetF | JumpF(_) | ReturnContract(_)
+    )
+}
+
+// Decodes the operation at the start of `code`, or `None` if it is not valid in EOF code.
+// Immediates running past the end of the code are padded with zeros.
+fn decode_operation(code: &[u8]) -> Option<Operation> {
+    use Operation::*;
+
+    let byte = |at: usize| code.get(at).copied().unwrap_or_default();
+    let u16 = || u16::from_be_bytes([byte(1), byte(2)]);
+    let operation = match code[0] {
+        0xd0 => DataLoad,
+        0xd1 => DataLoadN(u16()),
+        0xd2 => DataSize,
+        0xd3 => DataCopy,
+        0xe0 => RJump(u16() as i16),
+        0xe1 => RJumpI(u16() as i16),
+        0xe2 => RJumpV(byte(1)),
+        0xe3 => CallF(u16()),
+        0xe4 => RetF,
+        0xe5 => JumpF(u16()),
+        0xe6 => DupN(byte(1)),
+        0xe7 => SwapN(byte(1)),
+        0xe8 => Exchange(byte(1)),
+        0xec => EofCreate(byte(1)),
+        0xee => ReturnContract(byte(1)),
+        0xf7 => ReturnDataLoad,
+        0xf8 => ExtCall,
+        0xf9 => ExtDelegateCall,
+        0xfb => ExtStaticCall,
+        opcode => parse_operation(opcode, &mut code[1..].iter()),
+    };
+    // EIP-3670 and EIP-7069 remove operations which inspect code or gas, or use dynamic jumps
+    let is_removed = matches!(
+        operation,
+        Unknown(_)
+            | CallCode
+            | SelfDestruct
+            | Jump
+            | JumpI
+            | Pc
+            | Create
+            | Create2
+            | CodeSize
+            | CodeCopy
+            | ExtCodeSize
+            | ExtCodeCopy
+            | ExtCodeHash
+            | Gas
+            | Call
+            | StaticCall
+            | DelegateCall
+    );
+    (!is_removed).then_some(operation)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Assemble a container from the types and hex encoded code of its code sections.
+    fn container(sections: &[(u8, u8, u16, &str)], containers: &[&[u8]], data: &[u8]) -> Vec<u8> {
+        let code = sections
+            .iter()
+            .map(|(.., code)| hex::decode(code).unwrap())
+            .collect::<Vec<_>>();
+        let mut bytes = vec![0xef, 0x00, VERSION, KIND_TYPE];
+        bytes.extend(((sections.len() * TYPE_SIZE) as u16).to_be_bytes());
+        bytes.push(KIND_CODE);
+        bytes.extend((sections.len() as u16).to_be_bytes());
+        for code in &code {
+            bytes.extend((code.len() as u16).to_be_bytes());
+        }
+        if !containers.is_empty() {
+            bytes.push(KIND_CONTAINER);
+            bytes.extend((containers.len() as u16).to_be_bytes());
+            for container in containers {
+                bytes.extend((container.len() as u32).to_be_bytes());
+            }
+        }
+        bytes.push(KIND_DATA);
+        bytes.extend((data.len() as u16).to_be_bytes());
+        bytes.push(TERMINATOR);
+        for (inputs, outputs, max_stack_increase, _) in sections {
+            bytes.extend([*inputs, *outputs]);
+            bytes.extend(max_stack_increase.to_be_bytes());
+        }
+        for code in code {
+            bytes.extend(code);
+        }
+        for container in containers {
+            bytes.extend(*container);
+        }
+        bytes.extend(data);
+        bytes
+    }
+
+    #[test]
+    fn test_decode_valid_containers() {
+        let input = hex::decode("ef00010100040200010001ff00000000800000fe").unwrap();
+        let eof = Eof::decode(&input).unwrap();
+        assert_eq!(eof.code_sections[0].operations, vec![Operation::Invalid]);
+        assert_eq!(input, container(&[(0, 0x80, 0, "fe")], &[], &[]));
+
+        // CALLF 1, DATALOADN 0, STOP; PUSH0, RETF
+        let input = container(
+            &[(0, 0x80, 2, "e30001d1000000"), (0, 1, 1, "5fe4")],
+            &[],
+            &[0xaa; 32],
+        );
+        let eof = Eof::decode(&input).unwrap();
+        assert_eq!(eof.code_sections.len(), 2);
+        assert_eq!(
+            eof.code_sections[0].operations,
+            vec![
+                Operation::CallF(1),
+                Operation::DataLoadN(0),
+                Operation::Stop
+            ]
+        );
+        assert_eq!(eof.data, vec![0xaa; 32]);
+
+        // PUSH0, RJUMPI +1, PUSH0, RJUMP -7 (a loop with a branch of differing height)
+        let input = container(&[(0, 0x80, 1, "5fe100015fe0fff9")], &[], &[]);
+        assert_eq!(
+            Eof::decode(&input),
+            Err(EofError::InvalidStackHeight {
+                section: 0,
+                offset: 5
+            })
+        );
+        // PUSH0, POP, RJUMP -5
+        let input = container(&[(0, 0x80, 1, "5f50e0fffb")], &[], &[]);
+        assert!(Eof::decode(&input).is_ok());
+    }
+
+    #[test]
+    fn test_decode_subcontainers() {
+        let runtime = container(&[(0, 0x80, 0, "00")], &[], &[]);
+        // PUSH0, PUSH0, RETURNCONTRACT 0
+        let initcode = container(&[(0, 0x80, 2, "5f5fee00")], &[&runtime], &[]);
+        // PUSH0 x4, EOFCREATE 0, STOP
+        let input = container(&[(0, 0x80, 4, "5f5f5f5fec0000")], &[&initcode], &[]);
+        let eof = Eof::decode(&input).unwrap();
+        assert_eq!(eof.container_sections[0].container_sections.len(), 1);
+
+        // Initcode may not be deployed code, nor stop
+        assert!(matches!(
+            Eof::decode(&initcode),
+            Err(EofError::InvalidContainerKind { .. })
+        ));
+        assert!(matches!(
+            Eof::decode_initcode(&runtime),
+            Err(EofError::InvalidContainerKind { .. })
+        ));
+
+        let input = container(&[(0, 0x80, 0, "00")], &[&runtime], &[]);
+        assert_eq!(Eof::decode(&input), Err(EofError::UnreferencedContainer(0)));
+    }
+
+    #[test]
+    fn test_with_aux_data() {
+        let runtime = container(&[(0, 0x80, 0, "00")], &[], &[0xaa]);
+        let eof = Eof::decode(&runtime).unwrap();
+        assert_eq!(eof.bytes(), runtime.as_slice());
+        assert_eq!(
+            eof.with_aux_data(&[0xbb]),
+            Some(container(&[(0, 0x80, 0, "00")], &[], &[0xaa, 0xbb]))
+        );
+
+        // a runtime subcontainer declaring two bytes of data but holding one
+        let mut truncated = container(&[(0, 0x80, 0, "00")], &[], &[0xaa, 0xbb]);
+        truncated.pop();
+        let eof = Eof::decode_as(&truncated, ContainerKind::Runtime, true).unwrap();
+        assert_eq!(eof.with_aux_data(&[]), None);
+        assert_eq!(
+            eof.with_aux_data(&[0xcc]),
+            Some(container(&[(0, 0x80, 0, "00")], &[], &[0xaa, 0xcc]))
+        );
+        assert_eq!(eof.with_aux_data(&[0; 0x10000]), None);
+    }
+
+    #[test]
+    fn test_decode_invalid_containers() {
+        let cases = [
+            (
+                container(&[(0, 0x80, 0, "0c")], &[], &[]),
+                EofError::UndefinedInstruction {
+                    section: 0,
+                    offset: 0,
+                },
+            ),
+            // CODESIZE is removed from EOF
+            (
+                container(&[(0, 0x80, 1, "3800")], &[], &[]),
+                EofError::UndefinedInstruction {
+                    section: 0,
+                    offset: 0,
+                },
+            ),
+            (
+                container(&[(0, 0x80, 1, "6101")], &[], &[]),
+                EofError::TruncatedImmediate {
+                    section: 0,
+                    offset: 0,
+                },
+            ),
+            (
+                container(&[(0, 0x80, 0, "0100")], &[], &[]),
+                EofError::StackUnderflow {
+                    section: 0,
+                    offset: 0,
+                },
+            ),
+            (
+                container(&[(0, 0x80, 2, "5f00")], &[], &[]),
+                EofError::MaxStackHeightMismatch(0),
+            ),
+            (
+                container(&[(0, 0x80, 0, "0000")], &[], &[]),
+                EofError::UnreachableCode {
+                    section: 0,
+                    offset: 1,
+                },
+            ),
+            (
+                container(&[(0, 0x80, 1, "5f")], &[], &[]),
+                EofError::NoTerminatingInstruction(0),
+            ),
+            (
+                container(&[(0, 0x80, 0, "e0000100")], &[], &[]),
+                EofError::InvalidJumpDestination {
+                    section: 0,
+                    offset: 0,
+                },
+            ),
+            (
+                container(&[(0, 0x80, 0, "e3000100")], &[], &[]),
+                EofError::InvalidCodeSectionIndex {
+                    section: 0,
+                    offset: 0,
+                },
+            ),
+            (
+                container(&[(0, 0x80, 1, "d1000000")], &[], &[0; 31]),
+                EofError::InvalidDataOffset {
+                    section: 0,
+                    offset: 0,
+                },
+            ),
+            (
+                container(&[(0, 0x80, 0, "00"), (0, 0x80, 0, "00")], &[], &[]),
+                EofError::UnreachableCodeSection(1),
+            ),
+            // JUMPF to a returning section from a non-returning one
+            (
+                container(&[(0, 0x80, 0, "e50001"), (0, 0, 0, "e4")], &[], &[]),
+                EofError::InvalidNonReturning(0),
+            ),
+            (
+                container(&[(0, 0x80, 0, "e3000100"), (0, 0, 0, "00")], &[], &[]),
+                EofError::InvalidNonReturning(1),
+            ),
+            (
+                container(&[(0, 0, 0, "00")], &[], &[]),
+                EofError::InvalidType(0),
+            ),
+        ];
+        for (input, error) in cases {
+            assert_eq!(Eof::decode(&input), Err(error));
+        }
+
+        let input = container(&[(0, 0x80, 0, "00")], &[], &[1]);
+        assert_eq!(
+            Eof::decode(&input[..input.len() - 1]),
+            Err(EofError::Truncated)
+        );
+        assert_eq!(
+            Eof::decode(&[input, vec![0]].concat()),
+            Err(EofError::TrailingBytes)
+        );
+        assert_eq!(
+            Eof::decode(&[0xef, 0x00, 0x02]),
+            Err(EofError::UnsupportedVersion(2))
+        );
+        assert_eq!(Eof::decode(&[0xef]), Err(EofError::InvalidMagic));
+    }
+}
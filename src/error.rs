@@ -1,4 +1,8 @@
-use crate::interpreter::StackError;
+use crate::{
+    eof::EofError,
+    interpreter::{HaltReason, StackError},
+    processor::InvalidTransaction,
+};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -7,12 +11,34 @@ pub enum Error {
     Hex(#[from] hex::FromHexError),
     #[error("expected opcode at position but none was found")]
     InvalidCode,
-    #[error("encountered an unknown opcode {0}")]
-    InvalidOpcode(u8),
-    #[error(transparent)]
-    Stack(#[from] StackError),
-    #[error("gas used overflowed the maximum amount")]
-    GasUsedOverflow,
+    #[error("execution halted: {0}")]
+    Halt(#[from] HaltReason),
     #[error("expected frame but there was none")]
     FrameUnderflow,
+    #[error(transparent)]
+    InvalidTransaction(#[from] InvalidTransaction),
+    #[error(transparent)]
+    TransactionDecoding(#[from] alloy::eips::eip2718::Eip2718Error),
+    #[error("unsupported transaction type")]
+    UnsupportedTransactionType,
+    #[error("contract creation transactions can only be executed with `transact`")]
+    ContractCreationUnsupported,
+    #[error("transaction is missing or has an invalid `{0}` for encoding")]
+    UnencodableTransaction(&'static str),
+    #[error("invalid EOF container: {0}")]
+    Eof(#[from] EofError),
+    #[error("invalid gas schedule: `{0}` must be nonzero")]
+    InvalidGasSchedule(&'static str),
+    #[error("invalid Merkle proof: {0}")]
+    InvalidProof(&'static str),
+    #[error("database error: {0}")]
+    Database(Box<dyn std::error::Error + Send + Sync>),
+    #[error("failed to spawn a thread to execute on: {0}")]
+    Spawn(std::io::Error),
+}
+
+impl From<StackError> for Error {
+    fn from(err: StackError) -> Self {
+        Self::Halt(err.into())
+    }
 }
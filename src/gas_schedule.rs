@@ -1,37 +1,254 @@
-pub const G_ZERO: usize = 0;
-pub const G_JUMPDEST: usize = 1;
-pub const G_BASE: usize = 2;
-pub const G_VERY_LOW: usize = 3;
-pub const G_LOW: usize = 5;
-pub const G_MID: usize = 8;
-pub const G_HIGH: usize = 10;
-pub const G_WARM_ACCESS: usize = 100;
-pub const G_ACCESS_LIST_ADDRESS: usize = 2400;
-pub const G_ACCESS_LIST_STORAGE: usize = 1900;
-pub const G_COLD_ACCOUNT_ACCESS: usize = 2600;
-pub const G_COLD_SLOAD: usize = 2100;
-pub const G_SSET: usize = 20000;
-pub const G_SRESET: usize = 2900;
-pub const G_SELF_DESTRUCT: usize = 5000;
-pub const G_CREATE: usize = 32000;
-pub const G_CODE_DEPOSIT: usize = 200;
-pub const G_INIT_CODE_WORD: usize = 2;
-pub const G_CALL_VALUE: usize = 9000;
-pub const G_CALL_STIPEND: usize = 2300;
-pub const G_NEW_ACCOUNT: usize = 25000;
-pub const G_EXP: usize = 10;
-pub const G_EXP_BYTE: usize = 50;
-pub const G_MEMORY: usize = 3;
-pub const G_TX_CREATE: usize = 32000;
-pub const G_TX_DATA_ZERO: usize = 4;
-pub const G_TX_DATA_NONZERO: usize = 16;
-pub const G_TRANSACTION: usize = 21000;
-pub const G_LOG: usize = 375;
-pub const G_LOG_DATA: usize = 8;
-pub const G_LOG_TOPIC: usize = 375;
-pub const G_KECCAK256: usize = 30;
-pub const G_KECCAK256_WORD: usize = 6;
-pub const G_COPY: usize = 3;
-pub const G_BLOCKHASH: usize = 20;
-
-pub const R_SCLEAR: usize = 2900;
+use crate::{spec::SpecId, Error};
+
+/// Gas costs and refunds applied during execution.
+///
+/// Use `GasSchedule::for_spec` to get the schedule of a given fork; individual fields can be
+/// overridden to experiment with repricings, e.g.
+/// `GasSchedule { sload: 200, ..GasSchedule::for_spec(SpecId::Cancun) }`, and installed with
+/// `Context::with_gas_schedule`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasSchedule {
+    pub zero: u64,
+    pub jumpdest: u64,
+    pub base: u64,
+    pub very_low: u64,
+    pub low: u64,
+    pub mid: u64,
+    pub high: u64,
+    pub warm_access: u64,
+    pub access_list_address: u64,
+    pub access_list_storage: u64,
+    pub cold_account_access: u64,
+    pub cold_sload: u64,
+    pub sset: u64,
+    pub sreset: u64,
+    pub self_destruct: u64,
+    pub create: u64,
+    pub code_deposit: u64,
+    pub init_code_word: u64,
+    pub call_value: u64,
+    pub call_stipend: u64,
+    pub new_account: u64,
+    pub exp: u64,
+    pub exp_byte: u64,
+    pub memory: u64,
+    pub memory_quadratic_divisor: u64,
+    pub tx_create: u64,
+    pub tx_data_zero: u64,
+    pub tx_data_nonzero: u64,
+    // EIP-7623 cost per calldata token of the least a transaction may be charged
+    pub floor_data_gas: u64,
+    pub transaction: u64,
+    pub log: u64,
+    pub log_data: u64,
+    pub log_topic: u64,
+    pub keccak256: u64,
+    pub keccak256_word: u64,
+    pub copy: u64,
+    pub blockhash: u64,
+    pub rjumpi: u64,
+    pub data_load: u64,
+    // Static costs of state access operations; warm access costs after EIP-2929
+    pub balance: u64,
+    pub ext_code: u64,
+    pub ext_code_hash: u64,
+    pub sload: u64,
+    pub call: u64,
+    // Blob gas, per EIP-4844
+    pub blob_gas_per_blob: u64,
+    pub max_blobs_per_block: u64,
+    pub blob_base_fee_update_fraction: u64,
+    // EIP-7702 cost per authorization, partially refunded if the authority already exists
+    pub authorization: u64,
+    pub authorization_base: u64,
+    // Refunds
+    pub sclear_refund: u64,
+    pub self_destruct_refund: u64,
+    /// Maximum fraction of gas used that may be refunded, expressed as a divisor.
+    pub max_refund_quotient: u64,
+}
+
+impl Default for GasSchedule {
+    fn default() -> Self {
+        Self::for_spec(SpecId::default())
+    }
+}
+
+impl GasSchedule {
+    pub fn for_spec(spec: SpecId) -> Self {
+        let mut schedule = Self::frontier();
+        if spec.is_enabled_in(SpecId::Homestead) {
+            schedule.tx_create = 32000;
+        }
+        if spec.is_enabled_in(SpecId::TangerineWhistle) {
+            schedule.balance = 400;
+            schedule.ext_code = 700;
+            schedule.sload = 200;
+            schedule.call = 700;
+            schedule.self_destruct = 5000;
+        }
+        if spec.is_enabled_in(SpecId::SpuriousDragon) {
+            schedule.exp_byte = 50;
+        }
+        if spec.is_enabled_in(SpecId::Constantinople) {
+            schedule.ext_code_hash = 400;
+        }
+        if spec.is_enabled_in(SpecId::Istanbul) {
+            schedule.balance = 700;
+            schedule.ext_code_hash = 700;
+            schedule.sload = 800;
+            schedule.tx_data_nonzero = 16;
+        }
+        if spec.has_eip2929() {
+            schedule.balance = schedule.warm_access;
+            schedule.ext_code = schedule.warm_access;
+            schedule.ext_code_hash = schedule.warm_access;
+            schedule.sload = schedule.warm_access;
+            schedule.call = schedule.warm_access;
+            schedule.sreset = 5000 - schedule.cold_sload;
+        }
+        if spec.has_eip3529() {
+            schedule.sclear_refund = schedule.sreset + schedule.access_list_storage;
+            schedule.self_destruct_refund = 0;
+            schedule.max_refund_quotient = 5;
+        }
+        if spec.is_enabled_in(SpecId::Shanghai) {
+            schedule.init_code_word = 2;
+        }
+        if spec.is_enabled_in(SpecId::Cancun) {
+            schedule.blob_gas_per_blob = 1 << 17;
+            schedule.max_blobs_per_block = 6;
+            schedule.blob_base_fee_update_fraction = 3_338_477;
+        }
+        if spec.is_enabled_in(SpecId::Prague) {
+            // EIP-7691
+            schedule.max_blobs_per_block = 9;
+            schedule.blob_base_fee_update_fraction = 5_007_716;
+            schedule.authorization = 25000;
+            schedule.authorization_base = 12500;
+            schedule.floor_data_gas = 10;
+        }
+        schedule
+    }
+
+    fn frontier() -> Self {
+        Self {
+            zero: 0,
+            jumpdest: 1,
+            base: 2,
+            very_low: 3,
+            low: 5,
+            mid: 8,
+            high: 10,
+            warm_access: 100,
+            access_list_address: 2400,
+            access_list_storage: 1900,
+            cold_account_access: 2600,
+            cold_sload: 2100,
+            sset: 20000,
+            sreset: 5000,
+            self_destruct: 0,
+            create: 32000,
+            code_deposit: 200,
+            init_code_word: 0,
+            call_value: 9000,
+            call_stipend: 2300,
+            new_account: 25000,
+            exp: 10,
+            exp_byte: 10,
+            memory: 3,
+            memory_quadratic_divisor: 512,
+            tx_create: 0,
+            tx_data_zero: 4,
+            tx_data_nonzero: 68,
+            floor_data_gas: 0,
+            transaction: 21000,
+            log: 375,
+            log_data: 8,
+            log_topic: 375,
+            keccak256: 30,
+            keccak256_word: 6,
+            copy: 3,
+            blockhash: 20,
+            rjumpi: 4,
+            data_load: 4,
+            balance: 20,
+            ext_code: 20,
+            ext_code_hash: 0,
+            sload: 50,
+            call: 40,
+            blob_gas_per_blob: 0,
+            max_blobs_per_block: 0,
+            blob_base_fee_update_fraction: 0,
+            authorization: 0,
+            authorization_base: 0,
+            sclear_refund: 15000,
+            self_destruct_refund: 24000,
+            max_refund_quotient: 2,
+        }
+    }
+
+    /// Check that the schedule can be executed against, as overridden fields may leave it
+    /// dividing by zero.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.memory_quadratic_divisor == 0 {
+            return Err(Error::InvalidGasSchedule("memory_quadratic_divisor"));
+        }
+        if self.max_refund_quotient == 0 {
+            return Err(Error::InvalidGasSchedule("max_refund_quotient"));
+        }
+        Ok(())
+    }
+
+    /// Returns the total cost of `word_count` words of memory, saturating rather than
+    /// overflowing under an overridden schedule.
+    pub fn memory_cost(&self, word_count: usize) -> u64 {
+        if word_count == 0 {
+            return 0;
+        }
+        let word_count = word_count as u64;
+        let quadratic = (word_count * word_count)
+            .checked_div(self.memory_quadratic_divisor)
+            .unwrap_or(u64::MAX);
+        self.memory
+            .saturating_mul(word_count)
+            .saturating_add(quadratic)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_schedule_by_spec() {
+        let schedule = GasSchedule::for_spec(SpecId::Cancun);
+        assert_eq!(schedule.sload, 100);
+        assert_eq!(schedule.sreset, 2900);
+        assert_eq!(schedule.sclear_refund, 4800);
+        assert_eq!(schedule.tx_data_nonzero, 16);
+        assert_eq!(schedule.floor_data_gas, 0);
+        assert_eq!(GasSchedule::for_spec(SpecId::Prague).floor_data_gas, 10);
+
+        let schedule = GasSchedule::for_spec(SpecId::Byzantium);
+        assert_eq!(schedule.sload, 200);
+        assert_eq!(schedule.balance, 400);
+        assert_eq!(schedule.sclear_refund, 15000);
+        assert_eq!(schedule.tx_data_nonzero, 68);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(GasSchedule::default().validate().is_ok());
+        let schedule = GasSchedule {
+            memory_quadratic_divisor: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            schedule.validate(),
+            Err(Error::InvalidGasSchedule("memory_quadratic_divisor"))
+        ));
+        // an unvalidated schedule charges everything it can for memory instead of panicking
+        assert_eq!(schedule.memory_cost(2), u64::MAX);
+    }
+}
@@ -0,0 +1,414 @@
+use crate::{
+    bytecode::Bytecode,
+    context::{Block, Transaction},
+    eof::Eof,
+    gas_schedule::GasSchedule,
+    interpreter::{Account, ExecutionResult, Frame, HaltReason, Word, MAXIMUM_CODE_SIZE},
+    primitives::{Address, Log, B256, U256},
+    spec::SpecId,
+    Context, Error,
+};
+use alloy::primitives::keccak256;
+use std::sync::Arc;
+
+/// How the code run for a `Message` relates to the frame which sent it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CallKind {
+    /// Run the code of the recipient against its own storage and balance, as `CALL` and
+    /// `STATICCALL` do.
+    #[default]
+    Call,
+    /// Run the code of another account against the storage of the sender, keeping the
+    /// sender's caller and value.
+    DelegateCall,
+    /// Run the code of another account against the storage of the sender, as a call from the
+    /// sender to itself.
+    CallCode,
+    /// Create an account at an address derived from the sender and its nonce, running the
+    /// input as initcode and installing the code it returns.
+    Create,
+    /// As `Create`, but at an address derived from the sender, `salt` and the initcode.
+    Create2 { salt: Word },
+    /// Create an account at an address derived from the sender and `salt`, running the
+    /// EOF initcontainer in `Message::code` and installing the container it returns, per
+    /// EIP-7620.
+    EofCreate { salt: Word },
+}
+
+impl CallKind {
+    /// Returns `true` if the message creates an account.
+    pub fn is_create(&self) -> bool {
+        matches!(
+            self,
+            Self::Create | Self::Create2 { .. } | Self::EofCreate { .. }
+        )
+    }
+}
+
+/// A request from a frame to run the code of an account in a new frame, as an EVMC message.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub kind: CallKind,
+    /// Whether the new frame, and any frame it calls, is prevented from modifying state.
+    pub is_static: bool,
+    /// The number of frames above the new frame, the transaction's frame being at depth 0.
+    pub depth: usize,
+    pub gas_limit: u64,
+    /// The account whose storage and balance the new frame runs against, unused when creating
+    /// an account.
+    pub recipient: Address,
+    pub caller: Address,
+    /// The value transferred to the recipient, or for `CallKind::DelegateCall` the value of
+    /// the sending frame, which is not transferred.
+    pub value: U256,
+    /// The calldata of the new frame, or the initcode run for `CallKind::Create` and
+    /// `CallKind::Create2`.
+    pub input: Vec<u8>,
+    /// The initcontainer run for `CallKind::EofCreate`.
+    pub code: Vec<u8>,
+    /// The account whose code runs, or which delegates to the account whose code runs per
+    /// EIP-7702, unused when creating an account.
+    pub code_address: Address,
+}
+
+/// The result of running the frame for a `Message`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CallOutcome {
+    pub result: ExecutionResult,
+    /// The gas refunded by the frame, which unlike that in `result` is negative when it undid
+    /// refunded storage changes of the frames above it.
+    pub gas_refunded: i64,
+    /// The address of the account created for a message creating one, if creation succeeded.
+    pub created_address: Option<Address>,
+}
+
+/// The effect of writing a storage slot, from which `SSTORE` is priced, as the EVMC
+/// `evmc_storage_status`.
+///
+/// Each is named for how the write changes the slot relative to its value at the start of the
+/// transaction, the original value, where X is the original value, Y and Z other nonzero values
+/// and 0 zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageStatus {
+    /// Any write not covered below, including writing the current value.
+    Assigned,
+    /// 0 -> 0 -> Z
+    Added,
+    /// X -> X -> 0
+    Deleted,
+    /// X -> X -> Z
+    Modified,
+    /// X -> 0 -> Z
+    DeletedAdded,
+    /// X -> Y -> 0
+    ModifiedDeleted,
+    /// X -> 0 -> X
+    DeletedRestored,
+    /// 0 -> Y -> 0
+    AddedDeleted,
+    /// X -> Y -> X
+    ModifiedRestored,
+}
+
+impl StorageStatus {
+    /// Returns the effect of writing `value` to a slot holding `current`, which held
+    /// `original` at the start of the transaction.
+    pub fn new(original: Word, current: Word, value: Word) -> Self {
+        if current == value {
+            return Self::Assigned;
+        }
+        if original == current {
+            return match (original.is_zero(), value.is_zero()) {
+                (true, _) => Self::Added,
+                (false, true) => Self::Deleted,
+                (false, false) => Self::Modified,
+            };
+        }
+        match (original.is_zero(), current.is_zero(), value.is_zero()) {
+            (false, true, _) if value == original => Self::DeletedRestored,
+            (false, true, _) => Self::DeletedAdded,
+            (false, false, true) => Self::ModifiedDeleted,
+            (false, false, false) if value == original => Self::ModifiedRestored,
+            (true, false, true) => Self::AddedDeleted,
+            _ => Self::Assigned,
+        }
+    }
+}
+
+/// The environment operations execute against.
+///
+/// Operations read and write accounts, storage and block information through this trait as
+/// they execute, so execution can be embedded somewhere other than a `Context`, e.g. to mock
+/// state or change the behaviour of a rollup. As with an EVMC host, undoing the changes made
+/// by a frame which fails is left to the host.
+pub trait Host {
+    fn spec(&self) -> SpecId;
+
+    fn gas_schedule(&self) -> &GasSchedule;
+
+    fn chain_id(&self) -> u64;
+
+    fn block(&self) -> &Block;
+
+    fn transaction(&self) -> &Transaction;
+
+    /// Returns the block base fee, or zero before EIP-1559 was activated.
+    fn base_fee(&self) -> U256;
+
+    /// Returns the block blob base fee, or zero before EIP-4844 was activated.
+    fn blob_base_fee(&self) -> U256;
+
+    /// Returns the hash of the block with the given `number`.
+    fn block_hash(&self, number: u64) -> Result<B256, Error>;
+
+    /// Returns the account at `address`, which is empty if it does not exist.
+    fn load_account(&self, address: &Address) -> Result<Arc<Account>, Error>;
+
+    fn balance(&self, address: &Address) -> Result<U256, Error> {
+        Ok(self.load_account(address)?.balance)
+    }
+
+    /// Marks `address` as accessed, returning `true` if it had not been in this transaction.
+    fn access_account(&mut self, address: Address) -> bool;
+
+    /// Marks `key` in the storage of `address` as accessed, returning `true` if it had not
+    /// been in this transaction.
+    fn access_storage_key(&mut self, address: Address, key: Word) -> bool;
+
+    /// Returns the value stored at `key` by the account at `address`.
+    fn load_storage(&self, address: &Address, key: &Word) -> Result<Word, Error>;
+
+    /// Set the value stored at `key` by the account at `address`, returning the effect of the
+    /// write relative to the value at the start of the transaction.
+    fn store_storage(
+        &mut self,
+        address: Address,
+        key: Word,
+        value: Word,
+    ) -> Result<StorageStatus, Error>;
+
+    /// Returns the value of `key` in the EIP-1153 transient storage of `address`.
+    fn load_transient_storage(&self, address: &Address, key: &Word) -> Word;
+
+    fn store_transient_storage(&mut self, address: Address, key: Word, value: Word);
+
+    /// Called with each log emitted by a successful frame, in order. The logs are also
+    /// returned in the frame's `ExecutionResult`.
+    fn log(&mut self, _log: &Log) {}
+
+    /// Destroy the account at `address`, transferring its balance to `target`, and returning
+    /// `true` if it had not already been destroyed in this transaction.
+    fn self_destruct(&mut self, address: Address, target: Address) -> Result<bool, Error>;
+
+    /// Run the frame requested by `message`, transferring its value first. The calling frame
+    /// has already checked the call depth, the balance of the sender and, when creating an
+    /// account, that the nonce of the sender can be incremented.
+    fn call(&mut self, message: &Message) -> Result<CallOutcome, Error>;
+}
+
+impl Host for Context {
+    fn spec(&self) -> SpecId {
+        Context::spec(self)
+    }
+
+    fn gas_schedule(&self) -> &GasSchedule {
+        Context::gas_schedule(self)
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn block(&self) -> &Block {
+        &self.block
+    }
+
+    fn transaction(&self) -> &Transaction {
+        &self.transaction
+    }
+
+    fn base_fee(&self) -> U256 {
+        Context::base_fee(self)
+    }
+
+    fn blob_base_fee(&self) -> U256 {
+        Context::blob_base_fee(self)
+    }
+
+    fn block_hash(&self, number: u64) -> Result<B256, Error> {
+        self.state.block_hash(number)
+    }
+
+    fn load_account(&self, address: &Address) -> Result<Arc<Account>, Error> {
+        self.state.account(address)
+    }
+
+    fn access_account(&mut self, address: Address) -> bool {
+        self.substate.access_address(address)
+    }
+
+    fn access_storage_key(&mut self, address: Address, key: Word) -> bool {
+        self.substate.access_storage_key(address, key)
+    }
+
+    fn load_storage(&self, address: &Address, key: &Word) -> Result<Word, Error> {
+        self.state.storage(address, key)
+    }
+
+    fn store_storage(
+        &mut self,
+        address: Address,
+        key: Word,
+        value: Word,
+    ) -> Result<StorageStatus, Error> {
+        let original = self.original_storage(&address, &key)?;
+        let current = self.state.storage(&address, &key)?;
+        self.set_storage(address, key, value)?;
+        Ok(StorageStatus::new(original, current, value))
+    }
+
+    fn load_transient_storage(&self, address: &Address, key: &Word) -> Word {
+        self.substate.transient_storage(address, key)
+    }
+
+    fn store_transient_storage(&mut self, address: Address, key: Word, value: Word) {
+        self.substate.set_transient_storage(address, key, value);
+    }
+
+    fn self_destruct(&mut self, address: Address, target: Address) -> Result<bool, Error> {
+        let balance = std::mem::take(&mut self.journaled_account_mut(address)?.balance);
+        self.substate.touch(target);
+        if !balance.is_zero() {
+            self.journaled_account_mut(target)?.balance += balance;
+        }
+        Ok(self.substate.self_destruct(address))
+    }
+
+    fn call(&mut self, message: &Message) -> Result<CallOutcome, Error> {
+        if message.kind.is_create() {
+            return self.create(message);
+        }
+        let checkpoint = self.checkpoint();
+        if message.kind != CallKind::DelegateCall {
+            self.transfer(message.caller, message.recipient, message.value)?;
+        }
+        // NOTE: precompiles are not implemented, so calling one runs no code
+        let (account, _) = self.state.code_account(&message.code_address)?;
+        // Boxed to keep the frame off the native stack, on which each nested call recurses;
+        // that stack is sized for the maximum depth by `on_execution_stack`
+        let mut frame = Box::new(Frame::for_message(message, account, self.spec()));
+        let result = frame.run(self)?;
+        if !result.is_success() {
+            self.revert(checkpoint)?;
+        }
+        Ok(CallOutcome {
+            result,
+            gas_refunded: frame.gas_refunded,
+            created_address: None,
+        })
+    }
+}
+
+impl Context {
+    fn create(&mut self, message: &Message) -> Result<CallOutcome, Error> {
+        let spec = self.spec();
+        let sender = message.caller;
+        let address = match message.kind {
+            CallKind::Create2 { salt } => {
+                sender.create2_from_code(salt.to_be_bytes::<32>(), &message.input)
+            }
+            CallKind::EofCreate { salt } => {
+                // Unlike `CREATE2`, the initcode is not part of the derivation
+                let mut preimage = [0; 65];
+                preimage[0] = 0xff;
+                preimage[13..33].copy_from_slice(sender.as_slice());
+                preimage[33..].copy_from_slice(&salt.to_be_bytes::<32>());
+                Address::from_word(keccak256(preimage))
+            }
+            _ => sender.create(self.state.account(&sender)?.nonce),
+        };
+        // The nonce is incremented, and the new address warmed, even if creation fails
+        self.journaled_account_mut(sender)?.nonce += 1;
+        self.substate.access_address(address);
+        let existing = self.state.account(&address)?;
+        if existing.nonce != 0 || !existing.code.is_empty() {
+            return Ok(CallOutcome {
+                result: ExecutionResult::Halt {
+                    reason: HaltReason::CreateCollision,
+                    gas_used: message.gas_limit,
+                },
+                ..Default::default()
+            });
+        }
+
+        let checkpoint = self.checkpoint();
+        self.substate.create_account(address);
+        // EIP-161
+        if spec.is_enabled_in(SpecId::SpuriousDragon) {
+            self.journaled_account_mut(address)?.nonce = 1;
+        }
+        self.transfer(sender, address, message.value)?;
+        let is_eof = matches!(message.kind, CallKind::EofCreate { .. });
+        let code = if is_eof {
+            Bytecode::from(Eof::decode_initcode(&message.code)?)
+        } else {
+            Bytecode::from(message.input.as_slice())
+        };
+        let account = Arc::new(Account {
+            code,
+            ..Default::default()
+        });
+        let mut frame = Box::new(Frame::new(
+            address,
+            sender,
+            message.value,
+            message.gas_limit,
+            account,
+            spec,
+        ));
+        frame.depth = message.depth;
+        if is_eof {
+            frame.input = message.input.clone();
+        } else {
+            // Legacy initcode is always run as such, so an EOF container halts on its first
+            // byte
+            frame.code_section = None;
+        }
+        let mut result = frame.run(self)?;
+
+        if let ExecutionResult::Success {
+            output, gas_used, ..
+        } = &mut result
+        {
+            let code = std::mem::take(output);
+            let deposit = self.gas_schedule().code_deposit * code.len() as u64;
+            let affordable = *gas_used + deposit <= message.gas_limit;
+            // EIP-3541 and EIP-170; EOF initcode only returns validated containers
+            if (!is_eof && spec.is_enabled_in(SpecId::London) && code.first() == Some(&0xef))
+                || (spec.is_enabled_in(SpecId::SpuriousDragon) && code.len() > MAXIMUM_CODE_SIZE)
+            {
+                result = ExecutionResult::Halt {
+                    reason: HaltReason::InvalidContractCode,
+                    gas_used: message.gas_limit,
+                };
+            } else if affordable {
+                *gas_used += deposit;
+                self.journaled_account_mut(address)?.code = Bytecode::from(code.as_slice());
+            } else if spec.is_enabled_in(SpecId::Homestead) {
+                result = ExecutionResult::Halt {
+                    reason: HaltReason::OutOfGas,
+                    gas_used: message.gas_limit,
+                };
+            }
+            // Before Homestead, an account whose code cannot be paid for is left without code
+        }
+        if !result.is_success() {
+            self.revert(checkpoint)?;
+        }
+        Ok(CallOutcome {
+            created_address: result.is_success().then_some(address),
+            result,
+            gas_refunded: frame.gas_refunded,
+        })
+    }
+}
@@ -1,25 +1,123 @@
 use crate::{
     bytecode::Bytecode,
-    context::Context,
-    operations::{Operation, OperationResult},
-    primitives::{Address, U256},
+    context::{Context, Substate},
+    eof::Eof,
+    gas_schedule::GasSchedule,
+    host::{Host, Message},
+    operations::{CodeLocation, Exit, Operation, OperationResult},
+    primitives::{Address, Log, TxKind, U256},
+    spec::SpecId,
     Error,
 };
 use std::{
     collections::HashMap,
     ops::{Deref, DerefMut},
-    rc::Rc,
+    panic,
+    sync::Arc,
+    thread,
 };
 use thiserror::Error;
 
-const MAXIMUM_STACK_SIZE: usize = 1024;
+pub(crate) const MAXIMUM_STACK_SIZE: usize = 1024;
+pub(crate) const MAXIMUM_RETURN_STACK_SIZE: usize = 1024;
+pub(crate) const MAXIMUM_CALL_DEPTH: usize = 1024;
+// EIP-170
+pub(crate) const MAXIMUM_CODE_SIZE: usize = 0x6000;
+// EIP-3860
+pub(crate) const MAXIMUM_INITCODE_SIZE: usize = 2 * MAXIMUM_CODE_SIZE;
+const WORD_SIZE: usize = 32;
+// Bound on memory offsets and sizes; any access beyond this cannot be paid for.
+const MAXIMUM_MEMORY_SIZE: usize = u32::MAX as usize;
+// The native stack transactions execute on. Each nested frame runs within its caller's call to
+// `Host::call`, so this must fit frames at every depth up to the maximum, which unoptimized
+// builds take upwards of ten kilobytes each for.
+const EXECUTION_STACK_SIZE: usize = 64 * 1024 * 1024;
 
 pub type Word = U256;
 
-#[derive(Debug, Error)]
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
 pub enum StackError {
     #[error("stack underflow")]
     Underflow,
+    #[error("stack overflow")]
+    Overflow,
+}
+
+/// Exceptional conditions that stop execution of a frame and consume all of its gas.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum HaltReason {
+    #[error("out of gas")]
+    OutOfGas,
+    #[error("invalid opcode {0:#04x}")]
+    InvalidOpcode(u8),
+    #[error(transparent)]
+    Stack(#[from] StackError),
+    #[error("jump to invalid destination")]
+    InvalidJump,
+    #[error("state modification in static context")]
+    StaticViolation,
+    #[error("call depth exceeded")]
+    CallDepthExceeded,
+    #[error("return data access out of bounds")]
+    ReturnDataOutOfBounds,
+    #[error("contract creation collided with existing account")]
+    CreateCollision,
+    #[error("created contract code is too large or begins with 0xef")]
+    InvalidContractCode,
+    #[error("return stack overflow")]
+    ReturnStackOverflow,
+    #[error("call target is not an address")]
+    InvalidAddress,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionResult {
+    Success {
+        output: Vec<u8>,
+        logs: Vec<Log>,
+        gas_used: u64,
+        gas_refunded: u64,
+    },
+    Revert {
+        output: Vec<u8>,
+        gas_used: u64,
+    },
+    Halt {
+        reason: HaltReason,
+        gas_used: u64,
+    },
+}
+
+impl Default for ExecutionResult {
+    fn default() -> Self {
+        Self::Success {
+            output: Default::default(),
+            logs: Default::default(),
+            gas_used: Default::default(),
+            gas_refunded: Default::default(),
+        }
+    }
+}
+
+impl ExecutionResult {
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Success { .. })
+    }
+
+    pub fn output(&self) -> Option<&[u8]> {
+        match self {
+            Self::Success { output, .. } | Self::Revert { output, .. } => Some(output),
+            Self::Halt { .. } => None,
+        }
+    }
+
+    pub fn gas_used(&self) -> u64 {
+        match self {
+            Self::Success { gas_used, .. }
+            | Self::Revert { gas_used, .. }
+            | Self::Halt { gas_used, .. } => *gas_used,
+        }
+    }
 }
 
 #[derive(Debug, Default)]
@@ -41,6 +139,9 @@ impl DerefMut for Stack {
 
 impl Stack {
     pub fn push(&mut self, word: Word) -> Result<(), StackError> {
+        if self.0.len() >= MAXIMUM_STACK_SIZE {
+            return Err(StackError::Overflow);
+        }
         self.0.push(word);
         Ok(())
     }
@@ -48,96 +149,378 @@ impl Stack {
     pub fn pop(&mut self) -> Result<Word, StackError> {
         self.0.pop().ok_or(StackError::Underflow)
     }
+
+    // `depth` is 1-indexed from the top of the stack, matching `DUPn`
+    pub fn dup(&mut self, depth: usize) -> Result<(), StackError> {
+        let index = self
+            .0
+            .len()
+            .checked_sub(depth)
+            .ok_or(StackError::Underflow)?;
+        self.push(self.0[index])
+    }
+
+    // `depth` is 1-indexed from the word below the top of the stack, matching `SWAPn`
+    pub fn swap(&mut self, depth: usize) -> Result<(), StackError> {
+        let top = self.0.len().checked_sub(1).ok_or(StackError::Underflow)?;
+        let index = top.checked_sub(depth).ok_or(StackError::Underflow)?;
+        self.0.swap(top, index);
+        Ok(())
+    }
+
+    // `first` and `second` are 0-indexed from the top of the stack, matching `EXCHANGE`
+    pub fn exchange(&mut self, first: usize, second: usize) -> Result<(), StackError> {
+        let top = self.0.len().checked_sub(1).ok_or(StackError::Underflow)?;
+        let first = top.checked_sub(first).ok_or(StackError::Underflow)?;
+        let second = top.checked_sub(second).ok_or(StackError::Underflow)?;
+        self.0.swap(first, second);
+        Ok(())
+    }
+
+    /// Check that an operation consuming `inputs` words and producing `outputs` words
+    /// can run to completion against the current stack.
+    pub fn validate(&self, inputs: usize, outputs: usize) -> Result<(), StackError> {
+        let remaining = self
+            .0
+            .len()
+            .checked_sub(inputs)
+            .ok_or(StackError::Underflow)?;
+        if remaining + outputs > MAXIMUM_STACK_SIZE {
+            return Err(StackError::Overflow);
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Default, Clone)]
 pub struct Account {
     pub balance: U256,
-    pub nonce: U256,
+    pub nonce: u64,
     pub storage: HashMap<Word, Word>,
     pub code: Bytecode,
 }
 
+impl Account {
+    /// Returns `true` if the account is empty as defined by EIP-161.
+    pub fn is_empty(&self) -> bool {
+        self.balance.is_zero() && self.nonce == 0 && self.code.is_empty()
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Frame {
+    pub address: Address,
     pub caller: Address,
     pub value: U256,
-    pub gas_limit: U256,
-    pub account: Rc<Account>,
+    pub gas_limit: u64,
+    pub account: Arc<Account>,
+    pub input: Vec<u8>,
 
     pub program_counter: usize,
-    pub gas_used: U256,
+    pub gas_used: u64,
+    // May go negative in a frame which undoes refunded storage changes of its parent
+    pub gas_refunded: i64,
 
     pub stack: Stack,
     pub memory: Memory,
     pub output: Vec<u8>,
+    // The output of the most recent call made by this frame
+    pub return_data: Vec<u8>,
+    pub logs: Vec<Log>,
 
     pub can_modify_state: bool,
+    // The number of frames above this one, zero for the transaction's frame
+    pub depth: usize,
+
+    // The EOF code section being executed, or `None` when executing legacy code
+    pub code_section: Option<usize>,
+    pub return_stack: Vec<CodeLocation>,
 
     operation_counter: usize,
 }
 
-impl From<&Context> for Frame {
-    fn from(context: &Context) -> Self {
-        Frame {
-            caller: context.transaction.sender,
-            value: context.transaction.value,
-            gas_limit: context.transaction.gas_limit,
-            account: context
-                .state
-                .get(&context.transaction.recipient)
-                .cloned()
-                .unwrap_or_default(),
-            program_counter: Default::default(),
-            gas_used: Default::default(),
-            stack: Default::default(),
-            memory: Default::default(),
-            output: Default::default(),
-            can_modify_state: false,
-            operation_counter: 0,
-        }
+impl TryFrom<&Context> for Frame {
+    type Error = Error;
+
+    fn try_from(context: &Context) -> Result<Self, Error> {
+        let transaction = &context.transaction;
+        let TxKind::Call(recipient) = transaction.to else {
+            return Err(Error::ContractCreationUnsupported);
+        };
+        let (account, _) = context.state.code_account(&recipient)?;
+        Ok(Frame {
+            input: transaction.input.clone(),
+            ..Frame::new(
+                recipient,
+                transaction.sender,
+                transaction.value,
+                transaction.gas_limit,
+                account,
+                context.spec(),
+            )
+        })
     }
 }
 
 impl Frame {
+    /// Returns a frame running the code of `account` at `address` under the rules of `spec`,
+    /// with nothing yet accessed.
+    pub fn new(
+        address: Address,
+        caller: Address,
+        value: U256,
+        gas_limit: u64,
+        account: Arc<Account>,
+        spec: SpecId,
+    ) -> Self {
+        let code_section = (spec.has_eip3540() && account.code.eof().is_some()).then_some(0);
+        Frame {
+            address,
+            caller,
+            value,
+            gas_limit,
+            account,
+            can_modify_state: true,
+            code_section,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the frame requested by `message`, running the code of `account`.
+    pub fn for_message(message: &Message, account: Arc<Account>, spec: SpecId) -> Self {
+        Frame {
+            input: message.input.clone(),
+            can_modify_state: !message.is_static,
+            depth: message.depth,
+            ..Frame::new(
+                message.recipient,
+                message.caller,
+                message.value,
+                message.gas_limit,
+                account,
+                spec,
+            )
+        }
+    }
+
     pub fn next_operation(&mut self) -> Option<Operation> {
-        self.account.code.get(self.operation_counter).cloned()
+        match (self.eof(), self.code_section) {
+            (Some(eof), Some(section)) => eof.code_sections[section]
+                .operations
+                .get(self.operation_counter)
+                .cloned(),
+            _ => self.account.code.get(self.operation_counter).cloned(),
+        }
     }
 
-    pub fn apply(&mut self, result: OperationResult) -> Result<(), Error> {
-        self.gas_used = self
+    /// Returns the EOF container being executed, if this frame is executing one.
+    pub fn eof(&self) -> Option<&Eof> {
+        self.code_section.and(self.account.code.eof())
+    }
+
+    pub fn gas_remaining(&self) -> u64 {
+        self.gas_limit - self.gas_used
+    }
+
+    /// Charge `amount` of gas to this frame, consuming all of the remaining gas
+    /// if the frame's gas limit would be exceeded.
+    pub fn charge_gas(&mut self, amount: u64) -> Result<(), Error> {
+        match self
             .gas_used
-            .checked_add(result.gas_used)
-            .ok_or(Error::GasUsedOverflow)?;
-        self.program_counter += result.program_counter_increment;
-        self.operation_counter += 1;
+            .checked_add(amount)
+            .filter(|gas_used| *gas_used <= self.gas_limit)
+        {
+            Some(gas_used) => {
+                self.gas_used = gas_used;
+                Ok(())
+            }
+            None => {
+                self.gas_used = self.gas_limit;
+                Err(HaltReason::OutOfGas.into())
+            }
+        }
+    }
+
+    /// Charge for and perform any memory expansion needed to access `size` bytes at `offset`,
+    /// returning both as `usize`.
+    pub fn expand_memory(
+        &mut self,
+        offset: Word,
+        size: Word,
+        schedule: &GasSchedule,
+    ) -> Result<(usize, usize), Error> {
+        if size.is_zero() {
+            return Ok((0, 0));
+        }
+        let offset = to_memory_index(offset)?;
+        let size = to_memory_index(size)?;
+        let word_count = (offset + size).div_ceil(WORD_SIZE);
+        self.charge_gas(self.memory.expansion_cost(word_count, schedule))?;
+        self.memory.expand(word_count);
+        Ok((offset, size))
+    }
+
+    /// Marks `address` as accessed, returning `true` if it was cold under EIP-2929.
+    pub fn warm_account(&mut self, address: Address, host: &mut dyn Host) -> bool {
+        host.access_account(address) && host.spec().has_eip2929()
+    }
+
+    /// Marks `key` in this frame's storage as accessed, returning `true` if it was cold under
+    /// EIP-2929.
+    pub fn warm_storage_key(&mut self, key: Word, host: &mut dyn Host) -> bool {
+        host.access_storage_key(self.address, key) && host.spec().has_eip2929()
+    }
+
+    /// Marks `address` as accessed, charging the EIP-2929 surcharge for a cold account.
+    pub fn access_account(&mut self, address: Address, host: &mut dyn Host) -> Result<(), Error> {
+        if self.warm_account(address, host) {
+            let schedule = host.gas_schedule();
+            self.charge_gas(schedule.cold_account_access - schedule.warm_access)?;
+        }
         Ok(())
     }
 
-    pub fn run(&mut self, context: &Context) -> Result<(), Error> {
-        // TODO: (journal) value transfer
+    pub fn apply(&mut self, result: OperationResult) {
+        match result.jump {
+            Some(location) => self.jump(location),
+            None => {
+                self.program_counter += result.program_counter_increment;
+                self.operation_counter += 1;
+            }
+        }
+    }
+
+    // Continue execution at `location`, which is guaranteed to be an instruction by EOF
+    // validation or, in legacy code, by checking for a `JUMPDEST`
+    fn jump(&mut self, location: CodeLocation) {
+        self.operation_counter = match self.eof() {
+            Some(eof) => {
+                eof.code_sections[location.code_section].operation_index(location.program_counter)
+            }
+            None => self.account.code.jump_destination(location.program_counter),
+        }
+        .expect("jump destinations are validated");
+        if self.code_section.is_some() {
+            self.code_section = Some(location.code_section);
+        }
+        self.program_counter = location.program_counter;
+    }
+
+    pub fn run(&mut self, host: &mut dyn Host) -> Result<ExecutionResult, Error> {
         while let Some(operation) = self.next_operation() {
-            let result = self.dispatch_operation(operation, context)?;
-            self.apply(result)?;
+            match self.step(operation, host) {
+                Ok(result) => {
+                    let exit = result.exit;
+                    self.apply(result);
+                    match exit {
+                        Some(Exit::Stop | Exit::Return) => return Ok(self.success()),
+                        Some(Exit::Revert) => {
+                            return Ok(ExecutionResult::Revert {
+                                output: self.output.clone(),
+                                gas_used: self.gas_used,
+                            })
+                        }
+                        None => {}
+                    }
+                }
+                Err(Error::Halt(reason)) => {
+                    self.gas_used = self.gas_limit;
+                    return Ok(ExecutionResult::Halt {
+                        reason,
+                        gas_used: self.gas_used,
+                    });
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(self.success())
+    }
+
+    fn step(
+        &mut self,
+        operation: Operation,
+        host: &mut dyn Host,
+    ) -> Result<OperationResult, Error> {
+        if !host.spec().is_enabled_in(operation.introduced_in()) {
+            return Err(HaltReason::InvalidOpcode(operation.opcode()).into());
+        }
+        self.charge_gas(operation.gas_schedule_cost(host.gas_schedule()))?;
+        self.dispatch_operation(operation, host)
+    }
+
+    fn success(&self) -> ExecutionResult {
+        ExecutionResult::Success {
+            output: self.output.clone(),
+            logs: self.logs.clone(),
+            gas_used: self.gas_used,
+            gas_refunded: self.gas_refunded.max(0) as u64,
         }
-        Ok(())
     }
 
     pub fn dispatch_operation(
         &mut self,
         operation: Operation,
-        context: &Context,
+        host: &mut dyn Host,
     ) -> Result<OperationResult, Error> {
-        operation.apply(&mut self.stack, &mut self.memory, context)
+        let (inputs, outputs) = operation.stack_requirements();
+        self.stack.validate(inputs, outputs)?;
+        operation.apply(self, host)
     }
 }
 
+fn to_memory_index(word: Word) -> Result<usize, Error> {
+    usize::try_from(word)
+        .ok()
+        .filter(|index| *index <= MAXIMUM_MEMORY_SIZE)
+        .ok_or(HaltReason::OutOfGas.into())
+}
+
 #[derive(Debug, Default)]
 pub struct Memory {
     data: Vec<u8>,
     active_word_count: usize,
 }
 
+impl Memory {
+    pub fn size(&self) -> usize {
+        self.active_word_count * WORD_SIZE
+    }
+
+    /// Returns the gas required to grow memory to `word_count` words, if it is not already
+    /// at least that large.
+    pub fn expansion_cost(&self, word_count: usize, schedule: &GasSchedule) -> u64 {
+        if word_count <= self.active_word_count {
+            return 0;
+        }
+        schedule.memory_cost(word_count) - schedule.memory_cost(self.active_word_count)
+    }
+
+    pub fn expand(&mut self, word_count: usize) {
+        if word_count > self.active_word_count {
+            self.active_word_count = word_count;
+            self.data.resize(self.size(), 0);
+        }
+    }
+
+    // NOTE: callers are expected to `expand` memory before accessing it
+    pub fn load(&self, offset: usize) -> Word {
+        Word::from_be_slice(&self.data[offset..offset + WORD_SIZE])
+    }
+
+    pub fn store(&mut self, offset: usize, data: &[u8]) {
+        self.data[offset..offset + data.len()].copy_from_slice(data);
+    }
+
+    /// Copy `size` bytes from `source` to `destination`, which may overlap.
+    pub fn copy(&mut self, source: usize, destination: usize, size: usize) {
+        self.data.copy_within(source..source + size, destination);
+    }
+
+    pub fn slice(&self, offset: usize, size: usize) -> &[u8] {
+        &self.data[offset..offset + size]
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Interpreter {
     frames: Vec<Frame>,
@@ -147,6 +530,7 @@ pub struct Interpreter {
 pub struct Execution {
     pub context: Context,
     pub frame: Frame,
+    pub result: ExecutionResult,
 }
 
 impl Execution {
@@ -158,17 +542,1165 @@ impl Execution {
 }
 
 impl Interpreter {
-    pub fn execute(mut self, context: Context) -> Result<Execution, Error> {
-        let frame = From::from(&context);
+    pub fn execute(self, mut context: Context) -> Result<Execution, Error> {
+        context.substate = Substate::for_transaction(&context)?;
+        let frame = Frame::try_from(&context)?;
+        self.execute_frame(frame, context)
+    }
+
+    pub(crate) fn execute_frame(
+        self,
+        frame: Frame,
+        mut context: Context,
+    ) -> Result<Execution, Error> {
+        let checkpoint = context.checkpoint();
+        let (frame, result) = on_execution_stack(|| self.execute_with_host(frame, &mut context))?;
+        if !result.is_success() {
+            context.revert(checkpoint)?;
+        }
+        context.remove_self_destructs();
+        context.remove_touched_empty_accounts()?;
+        Ok(Execution {
+            context,
+            frame,
+            result,
+        })
+    }
+
+    /// Run `frame` against `host`, passing its logs to `host` if it succeeds.
+    ///
+    /// Unlike `execute` and `transact`, this runs on the calling thread, whose stack must fit any
+    /// frames the host nests within calls to `Host::call`.
+    pub fn execute_with_host(
+        mut self,
+        frame: Frame,
+        host: &mut dyn Host,
+    ) -> Result<(Frame, ExecutionResult), Error> {
         self.frames.push(frame);
-        self.run(&context)?;
+        let result = self.run(host)?;
         let frame = self.frames.pop().ok_or(Error::FrameUnderflow)?;
-        Ok(Execution { context, frame })
+        if result.is_success() {
+            for log in &frame.logs {
+                host.log(log);
+            }
+        }
+        Ok((frame, result))
     }
 
-    pub fn run(&mut self, context: &Context) -> Result<(), Error> {
+    pub fn run(&mut self, host: &mut dyn Host) -> Result<ExecutionResult, Error> {
         let frame = self.frames.last_mut().ok_or(Error::FrameUnderflow)?;
-        frame.run(context)?;
-        Ok(())
+        frame.run(host)
+    }
+}
+
+/// Returns the result of `f`, run on a new thread with a stack large enough for frames nested to
+/// the maximum call depth, whatever the stack of the calling thread.
+pub(crate) fn on_execution_stack<T: Send>(
+    f: impl FnOnce() -> Result<T, Error> + Send,
+) -> Result<T, Error> {
+    thread::scope(|scope| {
+        thread::Builder::new()
+            .stack_size(EXECUTION_STACK_SIZE)
+            .spawn_scoped(scope, f)
+            .map_err(Error::Spawn)?
+            .join()
+            .unwrap_or_else(|panic| panic::resume_unwind(panic))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::{Block, State, Transaction},
+        host::{CallOutcome, StorageStatus},
+        primitives::B256,
+        spec::SpecId,
+    };
+
+    fn execute(code: &str) -> Result<Execution, Error> {
+        execute_with_gas_limit(code, 1_000_000)
+    }
+
+    fn execute_with_gas_limit(code: &str, gas_limit: u64) -> Result<Execution, Error> {
+        execute_with_spec(code, gas_limit, SpecId::default())
+    }
+
+    fn execute_with_spec(code: &str, gas_limit: u64, spec: SpecId) -> Result<Execution, Error> {
+        let account = Account {
+            code: code.try_into()?,
+            ..Default::default()
+        };
+        let mut context = Context::new(spec);
+        context.transaction = Transaction {
+            to: TxKind::Call(Address::default()),
+            gas_limit,
+            ..Default::default()
+        };
+        context.state = [(Address::default(), account)].into_iter().into();
+        Interpreter::default().execute(context)
+    }
+
+    #[test]
+    fn test_dup_and_swap() {
+        // PUSH1 1, PUSH2 0x0002, DUP2, POP, SWAP1
+        let execution = execute("0x6001610002815090").unwrap();
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[U256::from(2), U256::from(1)]);
+    }
+
+    #[test]
+    fn test_push0_through_push32() {
+        let mut code = String::from("0x5f");
+        for size in 1..=32 {
+            code.push_str(&format!("{:02x}", 0x5f + size));
+            code.push_str(&"ff".repeat(size));
+        }
+        let execution = execute(&code).unwrap();
+        let (stack, _) = execution.output();
+        assert_eq!(stack.len(), 33);
+        assert_eq!(stack[0], U256::ZERO);
+        assert_eq!(stack[20], U256::from_be_slice(&[0xff; 20]));
+        assert_eq!(stack[32], U256::MAX);
+    }
+
+    #[test]
+    fn test_stack_overflow() {
+        let code = format!("0x{}", "5f".repeat(MAXIMUM_STACK_SIZE + 1));
+        let execution = execute(&code).unwrap();
+        assert_eq!(
+            execution.result,
+            ExecutionResult::Halt {
+                reason: HaltReason::Stack(StackError::Overflow),
+                gas_used: 1_000_000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_underflow_has_no_side_effects() {
+        let mut frame = Frame::default();
+        frame.stack.push(U256::from(1)).unwrap();
+        let result = frame.dispatch_operation(Operation::Add, &mut Context::default());
+        assert!(matches!(
+            result,
+            Err(Error::Halt(HaltReason::Stack(StackError::Underflow)))
+        ));
+        assert_eq!(frame.stack.as_slice(), &[U256::from(1)]);
+
+        let result = frame.dispatch_operation(Operation::Swap1, &mut Context::default());
+        assert!(matches!(
+            result,
+            Err(Error::Halt(HaltReason::Stack(StackError::Underflow)))
+        ));
+        assert_eq!(frame.stack.as_slice(), &[U256::from(1)]);
+    }
+
+    #[test]
+    fn test_gas() {
+        // PUSH1 1, GAS
+        let execution = execute_with_gas_limit("0x60015a", 100).unwrap();
+        let (stack, _) = execution.output();
+        assert_eq!(stack[1], U256::from(100 - 3 - 2));
+        assert_eq!(execution.frame.gas_used, 5);
+    }
+
+    #[test]
+    fn test_out_of_gas() {
+        // PUSH1 1, PUSH1 2, ADD
+        let execution = execute_with_gas_limit("0x6001600201", 9).unwrap();
+        assert!(execution.result.is_success());
+        let execution = execute_with_gas_limit("0x6001600201", 8).unwrap();
+        assert_eq!(
+            execution.result,
+            ExecutionResult::Halt {
+                reason: HaltReason::OutOfGas,
+                gas_used: 8,
+            }
+        );
+
+        let mut frame = Frame {
+            gas_limit: 8,
+            ..Default::default()
+        };
+        assert!(frame.charge_gas(6).is_ok());
+        assert!(matches!(
+            frame.charge_gas(3),
+            Err(Error::Halt(HaltReason::OutOfGas))
+        ));
+        assert_eq!(frame.gas_used, frame.gas_limit);
+    }
+
+    #[test]
+    fn test_return_and_revert() {
+        // PUSH2 0xbeef, PUSH1 0, MSTORE, PUSH1 2, PUSH1 30, RETURN
+        let execution = execute("0x61beef6000526002601ef3").unwrap();
+        assert_eq!(execution.result.output(), Some(&[0xbe, 0xef][..]));
+        // 3 + 3 + 3 (+ 3 memory expansion) + 3 + 3
+        assert_eq!(execution.result.gas_used(), 18);
+
+        // PUSH2 0xbeef, PUSH1 0, MSTORE, PUSH1 2, PUSH1 30, REVERT
+        let execution = execute("0x61beef6000526002601efd").unwrap();
+        assert_eq!(
+            execution.result,
+            ExecutionResult::Revert {
+                output: vec![0xbe, 0xef],
+                gas_used: 18,
+            }
+        );
+    }
+
+    #[test]
+    fn test_halts_consume_all_gas() {
+        // PUSH1 1, INVALID, PUSH1 2
+        let execution = execute("0x6001fe6002").unwrap();
+        assert_eq!(
+            execution.result,
+            ExecutionResult::Halt {
+                reason: HaltReason::InvalidOpcode(0xfe),
+                gas_used: 1_000_000,
+            }
+        );
+        assert_eq!(execution.frame.stack.as_slice(), &[U256::from(1)]);
+    }
+
+    #[test]
+    fn test_logs() {
+        // PUSH1 0xaa, PUSH1 0, MSTORE8, PUSH1 7, PUSH1 1, PUSH1 0, LOG1, STOP, PUSH1 1
+        let execution = execute("0x60aa600053600760016000a1006001").unwrap();
+        match execution.result {
+            ExecutionResult::Success { logs, .. } => {
+                assert_eq!(logs.len(), 1);
+                assert_eq!(logs[0].topics(), &[B256::with_last_byte(7)]);
+                assert_eq!(logs[0].data.data.as_ref(), &[0xaa]);
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+        assert!(execution.frame.stack.is_empty());
+    }
+
+    #[test]
+    fn test_arbitrary_bytecode_does_not_panic() {
+        // xorshift, to cover a spread of inputs without an extra dependency
+        let mut seed = 0x2545f4914f6cdd1du64;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        for _ in 0..1000 {
+            let length = next() % 64;
+            let code = (0..length).map(|_| next() as u8).collect::<Vec<_>>();
+            execute(&format!("0x{}", hex::encode(code))).unwrap();
+        }
+        for opcode in 0..=u8::MAX {
+            execute(&format!("0x{}", hex::encode([0x5f, 0x5f, 0x5f, opcode]))).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_operations_gated_by_spec() {
+        let execution = execute_with_spec("0x5f", 100, SpecId::Shanghai).unwrap();
+        assert!(execution.result.is_success());
+        let execution = execute_with_spec("0x5f", 100, SpecId::Paris).unwrap();
+        assert_eq!(
+            execution.result,
+            ExecutionResult::Halt {
+                reason: HaltReason::InvalidOpcode(0x5f),
+                gas_used: 100,
+            }
+        );
+
+        // PUSH1 0, PUSH1 0, REVERT
+        let execution = execute_with_spec("0x60006000fd", 100, SpecId::Byzantium).unwrap();
+        assert!(matches!(execution.result, ExecutionResult::Revert { .. }));
+        let execution = execute_with_spec("0x60006000fd", 100, SpecId::Homestead).unwrap();
+        assert!(matches!(
+            execution.result,
+            ExecutionResult::Halt {
+                reason: HaltReason::InvalidOpcode(0xfd),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_jumps() {
+        // PUSH1 1, PUSH1 8, JUMPI, INVALID, PUSH1 0x5b, JUMPDEST, PC
+        let execution = execute("0x6001600857fe605b5b58").unwrap();
+        assert!(execution.result.is_success());
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[U256::from(9)]);
+        assert_eq!(execution.result.gas_used(), 3 + 3 + 10 + 1 + 2);
+
+        // PUSH1 3, JUMP, PUSH1 0x5b, into the immediate of the PUSH1
+        let execution = execute("0x600356605b").unwrap();
+        assert_eq!(
+            execution.result,
+            ExecutionResult::Halt {
+                reason: HaltReason::InvalidJump,
+                gas_used: 1_000_000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_calldata_and_keccak256() {
+        // PUSH1 1, CALLDATALOAD, CALLDATASIZE, PUSH1 0, PUSH1 0, CALLDATACOPY,
+        // CALLDATASIZE, PUSH1 0, KECCAK256
+        let account = Account {
+            code: "0x60013536600060003736600020".try_into().unwrap(),
+            ..Default::default()
+        };
+        let mut context = Context::default();
+        context.transaction = Transaction {
+            to: TxKind::Call(Address::default()),
+            gas_limit: 100_000,
+            input: vec![0x01, 0x02, 0x03],
+            ..Default::default()
+        };
+        context.state = [(Address::default(), account)].into_iter().into();
+        let execution = Interpreter::default().execute(context).unwrap();
+        let (stack, _) = execution.output();
+        let mut word = [0; 32];
+        word[..2].copy_from_slice(&[0x02, 0x03]);
+        assert_eq!(
+            stack.as_slice(),
+            &[
+                U256::from_be_bytes(word),
+                U256::from_be_bytes(alloy::primitives::keccak256([0x01, 0x02, 0x03]).0),
+            ]
+        );
+        assert_eq!(execution.frame.memory.slice(0, 3), &[0x01, 0x02, 0x03]);
+
+        // PUSH1 1, PUSH1 0, PUSH1 0, RETURNDATACOPY, without any return data
+        let execution = execute("0x6001600060003e").unwrap();
+        assert!(matches!(
+            execution.result,
+            ExecutionResult::Halt {
+                reason: HaltReason::ReturnDataOutOfBounds,
+                ..
+            }
+        ));
+    }
+
+    const CALLEE: Address = Address::repeat_byte(0x33);
+
+    fn execute_with_callee(code: &str, callee_code: &str, gas_limit: u64) -> Execution {
+        execute_with_callee_and_spec(code, callee_code, gas_limit, SpecId::default())
+    }
+
+    fn execute_with_callee_and_spec(
+        code: &str,
+        callee_code: &str,
+        gas_limit: u64,
+        spec: SpecId,
+    ) -> Execution {
+        let account = Account {
+            balance: U256::from(100),
+            code: code.try_into().unwrap(),
+            ..Default::default()
+        };
+        let callee = Account {
+            code: callee_code.try_into().unwrap(),
+            ..Default::default()
+        };
+        let mut context = Context::new(spec);
+        context.transaction = Transaction {
+            to: TxKind::Call(Address::default()),
+            gas_limit,
+            ..Default::default()
+        };
+        context.state = [(Address::default(), account), (CALLEE, callee)]
+            .into_iter()
+            .into();
+        Interpreter::default().execute(context).unwrap()
+    }
+
+    #[test]
+    fn test_call() {
+        // PUSH1 32, PUSH1 0, PUSH1 0, PUSH1 0, PUSH1 0, PUSH20 0x3333.., PUSH2 0xffff, CALL,
+        // RETURNDATASIZE
+        let code = format!("0x6020600060006000600073{}61fffff13d", "33".repeat(20));
+        // PUSH1 1, PUSH1 0, TSTORE, PUSH1 42, PUSH1 0, MSTORE, PUSH1 32, PUSH1 0, RETURN
+        let execution = execute_with_callee(&code, "0x600160005d602a60005260206000f3", 100_000);
+        assert!(execution.result.is_success());
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[U256::from(1), U256::from(32)]);
+        assert_eq!(execution.frame.memory.load(0), U256::from(42));
+        // the pushes, a cold CALL expanding memory by a word, the callee and RETURNDATASIZE
+        assert_eq!(
+            execution.result.gas_used(),
+            7 * 3 + 2600 + 3 + (3 + 3 + 100 + 3 + 3 + 6 + 3 + 3) + 2
+        );
+        let substate = &execution.context.substate;
+        assert_eq!(
+            substate.transient_storage(&CALLEE, &U256::ZERO),
+            U256::from(1)
+        );
+
+        // as above, but the callee reverts
+        let execution = execute_with_callee(&code, "0x600160005d602a60005260206000fd", 100_000);
+        assert!(execution.result.is_success());
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[U256::ZERO, U256::from(32)]);
+        assert_eq!(execution.frame.memory.load(0), U256::from(42));
+        assert!(execution.context.substate.transient_storage.is_empty());
+
+        // a STATICCALL to the same callee halts it, consuming the gas passed to it
+        let code = format!("0x602060006000600073{}61fffffa3d", "33".repeat(20));
+        let execution = execute_with_callee(&code, "0x600160005d602a60005260206000f3", 100_000);
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[U256::ZERO, U256::ZERO]);
+        assert_eq!(execution.result.gas_used(), 6 * 3 + 2600 + 3 + 0xffff + 2);
+        assert!(execution.context.substate.transient_storage.is_empty());
+    }
+
+    #[test]
+    fn test_call_with_value() {
+        // PUSH1 0, PUSH1 0, PUSH1 0, PUSH1 0, PUSH1 10, PUSH20 0x3333.., PUSH1 0, CALL
+        let code = format!("0x6000600060006000600a73{}6000f1", "33".repeat(20));
+        let execution = execute_with_callee(&code, "0x00", 100_000);
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[U256::from(1)]);
+        let state = &execution.context.state;
+        assert_eq!(state.account(&CALLEE).unwrap().balance, U256::from(10));
+        assert_eq!(
+            state.account(&Address::default()).unwrap().balance,
+            U256::from(90)
+        );
+        // the callee is given only the stipend, which it does not use
+        assert_eq!(execution.result.gas_used(), 7 * 3 + 2600 + 9000 - 2300);
+
+        // as above, but with more value than the caller holds
+        let code = format!("0x6000600060006000610fff73{}6000f1", "33".repeat(20));
+        let execution = execute_with_callee(&code, "0x00", 100_000);
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[U256::ZERO]);
+        let state = &execution.context.state;
+        assert_eq!(state.account(&CALLEE).unwrap().balance, U256::ZERO);
+    }
+
+    #[test]
+    fn test_call_delegating_account() {
+        let delegating = Address::repeat_byte(0x55);
+        // PUSH1 32, PUSH1 0, PUSH1 0, PUSH1 0, PUSH1 0, PUSH20 0x5555.., PUSH2 0xffff, CALL,
+        // RETURNDATASIZE
+        let code = format!("0x6020600060006000600073{}61fffff13d", "55".repeat(20));
+        let account = Account {
+            code: code.as_str().try_into().unwrap(),
+            ..Default::default()
+        };
+        let delegation = Account {
+            code: Bytecode::delegation_to(CALLEE),
+            ..Default::default()
+        };
+        // PUSH1 1, PUSH1 0, TSTORE, PUSH1 42, PUSH1 0, MSTORE, PUSH1 32, PUSH1 0, RETURN
+        let callee = Account {
+            code: "0x600160005d602a60005260206000f3".try_into().unwrap(),
+            ..Default::default()
+        };
+        let mut context = Context::default();
+        context.transaction = Transaction {
+            to: TxKind::Call(Address::default()),
+            gas_limit: 100_000,
+            ..Default::default()
+        };
+        context.state = [
+            (Address::default(), account),
+            (delegating, delegation),
+            (CALLEE, callee),
+        ]
+        .into_iter()
+        .into();
+        let execution = Interpreter::default().execute(context).unwrap();
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[U256::from(1), U256::from(32)]);
+        // the code of the delegate runs against the delegating account
+        let substate = &execution.context.substate;
+        assert_eq!(
+            substate.transient_storage(&delegating, &U256::ZERO),
+            U256::from(1)
+        );
+        assert!(substate.accessed_addresses.contains(&CALLEE));
+        // as in `test_call`, with a further cold access of the delegate
+        assert_eq!(
+            execution.result.gas_used(),
+            7 * 3 + 2600 + 2600 + 3 + (3 + 3 + 100 + 3 + 3 + 6 + 3 + 3) + 2
+        );
+    }
+
+    fn execute_create(initcode: &str, operation: &str, existing: Option<Account>) -> Execution {
+        // PUSH<n> initcode, PUSH1 0, MSTORE, then <operation> 7 wei with the initcode, which
+        // is right-aligned in the first word of memory
+        let size = initcode.len() / 2;
+        let code = format!(
+            "0x{:02x}{initcode}600052{operation}60{size:02x}60{:02x}6007{}",
+            0x5f + size,
+            32 - size,
+            if operation.is_empty() { "f0" } else { "f5" },
+        );
+        let account = Account {
+            balance: U256::from(100),
+            code: code.as_str().try_into().unwrap(),
+            ..Default::default()
+        };
+        let mut context = Context::default();
+        context.transaction = Transaction {
+            to: TxKind::Call(Address::default()),
+            gas_limit: 100_000,
+            ..Default::default()
+        };
+        let mut state = State::from([(Address::default(), account)].into_iter());
+        if let Some(existing) = existing {
+            state.set(Address::default().create(0), Some(Arc::new(existing)));
+        }
+        context.state = state;
+        Interpreter::default().execute(context).unwrap()
+    }
+
+    #[test]
+    fn test_create() {
+        // PUSH1 1, PUSH1 0, MSTORE8, PUSH1 1, PUSH1 0, RETURN
+        let initcode = "600160005360016000f3";
+        let execution = execute_create(initcode, "", None);
+        assert!(execution.result.is_success());
+        let created = Address::default().create(0);
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[created.into_word().into()]);
+        let state = &execution.context.state;
+        let account = state.account(&created).unwrap();
+        assert_eq!(account.code.bytes(), &[0x01]);
+        assert_eq!(account.nonce, 1);
+        assert_eq!(account.balance, U256::from(7));
+        assert_eq!(state.account(&Address::default()).unwrap().nonce, 1);
+        // the initcode is stored, then CREATE pays for a word of it, the initcode and the
+        // deposit of one byte of code
+        assert_eq!(
+            execution.result.gas_used(),
+            3 + 3 + 6 + 3 + 3 + 3 + 32000 + 2 + (3 + 3 + 6 + 3 + 3) + 200
+        );
+
+        // as above with CREATE2 and a salt of 0x42, which also pays to hash the initcode
+        let execution = execute_create(initcode, "6042", None);
+        let created = Address::default()
+            .create2_from_code(B256::with_last_byte(0x42), hex::decode(initcode).unwrap());
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[created.into_word().into()]);
+        let account = execution.context.state.account(&created).unwrap();
+        assert_eq!(account.code.bytes(), &[0x01]);
+        assert_eq!(
+            execution.result.gas_used(),
+            3 + 3 + 6 + 3 + 3 + 3 + 3 + 32000 + 2 + 6 + (3 + 3 + 6 + 3 + 3) + 200
+        );
+    }
+
+    #[test]
+    fn test_failed_create() {
+        let created = Address::default().create(0);
+        // PUSH1 1, PUSH1 0, MSTORE8, PUSH1 1, PUSH1 0, REVERT
+        let execution = execute_create("600160005360016000fd", "", None);
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[U256::ZERO]);
+        assert_eq!(execution.frame.return_data, vec![0x01]);
+        let state = &execution.context.state;
+        assert!(state.get(&created).unwrap().is_none());
+        // the nonce of the creator is still incremented
+        assert_eq!(state.account(&Address::default()).unwrap().nonce, 1);
+        assert!(execution.context.substate.created.is_empty());
+
+        // an existing account at the address consumes all of the gas passed on
+        let existing = Account {
+            nonce: 1,
+            ..Default::default()
+        };
+        let execution = execute_create("600160005360016000f3", "", Some(existing));
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[U256::ZERO]);
+        let gas_passed = {
+            let remaining = 100_000 - (3 + 3 + 6 + 3 + 3 + 3 + 32000 + 2);
+            remaining - remaining / 64
+        };
+        assert_eq!(
+            execution.result.gas_used(),
+            3 + 3 + 6 + 3 + 3 + 3 + 32000 + 2 + gas_passed
+        );
+
+        // code beginning with 0xef may not be deployed
+        // PUSH1 0xef, PUSH1 0, MSTORE8, PUSH1 1, PUSH1 0, RETURN
+        let execution = execute_create("60ef60005360016000f3", "", None);
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[U256::ZERO]);
+        assert!(execution.context.state.get(&created).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_self_destruct_of_created_account() {
+        // PUSH20 0x2222.., SELFDESTRUCT, as initcode
+        let initcode = format!("73{}ff", "22".repeat(20));
+        let execution = execute_create(&initcode, "", None);
+        assert!(execution.result.is_success());
+        // EIP-6780: the account is removed as it was created in the same transaction
+        let created = Address::default().create(0);
+        let state = &execution.context.state;
+        assert!(state.get(&created).unwrap().is_none());
+        assert_eq!(
+            state.account(&Address::repeat_byte(0x22)).unwrap().balance,
+            U256::from(7)
+        );
+    }
+
+    #[test]
+    fn test_call_depth() {
+        // PUSH1 0, TLOAD, PUSH1 1, ADD, PUSH1 0, TSTORE, then CALL to ADDRESS with all of
+        // the remaining gas
+        let code = "0x60005c60010160005d60006000600060006000305af100";
+        let execution = execute_with_gas_limit(code, 1_000_000_000_000).unwrap();
+        assert!(execution.result.is_success());
+        // the frame at the maximum depth fails to call further
+        assert_eq!(
+            execution
+                .context
+                .substate
+                .transient_storage(&Address::default(), &U256::ZERO),
+            U256::from(MAXIMUM_CALL_DEPTH + 1)
+        );
+    }
+
+    #[test]
+    fn test_call_depth_on_a_small_stack() {
+        // PUSH1 0, SLOAD, PUSH1 1, ADD, PUSH1 0, SSTORE, then CALL to ADDRESS with all but
+        // 100000 of the remaining gas, which without EIP-150 reaches the maximum depth
+        let code = "0x6000546001016000556000600060006000600030620186a05a03f100";
+        // A spawned thread's default stack is far smaller than the main thread's
+        let execution =
+            thread::spawn(move || execute_with_spec(code, 1_000_000_000_000, SpecId::Frontier))
+                .join()
+                .unwrap()
+                .unwrap();
+        assert!(execution.result.is_success());
+        assert_eq!(
+            execution
+                .context
+                .state
+                .storage(&Address::default(), &U256::ZERO)
+                .unwrap(),
+            U256::from(MAXIMUM_CALL_DEPTH + 1)
+        );
+    }
+
+    #[test]
+    fn test_gas_schedule_override() {
+        let account = Account {
+            code: "0x600160005260206000f3".try_into().unwrap(),
+            ..Default::default()
+        };
+        let mut context = Context::default()
+            .with_gas_schedule(GasSchedule {
+                very_low: 1,
+                memory: 10,
+                ..Default::default()
+            })
+            .unwrap();
+        context.transaction = Transaction {
+            to: TxKind::Call(Address::default()),
+            gas_limit: 100,
+            ..Default::default()
+        };
+        context.state = [(Address::default(), account)].into_iter().into();
+        let execution = Interpreter::default().execute(context).unwrap();
+        // 4 pushes and an MSTORE at 1 gas each, plus one word of memory
+        assert_eq!(execution.result.gas_used(), 5 + 10);
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_execution_types_are_send_and_sync() {
+        assert_send_sync::<Context>();
+        assert_send_sync::<State>();
+        assert_send_sync::<Execution>();
+        assert_send_sync::<Frame>();
+        assert_send_sync::<Interpreter>();
+    }
+
+    #[test]
+    fn test_execute_on_another_thread() {
+        // PUSH1 1, PUSH1 0, SSTORE
+        let execution = std::thread::spawn(|| execute("0x6001600055"))
+            .join()
+            .unwrap()
+            .unwrap();
+        assert!(execution.result.is_success());
+        assert_eq!(
+            execution
+                .context
+                .state
+                .storage(&Address::default(), &U256::ZERO)
+                .unwrap(),
+            U256::from(1)
+        );
+    }
+
+    #[test]
+    fn test_storage() {
+        // PUSH1 1, PUSH1 0, SSTORE, PUSH1 0, SLOAD
+        let execution = execute("0x6001600055600054").unwrap();
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[U256::from(1)]);
+        // cold SSTORE of a new value and a warm SLOAD
+        assert_eq!(execution.result.gas_used(), 3 + 3 + 2100 + 20000 + 3 + 100);
+        let account = &execution
+            .context
+            .state
+            .account(&Address::default())
+            .unwrap();
+        assert_eq!(account.storage[&U256::ZERO], U256::from(1));
+
+        // PUSH1 1, PUSH1 0, SSTORE, PUSH1 0, PUSH1 0, SSTORE
+        let execution = execute("0x60016000556000600055").unwrap();
+        assert_eq!(
+            execution.result,
+            ExecutionResult::Success {
+                output: vec![],
+                logs: vec![],
+                gas_used: 3 + 3 + 2100 + 20000 + 3 + 3 + 100,
+                gas_refunded: 20000 - 100,
+            }
+        );
+        let state = &execution.context.state;
+        assert_eq!(
+            state.storage(&Address::default(), &U256::ZERO).unwrap(),
+            U256::ZERO
+        );
+
+        // PUSH1 1, PUSH1 0, SSTORE, INVALID
+        let execution = execute("0x6001600055fe").unwrap();
+        let account = &execution
+            .context
+            .state
+            .account(&Address::default())
+            .unwrap();
+        assert!(account.storage.is_empty());
+    }
+
+    #[test]
+    fn test_transient_storage() {
+        // PUSH1 1, PUSH1 0, TSTORE, PUSH1 0, TLOAD
+        let code = "0x600160005d60005c";
+        let execution = execute(code).unwrap();
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[U256::from(1)]);
+        assert_eq!(execution.result.gas_used(), 3 + 3 + 100 + 3 + 100);
+        assert!(execution
+            .context
+            .state
+            .account(&Address::default())
+            .unwrap()
+            .storage
+            .is_empty());
+
+        assert_eq!(
+            execution
+                .context
+                .substate
+                .transient_storage(&Address::default(), &U256::ZERO),
+            U256::from(1)
+        );
+
+        // PUSH1 1, PUSH1 0, TSTORE, INVALID
+        let execution = execute("0x600160005dfe").unwrap();
+        assert!(execution.context.substate.transient_storage.is_empty());
+
+        let execution = execute_with_spec(code, 1_000_000, SpecId::Shanghai).unwrap();
+        assert!(matches!(
+            execution.result,
+            ExecutionResult::Halt {
+                reason: HaltReason::InvalidOpcode(0x5d),
+                ..
+            }
+        ));
+
+        let mut frame = Frame::default();
+        frame.stack.push(U256::from(1)).unwrap();
+        frame.stack.push(U256::ZERO).unwrap();
+        let result = frame.dispatch_operation(Operation::TStore, &mut Context::default());
+        assert!(matches!(
+            result,
+            Err(Error::Halt(HaltReason::StaticViolation))
+        ));
+    }
+
+    #[test]
+    fn test_mcopy() {
+        // PUSH32 0x0102..20, PUSH1 0, MSTORE, PUSH1 32, PUSH1 0, PUSH1 1, MCOPY
+        let word = (1..=32u8).collect::<Vec<_>>();
+        let code = format!("0x7f{}6000526020600060015e", hex::encode(&word));
+        let execution = execute(&code).unwrap();
+        let memory = &execution.frame.memory;
+        assert_eq!(memory.size(), 64);
+        assert_eq!(memory.slice(0, 1), &[1]);
+        assert_eq!(memory.slice(1, 32), word.as_slice());
+        assert_eq!(memory.slice(33, 31), &[0; 31]);
+        // the copy of one word expands memory by one word
+        assert_eq!(
+            execution.result.gas_used(),
+            3 + 3 + 6 + 3 + 3 + 3 + 3 + 3 + 3
+        );
+    }
+
+    #[test]
+    fn test_eof_functions() {
+        // section 0: PUSH1 3, CALLF 1, DATALOADN 0, RJUMPI 1, INVALID, STOP
+        // section 1: DUPN 0, ADD, RETF
+        let code = format!(
+            "0xef0001010008020002000d0004ff002000{}{}{}{}01",
+            "0080000201010001",
+            "6003e30001d10000e10001fe00",
+            "e60001e4",
+            "00".repeat(31),
+        );
+        let execution = execute_with_spec(&code, 1_000_000, SpecId::Experimental).unwrap();
+        assert!(execution.result.is_success());
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[U256::from(6)]);
+        assert!(execution.frame.return_stack.is_empty());
+        assert_eq!(execution.result.gas_used(), 3 + 5 + 3 + 3 + 3 + 3 + 4);
+
+        // outside of the experimental spec the container is not recognised
+        let execution = execute_with_spec(&code, 1_000_000, SpecId::Osaka).unwrap();
+        assert_eq!(
+            execution.result,
+            ExecutionResult::Halt {
+                reason: HaltReason::InvalidOpcode(0xef),
+                gas_used: 1_000_000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_eof_stack_and_data() {
+        // DATASIZE, PUSH0, DATALOAD, PUSH1 1, EXCHANGE 0x00, SWAPN 0, PUSH1 1,
+        // RJUMPV [0, 1], INVALID, STOP
+        let code = format!(
+            "0xef00010100040200010013ff002000{}{}{}",
+            "00800004",
+            "d25fd06001e800e7006001e20100000001fe00",
+            "11".repeat(32),
+        );
+        let execution = execute_with_spec(&code, 1_000_000, SpecId::Experimental).unwrap();
+        assert!(execution.result.is_success());
+        let (stack, _) = execution.output();
+        let word = U256::from_be_bytes([0x11; 32]);
+        assert_eq!(stack.as_slice(), &[word, U256::from(1), U256::from(32)]);
+    }
+
+    #[test]
+    fn test_eof_ext_calls() {
+        // PUSH1 0, PUSH1 0, PUSH1 0, PUSH20 0x3333.., EXTCALL, PUSH0, RETURNDATALOAD, STOP
+        let code = format!(
+            "0xef0001010004020001001fff000000{}{}",
+            "00800004",
+            format_args!("60006000600073{}f85ff700", "33".repeat(20)),
+        );
+        // PUSH1 42, PUSH1 0, MSTORE, PUSH1 32, PUSH1 0, RETURN
+        let callee = "0x602a60005260206000f3";
+        let execution = execute_with_callee_and_spec(&code, callee, 100_000, SpecId::Experimental);
+        assert!(execution.result.is_success());
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[U256::ZERO, U256::from(42)]);
+        // the pushes, a cold EXTCALL, the callee, PUSH0 and RETURNDATALOAD
+        assert_eq!(
+            execution.result.gas_used(),
+            3 * 3 + 3 + 2600 + (3 + 3 + 6 + 3 + 3) + 2 + 3
+        );
+
+        // EXTDELEGATECALL to legacy code fails without running it or consuming the gas
+        // passed to it
+        let code = format!(
+            "0xef0001010004020001001dff000000{}{}",
+            "00800003",
+            format_args!("6000600073{}f95ff700", "33".repeat(20)),
+        );
+        let execution = execute_with_callee_and_spec(&code, callee, 100_000, SpecId::Experimental);
+        assert!(execution.result.is_success());
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[U256::from(1), U256::ZERO]);
+        assert_eq!(execution.result.gas_used(), 2 * 3 + 3 + 2600 + 2 + 3);
+
+        // the target must fit in an address
+        let code = format!(
+            "0xef0001010004020001001cff000000{}{}",
+            "00800003",
+            format_args!("600060007401{}fb00", "00".repeat(20)),
+        );
+        let execution = execute_with_callee_and_spec(&code, callee, 100_000, SpecId::Experimental);
+        assert_eq!(
+            execution.result,
+            ExecutionResult::Halt {
+                reason: HaltReason::InvalidAddress,
+                gas_used: 100_000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_eof_create() {
+        // STOP
+        let runtime = "ef00010100040200010001ff0000000080000000";
+        // PUSH1 0xaa, PUSH0, MSTORE8, PUSH1 1, PUSH0, RETURNCONTRACT 0
+        let initcode = format!(
+            "ef00010100040200010009030001{:08x}ff000000{}{}{runtime}",
+            runtime.len() / 2,
+            "00800002",
+            "60aa5f5360015fee00",
+        );
+        // PUSH0, PUSH0, PUSH0, PUSH0, EOFCREATE 0, STOP
+        let code = format!(
+            "0xef00010100040200010007030001{:08x}ff000000{}{}{initcode}",
+            initcode.len() / 2,
+            "00800004",
+            "5f5f5f5fec0000",
+        );
+        let execution = execute_with_spec(&code, 1_000_000, SpecId::Experimental).unwrap();
+        assert!(execution.result.is_success());
+        // the sender and salt, both zero, each padded to a word
+        let mut preimage = [0; 65];
+        preimage[0] = 0xff;
+        let created = Address::from_word(alloy::primitives::keccak256(preimage));
+        let (stack, _) = execution.output();
+        assert_eq!(stack.as_slice(), &[created.into_word().into()]);
+        // the runtime container is deployed with the auxiliary data in its data section
+        let account = execution.context.state.account(&created).unwrap();
+        assert_eq!(
+            account.code.bytes(),
+            hex::decode("ef00010100040200010001ff0001000080000000aa").unwrap()
+        );
+        assert_eq!(account.nonce, 1);
+        assert!(account.code.eof().is_some());
+    }
+
+    // Serves a fixed value for every storage slot and records the effects applied to it
+    #[derive(Default)]
+    struct MockHost {
+        gas_schedule: GasSchedule,
+        block: Block,
+        transaction: Transaction,
+        stored: Vec<(Address, Word, Word)>,
+        logs: Vec<Log>,
+        self_destructs: Vec<(Address, Address)>,
+        calls: Vec<Message>,
+    }
+
+    impl Host for MockHost {
+        fn spec(&self) -> SpecId {
+            SpecId::default()
+        }
+
+        fn gas_schedule(&self) -> &GasSchedule {
+            &self.gas_schedule
+        }
+
+        fn chain_id(&self) -> u64 {
+            1
+        }
+
+        fn block(&self) -> &Block {
+            &self.block
+        }
+
+        fn transaction(&self) -> &Transaction {
+            &self.transaction
+        }
+
+        fn base_fee(&self) -> U256 {
+            U256::ZERO
+        }
+
+        fn blob_base_fee(&self) -> U256 {
+            U256::ZERO
+        }
+
+        fn block_hash(&self, _number: u64) -> Result<B256, Error> {
+            Ok(B256::ZERO)
+        }
+
+        fn load_account(&self, _address: &Address) -> Result<Arc<Account>, Error> {
+            Ok(Default::default())
+        }
+
+        fn access_account(&mut self, _address: Address) -> bool {
+            true
+        }
+
+        fn access_storage_key(&mut self, _address: Address, _key: Word) -> bool {
+            true
+        }
+
+        fn load_storage(&self, _address: &Address, _key: &Word) -> Result<Word, Error> {
+            Ok(U256::from(7))
+        }
+
+        fn store_storage(
+            &mut self,
+            address: Address,
+            key: Word,
+            value: Word,
+        ) -> Result<StorageStatus, Error> {
+            self.stored.push((address, key, value));
+            Ok(StorageStatus::new(U256::from(7), U256::from(7), value))
+        }
+
+        fn load_transient_storage(&self, _address: &Address, _key: &Word) -> Word {
+            Word::ZERO
+        }
+
+        fn store_transient_storage(&mut self, _address: Address, _key: Word, _value: Word) {}
+
+        fn log(&mut self, log: &Log) {
+            self.logs.push(log.clone());
+        }
+
+        fn self_destruct(&mut self, address: Address, target: Address) -> Result<bool, Error> {
+            self.self_destructs.push((address, target));
+            Ok(true)
+        }
+
+        // Every call returns its input after using one unit of gas
+        fn call(&mut self, message: &Message) -> Result<CallOutcome, Error> {
+            self.calls.push(message.clone());
+            Ok(CallOutcome {
+                result: ExecutionResult::Success {
+                    output: message.input.clone(),
+                    logs: vec![],
+                    gas_used: 1,
+                    gas_refunded: 0,
+                },
+                ..Default::default()
+            })
+        }
+    }
+
+    #[test]
+    fn test_execute_with_host() {
+        // PUSH1 1, SLOAD, PUSH1 1, ADD, PUSH1 2, SSTORE, PUSH1 0, PUSH1 0, LOG0
+        let address = Address::repeat_byte(0x11);
+        let frame = Frame {
+            address,
+            gas_limit: 100_000,
+            can_modify_state: true,
+            account: Arc::new(Account {
+                code: "0x60015460010160025560006000a0".try_into().unwrap(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut host = MockHost::default();
+        let (_, result) = Interpreter::default()
+            .execute_with_host(frame, &mut host)
+            .unwrap();
+        assert!(result.is_success());
+        assert_eq!(host.stored, vec![(address, U256::from(2), U256::from(8))]);
+        assert_eq!(host.logs.len(), 1);
+        assert_eq!(host.logs[0].address, address);
+    }
+
+    #[test]
+    fn test_call_and_self_destruct_with_host() {
+        // PUSH1 0xaa, PUSH1 0, MSTORE8, PUSH1 1, PUSH1 1, PUSH1 1, PUSH1 0, PUSH1 0,
+        // PUSH20 0x3333.., PUSH2 0xffff, CALL, PUSH20 0x4444.., SELFDESTRUCT
+        let code = format!(
+            "0x60aa6000536001600160016000600073{}61fffff173{}ff",
+            "33".repeat(20),
+            "44".repeat(20),
+        );
+        let address = Address::repeat_byte(0x11);
+        let frame = Frame {
+            address,
+            gas_limit: 100_000,
+            can_modify_state: true,
+            account: Arc::new(Account {
+                code: code.as_str().try_into().unwrap(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut host = MockHost::default();
+        let (frame, result) = Interpreter::default()
+            .execute_with_host(frame, &mut host)
+            .unwrap();
+        assert!(result.is_success());
+        assert_eq!(
+            host.calls,
+            vec![Message {
+                depth: 1,
+                gas_limit: 0xffff,
+                recipient: CALLEE,
+                caller: address,
+                input: vec![0xaa],
+                code_address: CALLEE,
+                ..Default::default()
+            }]
+        );
+        assert_eq!(frame.stack.as_slice(), &[U256::from(1)]);
+        assert_eq!(frame.memory.slice(0, 2), &[0xaa, 0xaa]);
+        assert_eq!(frame.return_data, vec![0xaa]);
+        assert_eq!(
+            host.self_destructs,
+            vec![(address, Address::repeat_byte(0x44))]
+        );
+    }
+
+    #[test]
+    fn test_self_destruct() {
+        let target = Address::repeat_byte(0x22);
+        let account = Account {
+            balance: U256::from(100),
+            storage: [(U256::ZERO, U256::from(1))].into_iter().collect(),
+            // PUSH20 0x2222.., SELFDESTRUCT
+            code: format!("0x73{}ff", "22".repeat(20))
+                .as_str()
+                .try_into()
+                .unwrap(),
+            ..Default::default()
+        };
+        let execute = |spec| {
+            let mut context = Context::new(spec);
+            context.transaction = Transaction {
+                to: TxKind::Call(Address::default()),
+                gas_limit: 100_000,
+                ..Default::default()
+            };
+            context.state = [(Address::default(), account.clone())].into_iter().into();
+            Interpreter::default().execute(context).unwrap()
+        };
+
+        let execution = execute(SpecId::Cancun);
+        // a cold target, created by the transfer
+        assert_eq!(execution.result.gas_used(), 3 + 5000 + 2600 + 25000);
+        let state = &execution.context.state;
+        assert_eq!(state.account(&target).unwrap().balance, U256::from(100));
+        // EIP-6780: the account itself remains
+        let address = Address::default();
+        assert_eq!(state.account(&address).unwrap().balance, U256::ZERO);
+        assert_eq!(state.storage(&address, &U256::ZERO).unwrap(), U256::from(1));
+
+        let execution = execute(SpecId::Shanghai);
+        let state = &execution.context.state;
+        assert_eq!(state.account(&target).unwrap().balance, U256::from(100));
+        assert!(state.get(&address).unwrap().is_none());
+        assert_eq!(state.storage(&address, &U256::ZERO).unwrap(), U256::ZERO);
+
+        let execution = execute(SpecId::Homestead);
+        assert_eq!(
+            execution.result,
+            ExecutionResult::Success {
+                output: vec![],
+                logs: vec![],
+                gas_used: 3,
+                gas_refunded: 24000,
+            }
+        );
     }
 }
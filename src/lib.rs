@@ -1,14 +1,25 @@
+pub mod access_list;
+pub mod block;
 pub mod bytecode;
 pub mod context;
+pub mod database;
+pub mod eof;
 mod error;
 pub mod gas_schedule;
+pub mod host;
 pub mod interpreter;
 pub mod operations;
 pub mod primitives;
+pub mod processor;
+pub mod proof;
+pub mod spec;
+pub mod trie;
 
 pub use context::Context;
 pub use error::Error;
-pub use interpreter::Interpreter;
+pub use host::Host;
+pub use interpreter::{ExecutionResult, Interpreter};
+pub use spec::SpecId;
 
 pub fn parse_hex(input: &str) -> Result<Vec<u8>, Error> {
     let input = input.trim().strip_prefix("0x").unwrap_or(input);
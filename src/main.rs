@@ -1,41 +1,62 @@
 use evm::{
-    context, context::Transaction as TransactionContext, interpreter::Account, parse_hex, Context,
-    Interpreter,
+    access_list::create_access_list,
+    context,
+    context::Transaction as TransactionContext,
+    interpreter::Account,
+    parse_hex,
+    primitives::{Address, TxKind},
+    Context, Interpreter, SpecId,
 };
+use std::env;
 use std::error::Error;
 use std::io::{self, Read};
 
+const DEFAULT_GAS_LIMIT: u64 = 30_000_000;
+const SENDER: Address = Address::repeat_byte(0x11);
+// Holds the code
+const RECIPIENT: Address = Address::ZERO;
+// Pass as the first argument to print the generated access list instead of executing the code.
+const ACCESS_LIST_MODE: &str = "access-list";
+
 fn main() -> Result<(), Box<dyn Error>> {
     let mut input = String::new();
     let mut handle = io::stdin();
     handle.read_to_string(&mut input)?;
     let input = input.split(",").collect::<Vec<&str>>();
     let bytecode = input[0].try_into()?;
-    let input = parse_hex(input[1])?;
+    let calldata = input.get(1).copied().unwrap_or_default();
+    let gas_limit = match input.get(2) {
+        Some(gas_limit) => gas_limit.trim().parse()?,
+        None => DEFAULT_GAS_LIMIT,
+    };
+    let input = parse_hex(calldata)?;
 
     let transaction = TransactionContext {
-        gas_limit: Default::default(),
-        gas_price: Default::default(),
-        sender: Default::default(),
-        recipient: Default::default(),
-        value: Default::default(),
+        sender: SENDER,
+        to: TxKind::Call(RECIPIENT),
+        gas_limit,
         input,
+        ..Default::default()
     };
     let account = Account {
         code: bytecode,
         ..Default::default()
     };
-    let state = context::State::from([(transaction.recipient, account)].into_iter());
+    let state = context::State::from([(RECIPIENT, account)].into_iter());
 
-    let context = Context {
-        block: Default::default(),
-        transaction,
-        precompiles: Default::default(),
-        state,
-    };
+    let mut context = Context::new(SpecId::default());
+    context.block.gas_limit = gas_limit;
+    context.transaction = transaction;
+    context.state = state;
+
+    if env::args().nth(1).as_deref() == Some(ACCESS_LIST_MODE) {
+        dbg!(create_access_list(context)?);
+        return Ok(());
+    }
 
     let execution = Interpreter::default().execute(context)?;
     dbg!(&execution);
     dbg!(execution.output());
+    dbg!(&execution.result);
     Ok(())
 }
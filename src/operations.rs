@@ -1,8 +1,20 @@
-use crate::gas_schedule;
+use crate::eof::Eof;
+use crate::gas_schedule::GasSchedule;
+use crate::host::{CallKind, CallOutcome, Message, StorageStatus};
 use crate::interpreter::Word;
-use crate::interpreter::{Memory, Stack};
-use crate::primitives::U256;
-use crate::{Context, Error};
+use crate::interpreter::{
+    Account, ExecutionResult, Frame, HaltReason, Stack, StackError, MAXIMUM_CALL_DEPTH,
+    MAXIMUM_INITCODE_SIZE, MAXIMUM_RETURN_STACK_SIZE, MAXIMUM_STACK_SIZE,
+};
+use crate::primitives::{Address, Log, B256, I256};
+use crate::spec::SpecId;
+use crate::{Error, Host};
+use alloy::primitives::keccak256;
+
+const MAXIMUM_BLOCKHASH_DEPTH: u64 = 256;
+// EIP-7069: gas an `EXT*CALL` keeps for its caller, and the least it will pass on
+const MINIMUM_RETAINED_GAS: u64 = 5000;
+const MINIMUM_CALLEE_GAS: u64 = 2300;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Operation {
@@ -63,6 +75,8 @@ pub enum Operation {
     ChainId,
     SelfBalance,
     BaseFee,
+    BlobHash,
+    BlobBaseFee,
     // Stack, memory, storage, flow operations
     Pop,
     Mload,
@@ -76,6 +90,9 @@ pub enum Operation {
     MSize,
     Gas,
     JumpDest,
+    Tload,
+    TStore,
+    MCopy,
     // Push operations
     Push0,
     Push1(u8),
@@ -161,30 +178,257 @@ pub enum Operation {
     Revert,
     Invalid,
     SelfDestruct,
+    // EVM Object Format operations, only defined within EOF code sections
+    DataLoad,
+    DataLoadN(u16),
+    DataSize,
+    DataCopy,
+    RJump(i16),
+    RJumpI(i16),
+    // The jump table is read from the code at execution
+    RJumpV(u8),
+    CallF(u16),
+    RetF,
+    JumpF(u16),
+    DupN(u8),
+    SwapN(u8),
+    Exchange(u8),
+    EofCreate(u8),
+    ReturnContract(u8),
+    ReturnDataLoad,
+    ExtCall,
+    ExtDelegateCall,
+    ExtStaticCall,
+    // Bytes that do not correspond to a defined operation
+    Unknown(u8),
+}
+
+/// Signals that an operation ended execution of the current frame without halting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    Stop,
+    Return,
+    Revert,
+}
+
+/// A position in the code sections of an EOF container.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CodeLocation {
+    pub code_section: usize,
+    pub program_counter: usize,
 }
 
 #[derive(Debug, Default)]
 pub struct OperationResult {
-    pub gas_used: U256,
     pub program_counter_increment: usize,
+    pub exit: Option<Exit>,
+    // Where execution continues instead of the next operation, for jumps and EOF control flow
+    pub jump: Option<CodeLocation>,
 }
 
 impl From<&Operation> for OperationResult {
     fn from(value: &Operation) -> Self {
         OperationResult {
-            gas_used: value.gas_used(),
             program_counter_increment: value.program_counter_increment(),
+            exit: None,
+            jump: None,
         }
     }
 }
 
-fn program_counter_increment_for_push(n: usize) -> usize {
-    n + 1
-}
-
 impl Operation {
-    pub(crate) const PUSH1_VALUE: u8 = 0x60;
-    pub(crate) const PUSH32_VALUE: u8 = 0x7f;
+    pub fn opcode(&self) -> u8 {
+        use Operation::*;
+
+        match self {
+            Stop => 0x00,
+            Add => 0x01,
+            Mul => 0x02,
+            Sub => 0x03,
+            Div => 0x04,
+            Sdiv => 0x05,
+            Mod => 0x06,
+            SMod => 0x07,
+            Addmod => 0x08,
+            Mulmod => 0x09,
+            Exp => 0x0a,
+            SignExtend => 0x0b,
+            Lt => 0x10,
+            Gt => 0x11,
+            Slt => 0x12,
+            Sgt => 0x13,
+            Eq => 0x14,
+            IsZero => 0x15,
+            And => 0x16,
+            Or => 0x17,
+            Xor => 0x18,
+            Not => 0x19,
+            Byte => 0x1a,
+            Shl => 0x1b,
+            Shr => 0x1c,
+            Sar => 0x1d,
+            Keccack256 => 0x20,
+            Address => 0x30,
+            Balance => 0x31,
+            Origin => 0x32,
+            Caller => 0x33,
+            CallValue => 0x34,
+            CallDataLoad => 0x35,
+            CallDataSize => 0x36,
+            CallDataCopy => 0x37,
+            CodeSize => 0x38,
+            CodeCopy => 0x39,
+            GasPrice => 0x3a,
+            ExtCodeSize => 0x3b,
+            ExtCodeCopy => 0x3c,
+            ReturnDataSize => 0x3d,
+            ReturnDataCopy => 0x3e,
+            ExtCodeHash => 0x3f,
+            Blockhash => 0x40,
+            Coinbase => 0x41,
+            Timestamp => 0x42,
+            Number => 0x43,
+            PrevRandao => 0x44,
+            GasLimit => 0x45,
+            ChainId => 0x46,
+            SelfBalance => 0x47,
+            BaseFee => 0x48,
+            BlobHash => 0x49,
+            BlobBaseFee => 0x4a,
+            Pop => 0x50,
+            Mload => 0x51,
+            Mstore => 0x52,
+            MStore8 => 0x53,
+            Sload => 0x54,
+            SStore => 0x55,
+            Jump => 0x56,
+            JumpI => 0x57,
+            Pc => 0x58,
+            MSize => 0x59,
+            Gas => 0x5a,
+            JumpDest => 0x5b,
+            Tload => 0x5c,
+            TStore => 0x5d,
+            MCopy => 0x5e,
+            Push0 => 0x5f,
+            Push1(_) => 0x60,
+            Push2(_) => 0x61,
+            Push3(_) => 0x62,
+            Push4(_) => 0x63,
+            Push5(_) => 0x64,
+            Push6(_) => 0x65,
+            Push7(_) => 0x66,
+            Push8(_) => 0x67,
+            Push9(_) => 0x68,
+            Push10(_) => 0x69,
+            Push11(_) => 0x6a,
+            Push12(_) => 0x6b,
+            Push13(_) => 0x6c,
+            Push14(_) => 0x6d,
+            Push15(_) => 0x6e,
+            Push16(_) => 0x6f,
+            Push17(_) => 0x70,
+            Push18(_) => 0x71,
+            Push19(_) => 0x72,
+            Push20(_) => 0x73,
+            Push21(_) => 0x74,
+            Push22(_) => 0x75,
+            Push23(_) => 0x76,
+            Push24(_) => 0x77,
+            Push25(_) => 0x78,
+            Push26(_) => 0x79,
+            Push27(_) => 0x7a,
+            Push28(_) => 0x7b,
+            Push29(_) => 0x7c,
+            Push30(_) => 0x7d,
+            Push31(_) => 0x7e,
+            Push32(_) => 0x7f,
+            Dup1 => 0x80,
+            Dup2 => 0x81,
+            Dup3 => 0x82,
+            Dup4 => 0x83,
+            Dup5 => 0x84,
+            Dup6 => 0x85,
+            Dup7 => 0x86,
+            Dup8 => 0x87,
+            Dup9 => 0x88,
+            Dup10 => 0x89,
+            Dup11 => 0x8a,
+            Dup12 => 0x8b,
+            Dup13 => 0x8c,
+            Dup14 => 0x8d,
+            Dup15 => 0x8e,
+            Dup16 => 0x8f,
+            Swap1 => 0x90,
+            Swap2 => 0x91,
+            Swap3 => 0x92,
+            Swap4 => 0x93,
+            Swap5 => 0x94,
+            Swap6 => 0x95,
+            Swap7 => 0x96,
+            Swap8 => 0x97,
+            Swap9 => 0x98,
+            Swap10 => 0x99,
+            Swap11 => 0x9a,
+            Swap12 => 0x9b,
+            Swap13 => 0x9c,
+            Swap14 => 0x9d,
+            Swap15 => 0x9e,
+            Swap16 => 0x9f,
+            Log0 => 0xa0,
+            Log1 => 0xa1,
+            Log2 => 0xa2,
+            Log3 => 0xa3,
+            Log4 => 0xa4,
+            Create => 0xf0,
+            Call => 0xf1,
+            CallCode => 0xf2,
+            Return => 0xf3,
+            DelegateCall => 0xf4,
+            Create2 => 0xf5,
+            StaticCall => 0xfa,
+            Revert => 0xfd,
+            Invalid => 0xfe,
+            SelfDestruct => 0xff,
+            DataLoad => 0xd0,
+            DataLoadN(_) => 0xd1,
+            DataSize => 0xd2,
+            DataCopy => 0xd3,
+            RJump(_) => 0xe0,
+            RJumpI(_) => 0xe1,
+            RJumpV(_) => 0xe2,
+            CallF(_) => 0xe3,
+            RetF => 0xe4,
+            JumpF(_) => 0xe5,
+            DupN(_) => 0xe6,
+            SwapN(_) => 0xe7,
+            Exchange(_) => 0xe8,
+            EofCreate(_) => 0xec,
+            ReturnContract(_) => 0xee,
+            ReturnDataLoad => 0xf7,
+            ExtCall => 0xf8,
+            ExtDelegateCall => 0xf9,
+            ExtStaticCall => 0xfb,
+            Unknown(opcode) => *opcode,
+        }
+    }
+
+    /// Returns the fork which introduced this operation.
+    pub fn introduced_in(&self) -> SpecId {
+        use Operation::*;
+
+        match self {
+            DelegateCall => SpecId::Homestead,
+            Revert | ReturnDataSize | ReturnDataCopy | StaticCall => SpecId::Byzantium,
+            Shl | Shr | Sar | Create2 | ExtCodeHash => SpecId::Constantinople,
+            ChainId | SelfBalance => SpecId::Istanbul,
+            BaseFee => SpecId::London,
+            Push0 => SpecId::Shanghai,
+            Tload | TStore | MCopy | BlobHash | BlobBaseFee => SpecId::Cancun,
+            operation if operation.is_eof() => SpecId::Experimental,
+            _ => SpecId::Frontier,
+        }
+    }
 
     pub fn get_push_size(&self) -> Option<usize> {
         use Operation::*;
@@ -231,74 +475,384 @@ impl Operation {
         self.get_push_size().is_some()
     }
 
+    /// Returns `true` for operations only defined in EOF code.
+    pub fn is_eof(&self) -> bool {
+        use Operation::*;
+
+        matches!(
+            self,
+            DataLoad
+                | DataLoadN(_)
+                | DataSize
+                | DataCopy
+                | RJump(_)
+                | RJumpI(_)
+                | RJumpV(_)
+                | CallF(_)
+                | RetF
+                | JumpF(_)
+                | DupN(_)
+                | SwapN(_)
+                | Exchange(_)
+                | EofCreate(_)
+                | ReturnContract(_)
+                | ReturnDataLoad
+                | ExtCall
+                | ExtDelegateCall
+                | ExtStaticCall
+        )
+    }
+
+    /// Returns the number of bytes taken by the operation in code, including immediates.
+    pub fn size(&self) -> usize {
+        use Operation::*;
+
+        let immediate_size = match self {
+            DataLoadN(_) | RJump(_) | RJumpI(_) | CallF(_) | JumpF(_) => 2,
+            DupN(_) | SwapN(_) | Exchange(_) | EofCreate(_) | ReturnContract(_) => 1,
+            RJumpV(max_index) => 1 + 2 * (*max_index as usize + 1),
+            operation => operation.get_push_size().unwrap_or_default(),
+        };
+        1 + immediate_size
+    }
+
+    pub fn get_dup_size(&self) -> Option<usize> {
+        use Operation::*;
+
+        match self {
+            Dup1 => Some(1),
+            Dup2 => Some(2),
+            Dup3 => Some(3),
+            Dup4 => Some(4),
+            Dup5 => Some(5),
+            Dup6 => Some(6),
+            Dup7 => Some(7),
+            Dup8 => Some(8),
+            Dup9 => Some(9),
+            Dup10 => Some(10),
+            Dup11 => Some(11),
+            Dup12 => Some(12),
+            Dup13 => Some(13),
+            Dup14 => Some(14),
+            Dup15 => Some(15),
+            Dup16 => Some(16),
+            _ => None,
+        }
+    }
+
     pub fn is_dup(&self) -> bool {
+        self.get_dup_size().is_some()
+    }
+
+    pub fn get_swap_size(&self) -> Option<usize> {
         use Operation::*;
 
         match self {
-            Dup1 | Dup2 | Dup3 | Dup4 | Dup5 | Dup6 | Dup7 | Dup8 | Dup9 | Dup10 | Dup11
-            | Dup12 | Dup13 | Dup14 | Dup15 | Dup16 => true,
-            _ => false,
+            Swap1 => Some(1),
+            Swap2 => Some(2),
+            Swap3 => Some(3),
+            Swap4 => Some(4),
+            Swap5 => Some(5),
+            Swap6 => Some(6),
+            Swap7 => Some(7),
+            Swap8 => Some(8),
+            Swap9 => Some(9),
+            Swap10 => Some(10),
+            Swap11 => Some(11),
+            Swap12 => Some(12),
+            Swap13 => Some(13),
+            Swap14 => Some(14),
+            Swap15 => Some(15),
+            Swap16 => Some(16),
+            _ => None,
         }
     }
 
     pub fn is_swap(&self) -> bool {
+        self.get_swap_size().is_some()
+    }
+
+    pub fn get_log_size(&self) -> Option<usize> {
         use Operation::*;
 
         match self {
+            Log0 => Some(0),
+            Log1 => Some(1),
+            Log2 => Some(2),
+            Log3 => Some(3),
+            Log4 => Some(4),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of words consumed from and pushed onto the stack.
+    pub fn stack_requirements(&self) -> (usize, usize) {
+        use Operation::*;
+
+        match self {
+            Stop | JumpDest | Invalid | Unknown(_) | RJump(_) => (0, 0),
+            // Depend on the type of the code section, checked by EOF validation
+            CallF(_) | RetF | JumpF(_) => (0, 0),
+            DataLoadN(_) | DataSize => (0, 1),
+            DataLoad | ReturnDataLoad => (1, 1),
+            DataCopy => (3, 0),
+            DupN(n) => (*n as usize + 1, *n as usize + 2),
+            SwapN(n) => (*n as usize + 2, *n as usize + 2),
+            Exchange(x) => {
+                let n = (*x >> 4) as usize + (*x & 0x0f) as usize + 3;
+                (n, n)
+            }
+            EofCreate(_) | ExtCall => (4, 1),
+            ReturnContract(_) => (2, 0),
+            ExtDelegateCall | ExtStaticCall => (3, 1),
+            Address | Origin | Caller | CallValue | CallDataSize | CodeSize | GasPrice
+            | ReturnDataSize | Coinbase | Timestamp | Number | PrevRandao | GasLimit | ChainId
+            | SelfBalance | BaseFee | BlobBaseFee | Pc | MSize | Gas => (0, 1),
+            IsZero | Not | Balance | CallDataLoad | ExtCodeSize | ExtCodeHash | Blockhash
+            | Mload | Sload | Tload | BlobHash => (1, 1),
+            Pop | Jump | SelfDestruct | RJumpI(_) | RJumpV(_) => (1, 0),
+            Add | Mul | Sub | Div | Sdiv | Mod | SMod | Exp | SignExtend | Lt | Gt | Slt | Sgt
+            | Eq | And | Or | Xor | Byte | Shl | Shr | Sar | Keccack256 => (2, 1),
+            Mstore | MStore8 | SStore | TStore | JumpI | Return | Revert => (2, 0),
+            Addmod | Mulmod | Create => (3, 1),
+            CallDataCopy | CodeCopy | ReturnDataCopy | MCopy => (3, 0),
+            ExtCodeCopy => (4, 0),
+            Create2 => (4, 1),
+            DelegateCall | StaticCall => (6, 1),
+            Call | CallCode => (7, 1),
+            Push0 | Push1(_) | Push2(_) | Push3(_) | Push4(_) | Push5(_) | Push6(_) | Push7(_)
+            | Push8(_) | Push9(_) | Push10(_) | Push11(_) | Push12(_) | Push13(_) | Push14(_)
+            | Push15(_) | Push16(_) | Push17(_) | Push18(_) | Push19(_) | Push20(_) | Push21(_)
+            | Push22(_) | Push23(_) | Push24(_) | Push25(_) | Push26(_) | Push27(_) | Push28(_)
+            | Push29(_) | Push30(_) | Push31(_) | Push32(_) => (0, 1),
+            Dup1 | Dup2 | Dup3 | Dup4 | Dup5 | Dup6 | Dup7 | Dup8 | Dup9 | Dup10 | Dup11
+            | Dup12 | Dup13 | Dup14 | Dup15 | Dup16 => {
+                let n = self.get_dup_size().expect("is dup operation");
+                (n, n + 1)
+            }
             Swap1 | Swap2 | Swap3 | Swap4 | Swap5 | Swap6 | Swap7 | Swap8 | Swap9 | Swap10
-            | Swap11 | Swap12 | Swap13 | Swap14 | Swap15 | Swap16 => true,
-            _ => false,
+            | Swap11 | Swap12 | Swap13 | Swap14 | Swap15 | Swap16 => {
+                let n = self.get_swap_size().expect("is swap operation");
+                (n + 1, n + 1)
+            }
+            Log0 | Log1 | Log2 | Log3 | Log4 => {
+                let n = self.get_log_size().expect("is log operation");
+                (n + 2, 0)
+            }
         }
     }
 
-    pub fn gas_schedule_cost(&self) -> usize {
+    /// Returns the static gas cost charged before the operation executes.
+    pub fn gas_schedule_cost(&self, schedule: &GasSchedule) -> u64 {
         use Operation::*;
 
         match self {
-            Stop | Return | Revert => gas_schedule::G_ZERO,
+            Stop | Return | Revert | Invalid | Unknown(_) | SStore | ReturnContract(_) => {
+                schedule.zero
+            }
+            RJump(_) | DataSize => schedule.base,
+            RetF | DupN(_) | SwapN(_) | Exchange(_) | DataLoadN(_) | DataCopy | ReturnDataLoad => {
+                schedule.very_low
+            }
+            CallF(_) | JumpF(_) => schedule.low,
+            RJumpI(_) | RJumpV(_) => schedule.rjumpi,
+            DataLoad => schedule.data_load,
+            EofCreate(_) => schedule.create,
+            ExtCall | ExtDelegateCall | ExtStaticCall => schedule.warm_access,
+            JumpDest => schedule.jumpdest,
             Address | Origin | Caller | CallValue | CallDataSize | CodeSize | GasPrice
             | Coinbase | Timestamp | Number | PrevRandao | GasLimit | ChainId | ReturnDataSize
-            | Pop | Pc | MSize | Gas | BaseFee | Push0 => gas_schedule::G_BASE,
+            | Pop | Pc | MSize | Gas | BaseFee | BlobBaseFee | Push0 => schedule.base,
             Add | Sub | Not | Lt | Gt | Slt | Sgt | Eq | IsZero | And | Or | Xor | Byte | Shl
-            | Shr | Sar | CallDataLoad | Mload | Mstore | MStore8 => gas_schedule::G_VERY_LOW,
-            operation
-                // NOTE: Push0 should already match, but add conditional here for extra safety
-                if (operation.is_push() | operation.is_dup() | operation.is_swap())
-                    && operation != &Push0 =>
-            {
-                gas_schedule::G_VERY_LOW
+            | Shr | Sar | CallDataLoad | Mload | Mstore | MStore8 | CallDataCopy | CodeCopy
+            | ReturnDataCopy | MCopy | BlobHash => schedule.very_low,
+            Push1(_) | Push2(_) | Push3(_) | Push4(_) | Push5(_) | Push6(_) | Push7(_)
+            | Push8(_) | Push9(_) | Push10(_) | Push11(_) | Push12(_) | Push13(_) | Push14(_)
+            | Push15(_) | Push16(_) | Push17(_) | Push18(_) | Push19(_) | Push20(_) | Push21(_)
+            | Push22(_) | Push23(_) | Push24(_) | Push25(_) | Push26(_) | Push27(_) | Push28(_)
+            | Push29(_) | Push30(_) | Push31(_) | Push32(_) => schedule.very_low,
+            Dup1 | Dup2 | Dup3 | Dup4 | Dup5 | Dup6 | Dup7 | Dup8 | Dup9 | Dup10 | Dup11
+            | Dup12 | Dup13 | Dup14 | Dup15 | Dup16 => schedule.very_low,
+            Swap1 | Swap2 | Swap3 | Swap4 | Swap5 | Swap6 | Swap7 | Swap8 | Swap9 | Swap10
+            | Swap11 | Swap12 | Swap13 | Swap14 | Swap15 | Swap16 => schedule.very_low,
+            Mul | Div | Sdiv | Mod | SMod | SignExtend | SelfBalance => schedule.low,
+            Addmod | Mulmod | Jump => schedule.mid,
+            JumpI | Exp => schedule.high,
+            Keccack256 => schedule.keccak256,
+            Blockhash => schedule.blockhash,
+            Balance => schedule.balance,
+            ExtCodeSize | ExtCodeCopy => schedule.ext_code,
+            ExtCodeHash => schedule.ext_code_hash,
+            Sload => schedule.sload,
+            Tload | TStore => schedule.warm_access,
+            Call | CallCode | DelegateCall | StaticCall => schedule.call,
+            Create | Create2 => schedule.create,
+            SelfDestruct => schedule.self_destruct,
+            Log0 | Log1 | Log2 | Log3 | Log4 => {
+                let topics = self.get_log_size().expect("is log operation") as u64;
+                schedule.log + topics * schedule.log_topic
             }
-            Mul | Div | Sdiv | Mod | SMod | SignExtend | SelfBalance => gas_schedule::G_LOW,
-            Addmod | Mulmod | Jump => gas_schedule::G_MID,
-            JumpI => gas_schedule::G_HIGH,
-            _ => todo!(),
         }
     }
 
-    pub fn gas_used(&self) -> U256 {
-        U256::from(self.gas_schedule_cost())
+    pub fn program_counter_increment(&self) -> usize {
+        self.size()
     }
 
-    pub fn program_counter_increment(&self) -> usize {
-        if let Some(push_size) = self.get_push_size() {
-            program_counter_increment_for_push(push_size)
-        } else {
-            1
+    fn exit(&self, exit: Exit) -> OperationResult {
+        OperationResult {
+            exit: Some(exit),
+            ..self.into()
         }
     }
 
-    pub fn apply(
-        &self,
-        stack: &mut Stack,
-        memory: &mut Memory,
-        context: &Context,
-    ) -> Result<OperationResult, Error> {
+    fn jump(&self, location: CodeLocation) -> OperationResult {
+        OperationResult {
+            jump: Some(location),
+            ..self.into()
+        }
+    }
+
+    pub fn apply(&self, frame: &mut Frame, host: &mut dyn Host) -> Result<OperationResult, Error> {
         use Operation::*;
+
+        let schedule = host.gas_schedule();
+        let stack = &mut frame.stack;
         match self {
+            Stop => Ok(self.exit(Exit::Stop)),
             Add => self.do_add(stack),
+            Mul => self.do_binary(stack, |a, b| a * b),
+            Sub => self.do_binary(stack, |a, b| a - b),
+            Div => self.do_binary(stack, |a, b| a.checked_div(b).unwrap_or_default()),
+            Sdiv => self.do_binary(stack, sdiv),
+            Mod => self.do_binary(stack, |a, b| a.checked_rem(b).unwrap_or_default()),
+            SMod => self.do_binary(stack, smod),
+            Addmod => self.do_ternary(stack, |a, b, n| a.add_mod(b, n)),
+            Mulmod => self.do_ternary(stack, |a, b, n| a.mul_mod(b, n)),
+            Exp => self.do_exp(frame, schedule),
+            SignExtend => self.do_binary(stack, sign_extend),
+            Lt => self.do_binary(stack, |a, b| Word::from(a < b)),
+            Gt => self.do_binary(stack, |a, b| Word::from(a > b)),
+            Slt => self.do_binary(stack, |a, b| {
+                Word::from(I256::from_raw(a) < I256::from_raw(b))
+            }),
+            Sgt => self.do_binary(stack, |a, b| {
+                Word::from(I256::from_raw(a) > I256::from_raw(b))
+            }),
+            Eq => self.do_binary(stack, |a, b| Word::from(a == b)),
+            IsZero => self.do_unary(stack, |a| Word::from(a.is_zero())),
+            And => self.do_binary(stack, |a, b| a & b),
+            Or => self.do_binary(stack, |a, b| a | b),
+            Xor => self.do_binary(stack, |a, b| a ^ b),
+            Not => self.do_unary(stack, |a| !a),
+            Byte => self.do_binary(stack, byte),
+            Shl => self.do_binary(stack, |shift, value| value.wrapping_shl(to_shift(shift))),
+            Shr => self.do_binary(stack, |shift, value| value.wrapping_shr(to_shift(shift))),
+            Sar => self.do_binary(stack, sar),
+            Keccack256 => self.do_keccak256(frame, schedule),
+            Address => self.do_push_address(stack, frame.address),
+            Origin => self.do_push_address(stack, host.transaction().sender),
+            Caller => self.do_push_address(stack, frame.caller),
+            CallValue => self.do_push_word(stack, frame.value),
+            CallDataLoad => self.do_call_data_load(frame),
+            CallDataSize => self.do_push_word(stack, Word::from(frame.input.len())),
+            CallDataCopy => self.do_call_data_copy(frame, schedule),
+            CodeSize => self.do_push_word(stack, Word::from(frame.account.code.bytes().len())),
+            CodeCopy => self.do_code_copy(frame, schedule),
+            ReturnDataSize => self.do_push_word(stack, Word::from(frame.return_data.len())),
+            ReturnDataCopy => self.do_return_data_copy(frame, schedule),
+            Coinbase => self.do_push_address(stack, host.block().coinbase),
+            Timestamp => self.do_push_word(stack, Word::from(host.block().timestamp)),
+            Number => self.do_push_word(stack, Word::from(host.block().number)),
+            PrevRandao => self.do_push_word(stack, host.block().prev_randao.into()),
+            GasLimit => self.do_push_word(stack, Word::from(host.block().gas_limit)),
+            SelfBalance => self.do_self_balance(frame, host),
+            Pop => self.do_pop(stack),
+            Push0 => self.do_push(&[], stack),
             Push1(immediate) => self.do_push(&[*immediate], stack),
+            Push2(immediate) => self.do_push(immediate, stack),
+            Push3(immediate) => self.do_push(immediate, stack),
+            Push4(immediate) => self.do_push(immediate, stack),
+            Push5(immediate) => self.do_push(immediate, stack),
+            Push6(immediate) => self.do_push(immediate, stack),
+            Push7(immediate) => self.do_push(immediate, stack),
+            Push8(immediate) => self.do_push(immediate, stack),
+            Push9(immediate) => self.do_push(immediate, stack),
+            Push10(immediate) => self.do_push(immediate, stack),
+            Push11(immediate) => self.do_push(immediate, stack),
+            Push12(immediate) => self.do_push(immediate, stack),
+            Push13(immediate) => self.do_push(immediate, stack),
+            Push14(immediate) => self.do_push(immediate, stack),
+            Push15(immediate) => self.do_push(immediate, stack),
+            Push16(immediate) => self.do_push(immediate, stack),
+            Push17(immediate) => self.do_push(immediate, stack),
+            Push18(immediate) => self.do_push(immediate, stack),
+            Push19(immediate) => self.do_push(immediate, stack),
+            Push20(immediate) => self.do_push(immediate, stack),
+            Push21(immediate) => self.do_push(immediate, stack),
+            Push22(immediate) => self.do_push(immediate, stack),
+            Push23(immediate) => self.do_push(immediate, stack),
+            Push24(immediate) => self.do_push(immediate, stack),
+            Push25(immediate) => self.do_push(immediate, stack),
+            Push26(immediate) => self.do_push(immediate, stack),
+            Push27(immediate) => self.do_push(immediate, stack),
+            Push28(immediate) => self.do_push(immediate, stack),
+            Push29(immediate) => self.do_push(immediate, stack),
+            Push30(immediate) => self.do_push(immediate, stack),
+            Push31(immediate) => self.do_push(immediate, stack),
             Push32(immediate) => self.do_push(immediate, stack),
-            _ => todo!(),
+            Dup1 | Dup2 | Dup3 | Dup4 | Dup5 | Dup6 | Dup7 | Dup8 | Dup9 | Dup10 | Dup11
+            | Dup12 | Dup13 | Dup14 | Dup15 | Dup16 => self.do_dup(stack),
+            Swap1 | Swap2 | Swap3 | Swap4 | Swap5 | Swap6 | Swap7 | Swap8 | Swap9 | Swap10
+            | Swap11 | Swap12 | Swap13 | Swap14 | Swap15 | Swap16 => self.do_swap(stack),
+            DupN(n) => self.do_dupn(stack, *n),
+            SwapN(n) => self.do_swapn(stack, *n),
+            Exchange(x) => self.do_exchange(stack, *x),
+            RJump(relative) => self.do_rjump(frame, *relative),
+            RJumpI(relative) => self.do_rjumpi(frame, *relative),
+            RJumpV(max_index) => self.do_rjumpv(frame, *max_index),
+            CallF(section) => self.do_callf(frame, *section),
+            RetF => self.do_retf(frame),
+            JumpF(section) => self.do_jumpf(frame, *section),
+            DataLoad => self.do_data_load(frame),
+            DataLoadN(offset) => self.do_data_loadn(frame, *offset),
+            DataSize => self.do_data_size(frame),
+            DataCopy => self.do_data_copy(frame, schedule),
+            Jump => self.do_jump(frame),
+            JumpI => self.do_jumpi(frame),
+            Pc => self.do_push_word(stack, Word::from(frame.program_counter)),
+            JumpDest => Ok(self.into()),
+            Mload => self.do_mload(frame, schedule),
+            Mstore => self.do_mstore(frame, schedule),
+            MStore8 => self.do_mstore8(frame, schedule),
+            MSize => self.do_msize(frame),
+            MCopy => self.do_mcopy(frame, schedule),
+            Balance => self.do_balance(frame, host),
+            ExtCodeSize => self.do_ext_code_size(frame, host),
+            ExtCodeCopy => self.do_ext_code_copy(frame, host),
+            ExtCodeHash => self.do_ext_code_hash(frame, host),
+            Sload => self.do_sload(frame, host),
+            SStore => self.do_sstore(frame, host),
+            Tload => self.do_tload(frame, host),
+            TStore => self.do_tstore(frame, host),
+            Gas => self.do_gas(frame),
+            GasPrice => self.do_gas_price(stack, host),
+            BaseFee => self.do_base_fee(stack, host),
+            Blockhash => self.do_blockhash(stack, host),
+            BlobHash => self.do_blob_hash(stack, host),
+            BlobBaseFee => self.do_blob_base_fee(stack, host),
+            ChainId => self.do_chain_id(stack, host),
+            Log0 | Log1 | Log2 | Log3 | Log4 => self.do_log(frame, schedule),
+            Return => self.do_return(frame, schedule, Exit::Return),
+            Create | Create2 => self.do_create(frame, host),
+            Call | CallCode | DelegateCall | StaticCall => self.do_call(frame, host),
+            EofCreate(index) => self.do_eof_create(frame, host, *index),
+            ReturnContract(index) => self.do_return_contract(frame, schedule, *index),
+            ReturnDataLoad => self.do_return_data_load(frame),
+            ExtCall | ExtDelegateCall | ExtStaticCall => self.do_ext_call(frame, host),
+            SelfDestruct => self.do_self_destruct(frame, host),
+            Revert => self.do_return(frame, schedule, Exit::Revert),
+            Invalid | Unknown(_) => Err(HaltReason::InvalidOpcode(self.opcode()).into()),
         }
     }
 
@@ -309,6 +863,188 @@ impl Operation {
         Ok(self.into())
     }
 
+    pub fn do_unary(
+        &self,
+        stack: &mut Stack,
+        operation: impl FnOnce(Word) -> Word,
+    ) -> Result<OperationResult, Error> {
+        let a = stack.pop()?;
+        stack.push(operation(a))?;
+        Ok(self.into())
+    }
+
+    pub fn do_binary(
+        &self,
+        stack: &mut Stack,
+        operation: impl FnOnce(Word, Word) -> Word,
+    ) -> Result<OperationResult, Error> {
+        let a = stack.pop()?;
+        let b = stack.pop()?;
+        stack.push(operation(a, b))?;
+        Ok(self.into())
+    }
+
+    pub fn do_ternary(
+        &self,
+        stack: &mut Stack,
+        operation: impl FnOnce(Word, Word, Word) -> Word,
+    ) -> Result<OperationResult, Error> {
+        let a = stack.pop()?;
+        let b = stack.pop()?;
+        let c = stack.pop()?;
+        stack.push(operation(a, b, c))?;
+        Ok(self.into())
+    }
+
+    pub fn do_exp(
+        &self,
+        frame: &mut Frame,
+        schedule: &GasSchedule,
+    ) -> Result<OperationResult, Error> {
+        let base = frame.stack.pop()?;
+        let exponent = frame.stack.pop()?;
+        frame.charge_gas(schedule.exp_byte * exponent.byte_len() as u64)?;
+        frame.stack.push(base.wrapping_pow(exponent))?;
+        Ok(self.into())
+    }
+
+    pub fn do_keccak256(
+        &self,
+        frame: &mut Frame,
+        schedule: &GasSchedule,
+    ) -> Result<OperationResult, Error> {
+        let offset = frame.stack.pop()?;
+        let size = frame.stack.pop()?;
+        let (offset, size) = frame.expand_memory(offset, size, schedule)?;
+        frame.charge_gas(schedule.keccak256_word * size.div_ceil(32) as u64)?;
+        let hash = keccak256(frame.memory.slice(offset, size));
+        frame.stack.push(hash.into())?;
+        Ok(self.into())
+    }
+
+    pub fn do_push_word(&self, stack: &mut Stack, word: Word) -> Result<OperationResult, Error> {
+        stack.push(word)?;
+        Ok(self.into())
+    }
+
+    pub fn do_push_address(
+        &self,
+        stack: &mut Stack,
+        address: Address,
+    ) -> Result<OperationResult, Error> {
+        stack.push(address.into_word().into())?;
+        Ok(self.into())
+    }
+
+    pub fn do_call_data_load(&self, frame: &mut Frame) -> Result<OperationResult, Error> {
+        let offset = frame.stack.pop()?;
+        let word = padded_slice(&frame.input, offset, 32);
+        frame.stack.push(Word::from_be_slice(&word))?;
+        Ok(self.into())
+    }
+
+    pub fn do_call_data_copy(
+        &self,
+        frame: &mut Frame,
+        schedule: &GasSchedule,
+    ) -> Result<OperationResult, Error> {
+        let destination = frame.stack.pop()?;
+        let offset = frame.stack.pop()?;
+        let size = frame.stack.pop()?;
+        let (destination, size) = frame.expand_memory(destination, size, schedule)?;
+        frame.charge_gas(schedule.copy * size.div_ceil(32) as u64)?;
+        frame
+            .memory
+            .store(destination, &padded_slice(&frame.input, offset, size));
+        Ok(self.into())
+    }
+
+    pub fn do_code_copy(
+        &self,
+        frame: &mut Frame,
+        schedule: &GasSchedule,
+    ) -> Result<OperationResult, Error> {
+        let destination = frame.stack.pop()?;
+        let offset = frame.stack.pop()?;
+        let size = frame.stack.pop()?;
+        let (destination, size) = frame.expand_memory(destination, size, schedule)?;
+        frame.charge_gas(schedule.copy * size.div_ceil(32) as u64)?;
+        let code = padded_slice(frame.account.code.bytes(), offset, size);
+        frame.memory.store(destination, &code);
+        Ok(self.into())
+    }
+
+    pub fn do_return_data_copy(
+        &self,
+        frame: &mut Frame,
+        schedule: &GasSchedule,
+    ) -> Result<OperationResult, Error> {
+        let destination = frame.stack.pop()?;
+        let offset = frame.stack.pop()?;
+        let size = frame.stack.pop()?;
+        // Unlike other copies, reading beyond the end of the return data halts
+        let end = offset
+            .checked_add(size)
+            .filter(|end| *end <= Word::from(frame.return_data.len()))
+            .ok_or(HaltReason::ReturnDataOutOfBounds)?;
+        let (destination, size) = frame.expand_memory(destination, size, schedule)?;
+        frame.charge_gas(schedule.copy * size.div_ceil(32) as u64)?;
+        let start = end.to::<usize>() - size;
+        frame
+            .memory
+            .store(destination, &frame.return_data[start..start + size]);
+        Ok(self.into())
+    }
+
+    pub fn do_return_data_load(&self, frame: &mut Frame) -> Result<OperationResult, Error> {
+        let offset = frame.stack.pop()?;
+        // Unlike `RETURNDATACOPY`, reading beyond the end of the return data is padded
+        let word = padded_slice(&frame.return_data, offset, 32);
+        frame.stack.push(Word::from_be_slice(&word))?;
+        Ok(self.into())
+    }
+
+    pub fn do_self_balance(
+        &self,
+        frame: &mut Frame,
+        host: &dyn Host,
+    ) -> Result<OperationResult, Error> {
+        let balance = host.balance(&frame.address)?;
+        frame.stack.push(balance)?;
+        Ok(self.into())
+    }
+
+    pub fn do_jump(&self, frame: &mut Frame) -> Result<OperationResult, Error> {
+        let destination = frame.stack.pop()?;
+        Ok(self.jump(self.jump_destination(frame, destination)?))
+    }
+
+    pub fn do_jumpi(&self, frame: &mut Frame) -> Result<OperationResult, Error> {
+        let destination = frame.stack.pop()?;
+        let condition = frame.stack.pop()?;
+        if condition.is_zero() {
+            return Ok(self.into());
+        }
+        Ok(self.jump(self.jump_destination(frame, destination)?))
+    }
+
+    // Returns the location of the `JUMPDEST` at `destination` in legacy code.
+    fn jump_destination(&self, frame: &Frame, destination: Word) -> Result<CodeLocation, Error> {
+        let program_counter = usize::try_from(destination)
+            .ok()
+            .filter(|offset| frame.account.code.jump_destination(*offset).is_some())
+            .ok_or(HaltReason::InvalidJump)?;
+        Ok(CodeLocation {
+            code_section: 0,
+            program_counter,
+        })
+    }
+
+    pub fn do_pop(&self, stack: &mut Stack) -> Result<OperationResult, Error> {
+        stack.pop()?;
+        Ok(self.into())
+    }
+
     pub fn do_push<const N: usize>(
         &self,
         immediate: &[u8; N],
@@ -317,4 +1053,1143 @@ impl Operation {
         stack.push(Word::from_be_slice(immediate))?;
         Ok(self.into())
     }
+
+    pub fn do_dup(&self, stack: &mut Stack) -> Result<OperationResult, Error> {
+        let depth = self.get_dup_size().expect("is dup operation");
+        stack.dup(depth)?;
+        Ok(self.into())
+    }
+
+    pub fn do_swap(&self, stack: &mut Stack) -> Result<OperationResult, Error> {
+        let depth = self.get_swap_size().expect("is swap operation");
+        stack.swap(depth)?;
+        Ok(self.into())
+    }
+
+    pub fn do_dupn(&self, stack: &mut Stack, n: u8) -> Result<OperationResult, Error> {
+        stack.dup(n as usize + 1)?;
+        Ok(self.into())
+    }
+
+    pub fn do_swapn(&self, stack: &mut Stack, n: u8) -> Result<OperationResult, Error> {
+        stack.swap(n as usize + 1)?;
+        Ok(self.into())
+    }
+
+    pub fn do_exchange(&self, stack: &mut Stack, x: u8) -> Result<OperationResult, Error> {
+        let n = (x >> 4) as usize + 1;
+        let m = (x & 0x0f) as usize + 1;
+        stack.exchange(n, n + m)?;
+        Ok(self.into())
+    }
+
+    pub fn do_rjump(&self, frame: &mut Frame, relative: i16) -> Result<OperationResult, Error> {
+        Ok(self.jump(self.relative_location(frame, relative)?))
+    }
+
+    pub fn do_rjumpi(&self, frame: &mut Frame, relative: i16) -> Result<OperationResult, Error> {
+        let condition = frame.stack.pop()?;
+        if condition.is_zero() {
+            return Ok(self.into());
+        }
+        Ok(self.jump(self.relative_location(frame, relative)?))
+    }
+
+    pub fn do_rjumpv(&self, frame: &mut Frame, max_index: u8) -> Result<OperationResult, Error> {
+        let case = frame.stack.pop()?;
+        let Some(case) = usize::try_from(case)
+            .ok()
+            .filter(|case| *case <= max_index as usize)
+        else {
+            return Ok(self.into());
+        };
+        let (eof, section) = self.executing_eof(&frame.account, frame.code_section)?;
+        // The jump table follows the opcode and its count of entries
+        let entry = frame.program_counter + 2 + 2 * case;
+        let code = &eof.code_sections[section].code;
+        let relative = i16::from_be_bytes([code[entry], code[entry + 1]]);
+        Ok(self.jump(self.relative_location(frame, relative)?))
+    }
+
+    pub fn do_callf(&self, frame: &mut Frame, section: u16) -> Result<OperationResult, Error> {
+        let (eof, current) = self.executing_eof(&frame.account, frame.code_section)?;
+        let types = eof.code_sections[section as usize].types;
+        if frame.stack.len() + types.max_stack_increase as usize > MAXIMUM_STACK_SIZE {
+            return Err(HaltReason::Stack(StackError::Overflow).into());
+        }
+        if frame.return_stack.len() >= MAXIMUM_RETURN_STACK_SIZE {
+            return Err(HaltReason::ReturnStackOverflow.into());
+        }
+        frame.return_stack.push(CodeLocation {
+            code_section: current,
+            program_counter: frame.program_counter + self.size(),
+        });
+        Ok(self.jump(CodeLocation {
+            code_section: section as usize,
+            program_counter: 0,
+        }))
+    }
+
+    pub fn do_retf(&self, frame: &mut Frame) -> Result<OperationResult, Error> {
+        let location = frame.return_stack.pop().ok_or(StackError::Underflow)?;
+        Ok(self.jump(location))
+    }
+
+    pub fn do_jumpf(&self, frame: &mut Frame, section: u16) -> Result<OperationResult, Error> {
+        let (eof, _) = self.executing_eof(&frame.account, frame.code_section)?;
+        let types = eof.code_sections[section as usize].types;
+        if frame.stack.len() + types.max_stack_increase as usize > MAXIMUM_STACK_SIZE {
+            return Err(HaltReason::Stack(StackError::Overflow).into());
+        }
+        Ok(self.jump(CodeLocation {
+            code_section: section as usize,
+            program_counter: 0,
+        }))
+    }
+
+    pub fn do_data_load(&self, frame: &mut Frame) -> Result<OperationResult, Error> {
+        let offset = frame.stack.pop()?;
+        let (eof, _) = self.executing_eof(&frame.account, frame.code_section)?;
+        let word = padded_slice(&eof.data, offset, 32);
+        frame.stack.push(Word::from_be_slice(&word))?;
+        Ok(self.into())
+    }
+
+    pub fn do_data_loadn(&self, frame: &mut Frame, offset: u16) -> Result<OperationResult, Error> {
+        let (eof, _) = self.executing_eof(&frame.account, frame.code_section)?;
+        let word = padded_slice(&eof.data, Word::from(offset), 32);
+        frame.stack.push(Word::from_be_slice(&word))?;
+        Ok(self.into())
+    }
+
+    pub fn do_data_size(&self, frame: &mut Frame) -> Result<OperationResult, Error> {
+        let (eof, _) = self.executing_eof(&frame.account, frame.code_section)?;
+        frame.stack.push(Word::from(eof.data.len()))?;
+        Ok(self.into())
+    }
+
+    pub fn do_data_copy(
+        &self,
+        frame: &mut Frame,
+        schedule: &GasSchedule,
+    ) -> Result<OperationResult, Error> {
+        let destination = frame.stack.pop()?;
+        let offset = frame.stack.pop()?;
+        let size = frame.stack.pop()?;
+        let (destination, size) = frame.expand_memory(destination, size, schedule)?;
+        frame.charge_gas(schedule.copy * size.div_ceil(32) as u64)?;
+        let (eof, _) = self.executing_eof(&frame.account, frame.code_section)?;
+        frame
+            .memory
+            .store(destination, &padded_slice(&eof.data, offset, size));
+        Ok(self.into())
+    }
+
+    // Returns the EOF container being executed and its current code section; EOF
+    // operations are invalid in legacy code.
+    fn executing_eof<'a>(
+        &self,
+        account: &'a Account,
+        code_section: Option<usize>,
+    ) -> Result<(&'a Eof, usize), Error> {
+        account
+            .code
+            .eof()
+            .zip(code_section)
+            .ok_or(HaltReason::InvalidOpcode(self.opcode()).into())
+    }
+
+    // Returns the location `relative` bytes after the end of this operation.
+    fn relative_location(&self, frame: &Frame, relative: i16) -> Result<CodeLocation, Error> {
+        let code_section = frame
+            .code_section
+            .ok_or(HaltReason::InvalidOpcode(self.opcode()))?;
+        let program_counter = (frame.program_counter + self.size())
+            .checked_add_signed(relative as isize)
+            .ok_or(HaltReason::InvalidJump)?;
+        Ok(CodeLocation {
+            code_section,
+            program_counter,
+        })
+    }
+
+    pub fn do_gas(&self, frame: &mut Frame) -> Result<OperationResult, Error> {
+        let gas_remaining = frame.gas_remaining();
+        frame.stack.push(Word::from(gas_remaining))?;
+        Ok(self.into())
+    }
+
+    pub fn do_mload(
+        &self,
+        frame: &mut Frame,
+        schedule: &GasSchedule,
+    ) -> Result<OperationResult, Error> {
+        let offset = frame.stack.pop()?;
+        let (offset, _) = frame.expand_memory(offset, Word::from(32), schedule)?;
+        frame.stack.push(frame.memory.load(offset))?;
+        Ok(self.into())
+    }
+
+    pub fn do_mstore(
+        &self,
+        frame: &mut Frame,
+        schedule: &GasSchedule,
+    ) -> Result<OperationResult, Error> {
+        let offset = frame.stack.pop()?;
+        let value = frame.stack.pop()?;
+        let (offset, _) = frame.expand_memory(offset, Word::from(32), schedule)?;
+        frame.memory.store(offset, &value.to_be_bytes::<32>());
+        Ok(self.into())
+    }
+
+    pub fn do_mstore8(
+        &self,
+        frame: &mut Frame,
+        schedule: &GasSchedule,
+    ) -> Result<OperationResult, Error> {
+        let offset = frame.stack.pop()?;
+        let value = frame.stack.pop()?;
+        let (offset, _) = frame.expand_memory(offset, Word::from(1), schedule)?;
+        frame.memory.store(offset, &[value.byte(0)]);
+        Ok(self.into())
+    }
+
+    pub fn do_msize(&self, frame: &mut Frame) -> Result<OperationResult, Error> {
+        let size = frame.memory.size();
+        frame.stack.push(Word::from(size))?;
+        Ok(self.into())
+    }
+
+    pub fn do_mcopy(
+        &self,
+        frame: &mut Frame,
+        schedule: &GasSchedule,
+    ) -> Result<OperationResult, Error> {
+        let destination = frame.stack.pop()?;
+        let source = frame.stack.pop()?;
+        let size = frame.stack.pop()?;
+        let (source, _) = frame.expand_memory(source, size, schedule)?;
+        let (destination, size) = frame.expand_memory(destination, size, schedule)?;
+        frame.charge_gas(schedule.copy * size.div_ceil(32) as u64)?;
+        frame.memory.copy(source, destination, size);
+        Ok(self.into())
+    }
+
+    pub fn do_log(
+        &self,
+        frame: &mut Frame,
+        schedule: &GasSchedule,
+    ) -> Result<OperationResult, Error> {
+        if !frame.can_modify_state {
+            return Err(HaltReason::StaticViolation.into());
+        }
+        let topic_count = self.get_log_size().expect("is log operation");
+        let offset = frame.stack.pop()?;
+        let size = frame.stack.pop()?;
+        let topics = (0..topic_count)
+            .map(|_| {
+                frame
+                    .stack
+                    .pop()
+                    .map(|topic| B256::from(topic.to_be_bytes()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let (offset, size) = frame.expand_memory(offset, size, schedule)?;
+        frame.charge_gas(schedule.log_data * size as u64)?;
+        let data = frame.memory.slice(offset, size).to_vec();
+        frame
+            .logs
+            .push(Log::new_unchecked(frame.address, topics, data.into()));
+        Ok(self.into())
+    }
+
+    pub fn do_self_destruct(
+        &self,
+        frame: &mut Frame,
+        host: &mut dyn Host,
+    ) -> Result<OperationResult, Error> {
+        if !frame.can_modify_state {
+            return Err(HaltReason::StaticViolation.into());
+        }
+        let spec = host.spec();
+        let target = Address::from_word(frame.stack.pop()?.into());
+        if frame.warm_account(target, host) {
+            frame.charge_gas(host.gas_schedule().cold_account_access)?;
+        }
+        let schedule = host.gas_schedule();
+        // EIP-150 charges for creating the target, which EIP-161 limits to transfers of value
+        // to an empty account
+        // NOTE: empty accounts are treated as missing before EIP-161
+        if spec.is_enabled_in(SpecId::TangerineWhistle)
+            && host.load_account(&target)?.is_empty()
+            && (!spec.is_enabled_in(SpecId::SpuriousDragon)
+                || !host.balance(&frame.address)?.is_zero())
+        {
+            frame.charge_gas(schedule.new_account)?;
+        }
+        let refund = schedule.self_destruct_refund as i64;
+        if host.self_destruct(frame.address, target)? {
+            frame.gas_refunded += refund;
+        }
+        Ok(self.exit(Exit::Stop))
+    }
+
+    pub fn do_create(
+        &self,
+        frame: &mut Frame,
+        host: &mut dyn Host,
+    ) -> Result<OperationResult, Error> {
+        if !frame.can_modify_state {
+            return Err(HaltReason::StaticViolation.into());
+        }
+        let value = frame.stack.pop()?;
+        let offset = frame.stack.pop()?;
+        let size = frame.stack.pop()?;
+        let salt = match self {
+            Operation::Create2 => Some(frame.stack.pop()?),
+            _ => None,
+        };
+        let spec = host.spec();
+        // EIP-3860
+        if spec.is_enabled_in(SpecId::Shanghai) && size > Word::from(MAXIMUM_INITCODE_SIZE) {
+            return Err(HaltReason::OutOfGas.into());
+        }
+        let schedule = host.gas_schedule();
+        let (offset, size) = frame.expand_memory(offset, size, schedule)?;
+        let word_count = size.div_ceil(32) as u64;
+        frame.charge_gas(schedule.init_code_word * word_count)?;
+        // CREATE2 also pays to hash the initcode
+        if salt.is_some() {
+            frame.charge_gas(schedule.keccak256_word * word_count)?;
+        }
+        // EIP-150: all but one 64th of the remaining gas is passed on
+        let remaining = frame.gas_remaining();
+        let gas_limit = if spec.is_enabled_in(SpecId::TangerineWhistle) {
+            remaining - remaining / 64
+        } else {
+            remaining
+        };
+        frame.charge_gas(gas_limit)?;
+        let message = Message {
+            kind: match salt {
+                Some(salt) => CallKind::Create2 { salt },
+                None => CallKind::Create,
+            },
+            depth: frame.depth + 1,
+            gas_limit,
+            caller: frame.address,
+            value,
+            input: frame.memory.slice(offset, size).to_vec(),
+            ..Default::default()
+        };
+        self.send_create(frame, host, message)
+    }
+
+    pub fn do_eof_create(
+        &self,
+        frame: &mut Frame,
+        host: &mut dyn Host,
+        index: u8,
+    ) -> Result<OperationResult, Error> {
+        if !frame.can_modify_state {
+            return Err(HaltReason::StaticViolation.into());
+        }
+        let value = frame.stack.pop()?;
+        let salt = frame.stack.pop()?;
+        let input_offset = frame.stack.pop()?;
+        let input_size = frame.stack.pop()?;
+        let (input_offset, input_size) =
+            frame.expand_memory(input_offset, input_size, host.gas_schedule())?;
+        let (eof, _) = self.executing_eof(&frame.account, frame.code_section)?;
+        let code = eof.container_sections[index as usize].bytes().to_vec();
+        let remaining = frame.gas_remaining();
+        let gas_limit = remaining - remaining / 64;
+        frame.charge_gas(gas_limit)?;
+        let message = Message {
+            kind: CallKind::EofCreate { salt },
+            depth: frame.depth + 1,
+            gas_limit,
+            caller: frame.address,
+            value,
+            input: frame.memory.slice(input_offset, input_size).to_vec(),
+            code,
+            ..Default::default()
+        };
+        self.send_create(frame, host, message)
+    }
+
+    // Send `message` creating an account on behalf of `frame`, which has already been charged
+    // the gas passed on, and push the address of the account created or zero.
+    fn send_create(
+        &self,
+        frame: &mut Frame,
+        host: &mut dyn Host,
+        message: Message,
+    ) -> Result<OperationResult, Error> {
+        frame.return_data.clear();
+        let creator = host.load_account(&frame.address)?;
+        // EIP-2681: the nonce of the creator may not overflow
+        if frame.depth >= MAXIMUM_CALL_DEPTH
+            || creator.balance < message.value
+            || creator.nonce == u64::MAX
+        {
+            frame.gas_used -= message.gas_limit;
+            frame.stack.push(Word::ZERO)?;
+            return Ok(self.into());
+        }
+        let outcome = host.call(&message)?;
+
+        frame.gas_used -= message.gas_limit - outcome.result.gas_used();
+        // Only the output of initcode which reverts is kept as return data
+        if let ExecutionResult::Revert { output, .. } = &outcome.result {
+            frame.return_data = output.clone();
+        }
+        let address = match outcome.result {
+            ExecutionResult::Success { logs, .. } => {
+                frame.logs.extend(logs);
+                frame.gas_refunded += outcome.gas_refunded;
+                outcome.created_address.unwrap_or_default()
+            }
+            _ => Address::ZERO,
+        };
+        frame.stack.push(address.into_word().into())?;
+        Ok(self.into())
+    }
+
+    pub fn do_return_contract(
+        &self,
+        frame: &mut Frame,
+        schedule: &GasSchedule,
+        index: u8,
+    ) -> Result<OperationResult, Error> {
+        let offset = frame.stack.pop()?;
+        let size = frame.stack.pop()?;
+        let (offset, size) = frame.expand_memory(offset, size, schedule)?;
+        let (eof, _) = self.executing_eof(&frame.account, frame.code_section)?;
+        let container = eof.container_sections[index as usize]
+            .with_aux_data(frame.memory.slice(offset, size))
+            .ok_or(HaltReason::InvalidContractCode)?;
+        frame.output = container;
+        Ok(self.exit(Exit::Return))
+    }
+
+    pub fn do_call(
+        &self,
+        frame: &mut Frame,
+        host: &mut dyn Host,
+    ) -> Result<OperationResult, Error> {
+        let gas = frame.stack.pop()?;
+        let address = Address::from_word(frame.stack.pop()?.into());
+        let value = match self {
+            Operation::Call | Operation::CallCode => frame.stack.pop()?,
+            _ => Word::ZERO,
+        };
+        let input_offset = frame.stack.pop()?;
+        let input_size = frame.stack.pop()?;
+        let output_offset = frame.stack.pop()?;
+        let output_size = frame.stack.pop()?;
+        let transfers_value = !value.is_zero();
+        if *self == Operation::Call && transfers_value && !frame.can_modify_state {
+            return Err(HaltReason::StaticViolation.into());
+        }
+        let (input_offset, input_size) =
+            frame.expand_memory(input_offset, input_size, host.gas_schedule())?;
+        let (output_offset, output_size) =
+            frame.expand_memory(output_offset, output_size, host.gas_schedule())?;
+        access_call_target(frame, address, host)?;
+
+        let spec = host.spec();
+        let schedule = host.gas_schedule();
+        if transfers_value {
+            frame.charge_gas(schedule.call_value)?;
+        }
+        // As for `SELFDESTRUCT`, EIP-161 limits the charge for creating the recipient to
+        // transfers of value
+        // NOTE: empty accounts are treated as missing before EIP-161
+        if *self == Operation::Call
+            && (transfers_value || !spec.is_enabled_in(SpecId::SpuriousDragon))
+            && host.load_account(&address)?.is_empty()
+        {
+            frame.charge_gas(schedule.new_account)?;
+        }
+        let requested = u64::try_from(gas).unwrap_or(u64::MAX);
+        // EIP-150: at most all but one 64th of the remaining gas is passed on
+        let mut gas_limit = if spec.is_enabled_in(SpecId::TangerineWhistle) {
+            let remaining = frame.gas_remaining();
+            requested.min(remaining - remaining / 64)
+        } else {
+            requested
+        };
+        frame.charge_gas(gas_limit)?;
+        if transfers_value {
+            gas_limit += schedule.call_stipend;
+        }
+
+        frame.return_data.clear();
+        if frame.depth >= MAXIMUM_CALL_DEPTH
+            || (transfers_value && host.balance(&frame.address)? < value)
+        {
+            // The call fails without running, returning the gas passed to it
+            frame.gas_used -= gas_limit;
+            frame.stack.push(Word::ZERO)?;
+            return Ok(self.into());
+        }
+        let message = Message {
+            kind: match self {
+                Operation::DelegateCall => CallKind::DelegateCall,
+                Operation::CallCode => CallKind::CallCode,
+                _ => CallKind::Call,
+            },
+            is_static: !frame.can_modify_state || *self == Operation::StaticCall,
+            depth: frame.depth + 1,
+            gas_limit,
+            recipient: match self {
+                Operation::Call | Operation::StaticCall => address,
+                _ => frame.address,
+            },
+            caller: match self {
+                Operation::DelegateCall => frame.caller,
+                _ => frame.address,
+            },
+            value: match self {
+                Operation::DelegateCall => frame.value,
+                _ => value,
+            },
+            input: frame.memory.slice(input_offset, input_size).to_vec(),
+            code_address: address,
+            ..Default::default()
+        };
+        let outcome = host.call(&message)?;
+
+        let success = complete_call(frame, gas_limit, outcome).is_success();
+        let copied = output_size.min(frame.return_data.len());
+        frame
+            .memory
+            .store(output_offset, &frame.return_data[..copied]);
+        frame.stack.push(Word::from(success))?;
+        Ok(self.into())
+    }
+
+    pub fn do_ext_call(
+        &self,
+        frame: &mut Frame,
+        host: &mut dyn Host,
+    ) -> Result<OperationResult, Error> {
+        let address = frame.stack.pop()?;
+        let input_offset = frame.stack.pop()?;
+        let input_size = frame.stack.pop()?;
+        let value = match self {
+            Operation::ExtCall => frame.stack.pop()?,
+            _ => Word::ZERO,
+        };
+        // EIP-7069: the target must be an address rather than any word
+        if address.leading_zeros() < 96 {
+            return Err(HaltReason::InvalidAddress.into());
+        }
+        let address = Address::from_word(address.into());
+        let transfers_value = !value.is_zero();
+        if transfers_value && !frame.can_modify_state {
+            return Err(HaltReason::StaticViolation.into());
+        }
+        let (input_offset, input_size) =
+            frame.expand_memory(input_offset, input_size, host.gas_schedule())?;
+        access_call_target(frame, address, host)?;
+        if transfers_value {
+            let schedule = host.gas_schedule();
+            frame.charge_gas(schedule.call_value)?;
+            if host.load_account(&address)?.is_empty() {
+                frame.charge_gas(schedule.new_account)?;
+            }
+        }
+        let remaining = frame.gas_remaining();
+        let gas_limit = remaining.saturating_sub((remaining / 64).max(MINIMUM_RETAINED_GAS));
+
+        frame.return_data.clear();
+        // Calls which cannot run fail as though the callee reverted, without consuming gas
+        if gas_limit < MINIMUM_CALLEE_GAS
+            || frame.depth >= MAXIMUM_CALL_DEPTH
+            || (transfers_value && host.balance(&frame.address)? < value)
+            || (*self == Operation::ExtDelegateCall && !runs_eof(address, host)?)
+        {
+            frame.stack.push(Word::from(1))?;
+            return Ok(self.into());
+        }
+        frame.charge_gas(gas_limit)?;
+        let is_delegate = *self == Operation::ExtDelegateCall;
+        let message = Message {
+            kind: if is_delegate {
+                CallKind::DelegateCall
+            } else {
+                CallKind::Call
+            },
+            is_static: !frame.can_modify_state || *self == Operation::ExtStaticCall,
+            depth: frame.depth + 1,
+            gas_limit,
+            recipient: if is_delegate { frame.address } else { address },
+            caller: if is_delegate {
+                frame.caller
+            } else {
+                frame.address
+            },
+            value: if is_delegate { frame.value } else { value },
+            input: frame.memory.slice(input_offset, input_size).to_vec(),
+            code_address: address,
+            ..Default::default()
+        };
+        let outcome = host.call(&message)?;
+
+        let status = match complete_call(frame, gas_limit, outcome) {
+            ExecutionResult::Success { .. } => 0,
+            ExecutionResult::Revert { .. } => 1,
+            ExecutionResult::Halt { .. } => 2,
+        };
+        frame.stack.push(Word::from(status))?;
+        Ok(self.into())
+    }
+
+    pub fn do_return(
+        &self,
+        frame: &mut Frame,
+        schedule: &GasSchedule,
+        exit: Exit,
+    ) -> Result<OperationResult, Error> {
+        let offset = frame.stack.pop()?;
+        let size = frame.stack.pop()?;
+        let (offset, size) = frame.expand_memory(offset, size, schedule)?;
+        frame.output = frame.memory.slice(offset, size).to_vec();
+        Ok(self.exit(exit))
+    }
+
+    pub fn do_gas_price(
+        &self,
+        stack: &mut Stack,
+        host: &dyn Host,
+    ) -> Result<OperationResult, Error> {
+        let gas_price = host.transaction().effective_gas_price(host.base_fee());
+        stack.push(gas_price)?;
+        Ok(self.into())
+    }
+
+    pub fn do_base_fee(
+        &self,
+        stack: &mut Stack,
+        host: &dyn Host,
+    ) -> Result<OperationResult, Error> {
+        stack.push(host.block().base_fee)?;
+        Ok(self.into())
+    }
+
+    pub fn do_blockhash(
+        &self,
+        stack: &mut Stack,
+        host: &dyn Host,
+    ) -> Result<OperationResult, Error> {
+        let number = stack.pop()?;
+        let current = host.block().number;
+        // Only the hashes of the most recent blocks are available
+        let hash = match u64::try_from(number) {
+            Ok(number) if number < current && current - number <= MAXIMUM_BLOCKHASH_DEPTH => {
+                Word::from_be_bytes(host.block_hash(number)?.0)
+            }
+            _ => Word::ZERO,
+        };
+        stack.push(hash)?;
+        Ok(self.into())
+    }
+
+    pub fn do_blob_hash(
+        &self,
+        stack: &mut Stack,
+        host: &dyn Host,
+    ) -> Result<OperationResult, Error> {
+        let index = stack.pop()?;
+        let hash = usize::try_from(index)
+            .ok()
+            .and_then(|index| host.transaction().blob_versioned_hashes.get(index))
+            .map(|hash| Word::from_be_bytes(hash.0))
+            .unwrap_or_default();
+        stack.push(hash)?;
+        Ok(self.into())
+    }
+
+    pub fn do_blob_base_fee(
+        &self,
+        stack: &mut Stack,
+        host: &dyn Host,
+    ) -> Result<OperationResult, Error> {
+        stack.push(host.blob_base_fee())?;
+        Ok(self.into())
+    }
+
+    pub fn do_chain_id(
+        &self,
+        stack: &mut Stack,
+        host: &dyn Host,
+    ) -> Result<OperationResult, Error> {
+        stack.push(Word::from(host.chain_id()))?;
+        Ok(self.into())
+    }
+
+    pub fn do_balance(
+        &self,
+        frame: &mut Frame,
+        host: &mut dyn Host,
+    ) -> Result<OperationResult, Error> {
+        let address = Address::from_word(frame.stack.pop()?.into());
+        frame.access_account(address, host)?;
+        let balance = host.balance(&address)?;
+        frame.stack.push(balance)?;
+        Ok(self.into())
+    }
+
+    // EIP-7702: these read the code of the account itself, so a delegating account exposes its
+    // delegation designator rather than the code of its delegate
+    pub fn do_ext_code_size(
+        &self,
+        frame: &mut Frame,
+        host: &mut dyn Host,
+    ) -> Result<OperationResult, Error> {
+        let address = Address::from_word(frame.stack.pop()?.into());
+        frame.access_account(address, host)?;
+        let size = host.load_account(&address)?.code.bytes().len();
+        frame.stack.push(Word::from(size))?;
+        Ok(self.into())
+    }
+
+    pub fn do_ext_code_copy(
+        &self,
+        frame: &mut Frame,
+        host: &mut dyn Host,
+    ) -> Result<OperationResult, Error> {
+        let address = Address::from_word(frame.stack.pop()?.into());
+        let destination = frame.stack.pop()?;
+        let offset = frame.stack.pop()?;
+        let size = frame.stack.pop()?;
+        frame.access_account(address, host)?;
+        let schedule = host.gas_schedule();
+        let (destination, size) = frame.expand_memory(destination, size, schedule)?;
+        frame.charge_gas(schedule.copy * size.div_ceil(32) as u64)?;
+
+        let account = host.load_account(&address)?;
+        frame.memory.store(
+            destination,
+            &padded_slice(account.code.bytes(), offset, size),
+        );
+        Ok(self.into())
+    }
+
+    pub fn do_ext_code_hash(
+        &self,
+        frame: &mut Frame,
+        host: &mut dyn Host,
+    ) -> Result<OperationResult, Error> {
+        let address = Address::from_word(frame.stack.pop()?.into());
+        frame.access_account(address, host)?;
+        let hash = Some(host.load_account(&address)?)
+            .filter(|account| !account.is_empty())
+            .map(|account| Word::from_be_bytes(keccak256(account.code.bytes()).0))
+            .unwrap_or_default();
+        frame.stack.push(hash)?;
+        Ok(self.into())
+    }
+
+    pub fn do_sload(
+        &self,
+        frame: &mut Frame,
+        host: &mut dyn Host,
+    ) -> Result<OperationResult, Error> {
+        let key = frame.stack.pop()?;
+        if frame.warm_storage_key(key, host) {
+            let schedule = host.gas_schedule();
+            frame.charge_gas(schedule.cold_sload - schedule.warm_access)?;
+        }
+        frame.stack.push(host.load_storage(&frame.address, &key)?)?;
+        Ok(self.into())
+    }
+
+    pub fn do_sstore(
+        &self,
+        frame: &mut Frame,
+        host: &mut dyn Host,
+    ) -> Result<OperationResult, Error> {
+        if !frame.can_modify_state {
+            return Err(HaltReason::StaticViolation.into());
+        }
+        let net_metering = host.spec().is_enabled_in(SpecId::Istanbul);
+        // EIP-2200: storage may not be modified with only the call stipend remaining
+        if net_metering && frame.gas_remaining() <= host.gas_schedule().call_stipend {
+            return Err(HaltReason::OutOfGas.into());
+        }
+        let key = frame.stack.pop()?;
+        let value = frame.stack.pop()?;
+        if frame.warm_storage_key(key, host) {
+            frame.charge_gas(host.gas_schedule().cold_sload)?;
+        }
+        // As with an EVMC VM, the slot is written before being charged for, the write being
+        // undone with the frame if it runs out of gas
+        let status = host.store_storage(frame.address, key, value)?;
+        let (cost, refund) = sstore_cost(host.gas_schedule(), status, net_metering);
+        frame.charge_gas(cost)?;
+        frame.gas_refunded += refund;
+        Ok(self.into())
+    }
+
+    pub fn do_tload(&self, frame: &mut Frame, host: &dyn Host) -> Result<OperationResult, Error> {
+        let key = frame.stack.pop()?;
+        let value = host.load_transient_storage(&frame.address, &key);
+        frame.stack.push(value)?;
+        Ok(self.into())
+    }
+
+    pub fn do_tstore(
+        &self,
+        frame: &mut Frame,
+        host: &mut dyn Host,
+    ) -> Result<OperationResult, Error> {
+        if !frame.can_modify_state {
+            return Err(HaltReason::StaticViolation.into());
+        }
+        let key = frame.stack.pop()?;
+        let value = frame.stack.pop()?;
+        host.store_transient_storage(frame.address, key, value);
+        Ok(self.into())
+    }
+}
+
+// Charge `frame` for accessing the target of a call, and for its delegate if it has one.
+fn access_call_target(
+    frame: &mut Frame,
+    address: Address,
+    host: &mut dyn Host,
+) -> Result<(), Error> {
+    frame.access_account(address, host)?;
+    // EIP-7702: the code of a delegate runs in place of the designator, and is charged as
+    // a further account access
+    if let Some(delegate) = host.load_account(&address)?.code.delegation() {
+        frame.charge_gas(host.gas_schedule().warm_access)?;
+        frame.access_account(delegate, host)?;
+    }
+    Ok(())
+}
+
+// Whether a call to `address` runs EOF code, following any delegation.
+fn runs_eof(address: Address, host: &dyn Host) -> Result<bool, Error> {
+    let account = host.load_account(&address)?;
+    let account = match account.code.delegation() {
+        Some(delegate) => host.load_account(&delegate)?,
+        None => account,
+    };
+    Ok(account.code.eof().is_some())
+}
+
+// Return the gas `outcome` left unused to `frame`, which passed `gas_limit` to the call, and
+// keep its output as the return data.
+fn complete_call(frame: &mut Frame, gas_limit: u64, outcome: CallOutcome) -> ExecutionResult {
+    // Unused gas, including any of the stipend, is returned to this frame
+    frame.gas_used -= gas_limit - outcome.result.gas_used();
+    frame.return_data = outcome.result.output().unwrap_or_default().to_vec();
+    let mut result = outcome.result;
+    if let ExecutionResult::Success { logs, .. } = &mut result {
+        frame.logs.append(logs);
+        frame.gas_refunded += outcome.gas_refunded;
+    }
+    result
+}
+
+/// Returns the cost and refund of an `SSTORE` with the effect `status`, metered per EIP-2200
+/// when `net_metering`, or otherwise from the current and new values alone.
+fn sstore_cost(schedule: &GasSchedule, status: StorageStatus, net_metering: bool) -> (u64, i64) {
+    use StorageStatus::*;
+
+    let clear_refund = schedule.sclear_refund as i64;
+    if !net_metering {
+        return match status {
+            Added | DeletedAdded | DeletedRestored => (schedule.sset, 0),
+            Deleted | ModifiedDeleted | AddedDeleted => (schedule.sreset, clear_refund),
+            Assigned | Modified | ModifiedRestored => (schedule.sreset, 0),
+        };
+    }
+    let sload = schedule.sload;
+    match status {
+        Assigned => (sload, 0),
+        Added => (schedule.sset, 0),
+        Deleted => (schedule.sreset, clear_refund),
+        Modified => (schedule.sreset, 0),
+        DeletedAdded => (sload, -clear_refund),
+        ModifiedDeleted => (sload, clear_refund),
+        DeletedRestored => (sload, (schedule.sreset - sload) as i64 - clear_refund),
+        AddedDeleted => (sload, (schedule.sset - sload) as i64),
+        ModifiedRestored => (sload, (schedule.sreset - sload) as i64),
+    }
+}
+
+fn sdiv(a: Word, b: Word) -> Word {
+    if b.is_zero() {
+        return Word::ZERO;
+    }
+    // `MIN / -1` overflows back to `MIN`
+    I256::from_raw(a).wrapping_div(I256::from_raw(b)).into_raw()
+}
+
+fn smod(a: Word, b: Word) -> Word {
+    if b.is_zero() {
+        return Word::ZERO;
+    }
+    // The result takes the sign of the dividend
+    I256::from_raw(a).wrapping_rem(I256::from_raw(b)).into_raw()
+}
+
+// Extends the sign of the two's complement integer in the low `size + 1` bytes of `value`.
+fn sign_extend(size: Word, value: Word) -> Word {
+    if size >= Word::from(31) {
+        return value;
+    }
+    let sign_bit = size.to::<usize>() * 8 + 7;
+    let mask = (Word::from(1) << sign_bit) - Word::from(1);
+    if value.bit(sign_bit) {
+        value | !mask
+    } else {
+        value & mask
+    }
+}
+
+// Returns the byte of `value` at `index`, counting from the most significant byte.
+fn byte(index: Word, value: Word) -> Word {
+    match usize::try_from(index) {
+        Ok(index) if index < 32 => Word::from(value.byte(31 - index)),
+        _ => Word::ZERO,
+    }
+}
+
+fn sar(shift: Word, value: Word) -> Word {
+    // Shifting a negative value by 255 or more bits leaves -1
+    I256::from_raw(value)
+        .asr(to_shift(shift).min(255))
+        .into_raw()
+}
+
+// Returns `shift` as a bit count, saturating at a shift which clears a word.
+fn to_shift(shift: Word) -> usize {
+    usize::try_from(shift).unwrap_or(usize::MAX).min(256)
+}
+
+// Returns `size` bytes of `data` from `offset`, reading zeros beyond its end.
+fn padded_slice(data: &[u8], offset: Word, size: usize) -> Vec<u8> {
+    let offset = usize::try_from(offset)
+        .unwrap_or(usize::MAX)
+        .min(data.len());
+    let available = &data[offset..data.len().min(offset.saturating_add(size))];
+    let mut padded = vec![0; size];
+    padded[..available.len()].copy_from_slice(available);
+    padded
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        bytecode::Bytecode,
+        context::{Block, State, Transaction},
+        database::InMemoryDatabase,
+        primitives::U256,
+        Context,
+    };
+
+    #[test]
+    fn test_opcode_round_trip() {
+        for opcode in 0..=u8::MAX {
+            let bytecode = Bytecode::from([opcode].as_slice());
+            assert_eq!(bytecode[0].opcode(), opcode);
+        }
+    }
+
+    // Applies `operation` to `inputs`, given with the top of the stack first
+    fn apply(operation: Operation, inputs: &[Word]) -> Word {
+        let mut frame = Frame::default();
+        frame.gas_limit = 1_000;
+        for input in inputs.iter().rev() {
+            frame.stack.push(*input).unwrap();
+        }
+        operation
+            .apply(&mut frame, &mut Context::default())
+            .unwrap();
+        frame.stack.pop().unwrap()
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let minus = |value: u64| U256::ZERO - U256::from(value);
+        let min = U256::from(1) << 255;
+        assert_eq!(
+            apply(Operation::Sub, &[U256::ZERO, U256::from(1)]),
+            minus(1)
+        );
+        assert_eq!(apply(Operation::Mul, &[U256::MAX, U256::from(2)]), minus(2));
+        assert_eq!(
+            apply(Operation::Div, &[U256::from(7), U256::ZERO]),
+            U256::ZERO
+        );
+        assert_eq!(apply(Operation::Sdiv, &[minus(7), U256::from(2)]), minus(3));
+        assert_eq!(apply(Operation::Sdiv, &[min, minus(1)]), min);
+        assert_eq!(
+            apply(Operation::Mod, &[U256::from(7), U256::ZERO]),
+            U256::ZERO
+        );
+        assert_eq!(apply(Operation::SMod, &[minus(7), U256::from(2)]), minus(1));
+        assert_eq!(
+            apply(
+                Operation::Addmod,
+                &[U256::MAX, U256::from(2), U256::from(3)]
+            ),
+            U256::from(2)
+        );
+        assert_eq!(
+            apply(Operation::Mulmod, &[U256::MAX, U256::MAX, U256::ZERO]),
+            U256::ZERO
+        );
+        assert_eq!(
+            apply(Operation::Exp, &[U256::from(2), U256::from(256)]),
+            U256::ZERO
+        );
+        assert_eq!(
+            apply(Operation::SignExtend, &[U256::ZERO, U256::from(0xff)]),
+            minus(1)
+        );
+        assert_eq!(
+            apply(Operation::SignExtend, &[U256::from(1), U256::from(0x017f)]),
+            U256::from(0x017f)
+        );
+    }
+
+    #[test]
+    fn test_comparison_and_bitwise() {
+        let minus_one = U256::MAX;
+        assert_eq!(
+            apply(Operation::Lt, &[U256::from(1), minus_one]),
+            U256::from(1)
+        );
+        assert_eq!(
+            apply(Operation::Slt, &[U256::from(1), minus_one]),
+            U256::ZERO
+        );
+        assert_eq!(
+            apply(Operation::Sgt, &[U256::from(1), minus_one]),
+            U256::from(1)
+        );
+        assert_eq!(
+            apply(Operation::Byte, &[U256::from(31), U256::from(0xab)]),
+            U256::from(0xab)
+        );
+        assert_eq!(
+            apply(Operation::Byte, &[U256::from(32), minus_one]),
+            U256::ZERO
+        );
+        assert_eq!(
+            apply(Operation::Shl, &[U256::from(256), minus_one]),
+            U256::ZERO
+        );
+        assert_eq!(
+            apply(Operation::Shr, &[U256::from(255), minus_one]),
+            U256::from(1)
+        );
+        assert_eq!(apply(Operation::Sar, &[U256::MAX, minus_one]), minus_one);
+        assert_eq!(
+            apply(Operation::Sar, &[U256::from(1), U256::from(5)]),
+            U256::from(2)
+        );
+    }
+
+    #[test]
+    fn test_gas_price() {
+        let mut context = Context::default();
+        context.block = Block {
+            base_fee: U256::from(7),
+            ..Default::default()
+        };
+        context.transaction = Transaction {
+            max_fee_per_gas: Some(U256::from(10)),
+            max_priority_fee_per_gas: U256::from(2),
+            ..Default::default()
+        };
+        let mut frame = Frame::default();
+        Operation::GasPrice.apply(&mut frame, &mut context).unwrap();
+        Operation::BaseFee.apply(&mut frame, &mut context).unwrap();
+        assert_eq!(frame.stack.as_slice(), &[U256::from(9), U256::from(7)]);
+    }
+
+    #[test]
+    fn test_blob_operations() {
+        let hash = B256::repeat_byte(0x01);
+        let mut context = Context::default();
+        context.block = Block {
+            excess_blob_gas: 10_000_000,
+            ..Default::default()
+        };
+        context.transaction = Transaction {
+            blob_versioned_hashes: vec![hash],
+            ..Default::default()
+        };
+        let mut frame = Frame::default();
+        for index in [0, 1] {
+            frame.stack.push(U256::from(index)).unwrap();
+            Operation::BlobHash.apply(&mut frame, &mut context).unwrap();
+        }
+        Operation::BlobBaseFee
+            .apply(&mut frame, &mut context)
+            .unwrap();
+        assert_eq!(
+            frame.stack.as_slice(),
+            &[U256::from_be_bytes(hash.0), U256::ZERO, U256::from(7)]
+        );
+    }
+
+    #[test]
+    fn test_blockhash() {
+        let hash = |number: u64| B256::from(U256::from(number));
+        let mut database = InMemoryDatabase::default();
+        for number in [43, 44, 299] {
+            database.insert_block_hash(number, hash(number));
+        }
+        let mut context = Context::default();
+        context.block = Block {
+            number: 300,
+            ..Default::default()
+        };
+        context.state = State::new(database);
+        let mut frame = Frame::default();
+        for number in [43, 44, 299, 300] {
+            frame.stack.push(U256::from(number)).unwrap();
+            Operation::Blockhash
+                .apply(&mut frame, &mut context)
+                .unwrap();
+        }
+        assert_eq!(
+            frame.stack.as_slice(),
+            &[U256::ZERO, U256::from(44), U256::from(299), U256::ZERO]
+        );
+    }
+
+    #[test]
+    fn test_gas_cost_by_spec() {
+        assert_eq!(
+            Operation::Sload.gas_schedule_cost(&GasSchedule::for_spec(SpecId::Frontier)),
+            50
+        );
+        assert_eq!(
+            Operation::Sload.gas_schedule_cost(&GasSchedule::for_spec(SpecId::Istanbul)),
+            800
+        );
+        assert_eq!(
+            Operation::Sload.gas_schedule_cost(&GasSchedule::for_spec(SpecId::Berlin)),
+            100
+        );
+        assert_eq!(
+            Operation::Balance.gas_schedule_cost(&GasSchedule::for_spec(SpecId::Byzantium)),
+            400
+        );
+        assert_eq!(
+            Operation::SelfDestruct.gas_schedule_cost(&GasSchedule::for_spec(SpecId::Homestead)),
+            0
+        );
+    }
 }
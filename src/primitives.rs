@@ -1,2 +1,11 @@
 pub type U256 = alloy::primitives::U256;
 pub type Address = alloy::primitives::Address;
+pub type B256 = alloy::primitives::B256;
+pub type I256 = alloy::primitives::I256;
+pub type Bytes = alloy::primitives::Bytes;
+pub type Log = alloy::primitives::Log;
+pub type Signature = alloy::primitives::Signature;
+pub type TxKind = alloy::primitives::TxKind;
+pub type AccessList = alloy::eips::eip2930::AccessList;
+pub type AccessListItem = alloy::eips::eip2930::AccessListItem;
+pub type SignedAuthorization = alloy::eips::eip7702::SignedAuthorization;
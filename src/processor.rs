@@ -0,0 +1,844 @@
+use crate::{
+    bytecode::Bytecode,
+    context::{Substate, TransactionType},
+    host::{CallKind, Message},
+    interpreter::{
+        on_execution_stack, Execution, ExecutionResult, Frame, Interpreter, MAXIMUM_INITCODE_SIZE,
+    },
+    primitives::{Address, TxKind, U256},
+    spec::SpecId,
+    Context, Error, Host,
+};
+use thiserror::Error;
+
+// EIP-4844: version byte of blob versioned hashes derived from KZG commitments
+const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// Reasons a transaction cannot be included, checked before any state is modified.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum InvalidTransaction {
+    #[error("expected nonce {expected} but transaction has nonce {actual}")]
+    NonceMismatch { expected: u64, actual: u64 },
+    #[error("sender nonce cannot be incremented")]
+    NonceOverflow,
+    #[error("sender has deployed code")]
+    SenderHasCode,
+    #[error("sender balance {available} is less than the required {required}")]
+    InsufficientFunds { required: U256, available: U256 },
+    #[error("gas limit {gas_limit} exceeds the block gas limit {block_gas_limit}")]
+    GasLimitExceedsBlockGasLimit {
+        gas_limit: u64,
+        block_gas_limit: u64,
+    },
+    #[error("initcode of {size} bytes exceeds the maximum of {max}")]
+    InitcodeTooLarge { size: usize, max: usize },
+    #[error("intrinsic gas {intrinsic_gas} exceeds the gas limit {gas_limit}")]
+    IntrinsicGasTooLow { intrinsic_gas: u64, gas_limit: u64 },
+    #[error("floor data gas {floor_data_gas} exceeds the gas limit {gas_limit}")]
+    FloorDataGasTooLow { floor_data_gas: u64, gas_limit: u64 },
+    #[error("fee market transactions are not supported before London")]
+    FeeMarketNotSupported,
+    #[error("max fee per gas {max_fee_per_gas} is less than the block base fee {base_fee}")]
+    GasPriceLessThanBaseFee {
+        max_fee_per_gas: U256,
+        base_fee: U256,
+    },
+    #[error("max priority fee per gas exceeds the max fee per gas")]
+    PriorityFeeGreaterThanMaxFee,
+    #[error("invalid signature")]
+    InvalidSignature,
+    #[error("transaction is for chain {actual} but expected chain {expected}")]
+    ChainIdMismatch { expected: u64, actual: u64 },
+    #[error("blob transactions are not supported before Cancun")]
+    BlobsNotSupported,
+    #[error("blob transaction has no blobs")]
+    EmptyBlobs,
+    #[error("transaction has {actual} blobs but at most {max} are allowed")]
+    TooManyBlobs { max: u64, actual: u64 },
+    #[error("blob versioned hash has an unsupported version")]
+    InvalidBlobVersionedHash,
+    #[error("max fee per blob gas {max_fee_per_blob_gas} is less than the blob base fee {blob_base_fee}")]
+    BlobGasPriceLessThanBlobBaseFee {
+        max_fee_per_blob_gas: U256,
+        blob_base_fee: U256,
+    },
+    #[error("set code transactions are not supported before Prague")]
+    SetCodeNotSupported,
+    #[error("set code transaction has no authorizations")]
+    EmptyAuthorizationList,
+}
+
+impl Interpreter {
+    /// Validate and execute `context.transaction`, charging fees against `context.state`.
+    ///
+    /// The sender's nonce is incremented, any EIP-7702 authorizations are applied and the gas
+    /// fee is paid whether or not execution succeeds; the value transfer is only kept on
+    /// success. The base fee portion of the fee is burned and the remainder paid to the
+    /// coinbase. The reported gas used is net of refunds, as it would appear in a receipt.
+    pub fn transact(self, context: Context) -> Result<Execution, Error> {
+        let (mut execution, coinbase_fee) = self.transact_deferring_coinbase_fee(context)?;
+        // Crediting nothing would create an empty coinbase account, which EIP-161 forbids
+        if !coinbase_fee.is_zero() {
+            let coinbase = execution.context.block.coinbase;
+            execution.context.state.account_mut(coinbase)?.balance += coinbase_fee;
+        }
+        Ok(execution)
+    }
+
+    /// As `transact`, but returns the fee owed to the coinbase instead of paying it, so that
+    /// transactions executed concurrently do not all conflict over the coinbase balance.
+    pub(crate) fn transact_deferring_coinbase_fee(
+        self,
+        mut context: Context,
+    ) -> Result<(Execution, U256), Error> {
+        let transaction = &context.transaction;
+        if let Some(chain_id) = transaction.chain_id {
+            if chain_id != context.chain_id {
+                return Err(InvalidTransaction::ChainIdMismatch {
+                    expected: context.chain_id,
+                    actual: chain_id,
+                }
+                .into());
+            }
+        }
+        if transaction.gas_limit > context.block.gas_limit {
+            return Err(InvalidTransaction::GasLimitExceedsBlockGasLimit {
+                gas_limit: transaction.gas_limit,
+                block_gas_limit: context.block.gas_limit,
+            }
+            .into());
+        }
+        let base_fee = context.base_fee();
+        if transaction.max_fee_per_gas.is_some() && !context.spec().is_enabled_in(SpecId::London) {
+            return Err(InvalidTransaction::FeeMarketNotSupported.into());
+        }
+        let max_fee_per_gas = transaction.max_gas_price();
+        if transaction.max_priority_fee_per_gas > max_fee_per_gas {
+            return Err(InvalidTransaction::PriorityFeeGreaterThanMaxFee.into());
+        }
+        if max_fee_per_gas < base_fee {
+            return Err(InvalidTransaction::GasPriceLessThanBaseFee {
+                max_fee_per_gas,
+                base_fee,
+            }
+            .into());
+        }
+        let gas_price = transaction.effective_gas_price(base_fee);
+
+        let blob_gas = U256::from(transaction.blob_gas(context.gas_schedule()));
+        let blob_base_fee = context.blob_base_fee();
+        let max_fee_per_blob_gas = match transaction.max_fee_per_blob_gas {
+            Some(max_fee_per_blob_gas) => {
+                if !context.spec().is_enabled_in(SpecId::Cancun) {
+                    return Err(InvalidTransaction::BlobsNotSupported.into());
+                }
+                let hashes = &transaction.blob_versioned_hashes;
+                if hashes.is_empty() {
+                    return Err(InvalidTransaction::EmptyBlobs.into());
+                }
+                let max = context.gas_schedule().max_blobs_per_block;
+                if hashes.len() as u64 > max {
+                    return Err(InvalidTransaction::TooManyBlobs {
+                        max,
+                        actual: hashes.len() as u64,
+                    }
+                    .into());
+                }
+                if hashes
+                    .iter()
+                    .any(|hash| hash[0] != VERSIONED_HASH_VERSION_KZG)
+                {
+                    return Err(InvalidTransaction::InvalidBlobVersionedHash.into());
+                }
+                if max_fee_per_blob_gas < blob_base_fee {
+                    return Err(InvalidTransaction::BlobGasPriceLessThanBlobBaseFee {
+                        max_fee_per_blob_gas,
+                        blob_base_fee,
+                    }
+                    .into());
+                }
+                max_fee_per_blob_gas
+            }
+            None => U256::ZERO,
+        };
+
+        let is_set_code = transaction.transaction_type == TransactionType::Eip7702
+            || !transaction.authorization_list.is_empty();
+        if is_set_code {
+            if !context.spec().is_enabled_in(SpecId::Prague) {
+                return Err(InvalidTransaction::SetCodeNotSupported.into());
+            }
+            if transaction.authorization_list.is_empty() {
+                return Err(InvalidTransaction::EmptyAuthorizationList.into());
+            }
+        }
+
+        // EIP-3860
+        if transaction.to.is_create()
+            && context.spec().is_enabled_in(SpecId::Shanghai)
+            && transaction.input.len() > MAXIMUM_INITCODE_SIZE
+        {
+            return Err(InvalidTransaction::InitcodeTooLarge {
+                size: transaction.input.len(),
+                max: MAXIMUM_INITCODE_SIZE,
+            }
+            .into());
+        }
+
+        let intrinsic_gas = transaction.intrinsic_gas(context.gas_schedule());
+        if intrinsic_gas > transaction.gas_limit {
+            return Err(InvalidTransaction::IntrinsicGasTooLow {
+                intrinsic_gas,
+                gas_limit: transaction.gas_limit,
+            }
+            .into());
+        }
+        // EIP-7623
+        let floor_data_gas = transaction.floor_data_gas(context.gas_schedule());
+        if floor_data_gas > transaction.gas_limit {
+            return Err(InvalidTransaction::FloorDataGasTooLow {
+                floor_data_gas,
+                gas_limit: transaction.gas_limit,
+            }
+            .into());
+        }
+
+        let sender = context.state.account(&transaction.sender)?;
+        // EIP-3607, relaxed by EIP-7702 to allow senders which delegate their code
+        if !sender.code.is_empty() && sender.code.delegation().is_none() {
+            return Err(InvalidTransaction::SenderHasCode.into());
+        }
+        if sender.nonce != transaction.nonce {
+            return Err(InvalidTransaction::NonceMismatch {
+                expected: sender.nonce,
+                actual: transaction.nonce,
+            }
+            .into());
+        }
+        let nonce = sender
+            .nonce
+            .checked_add(1)
+            .ok_or(InvalidTransaction::NonceOverflow)?;
+        let gas_limit = U256::from(transaction.gas_limit);
+        // The blob fee is burned in full and never refunded
+        let gas_fee = gas_limit * gas_price + blob_gas * blob_base_fee;
+        let required = gas_limit
+            .saturating_mul(max_fee_per_gas)
+            .saturating_add(blob_gas.saturating_mul(max_fee_per_blob_gas))
+            .saturating_add(transaction.value);
+        if sender.balance < required {
+            return Err(InvalidTransaction::InsufficientFunds {
+                required,
+                available: sender.balance,
+            }
+            .into());
+        }
+
+        let (sender_address, to, value) = (transaction.sender, transaction.to, transaction.value);
+        let account = context.state.account_mut(sender_address)?;
+        account.balance -= gas_fee;
+        // Creation increments the nonce itself, having derived the new address from it
+        if to.is_call() {
+            account.nonce = nonce;
+        }
+        let (authorities, authorization_refund) = apply_authorizations(&mut context)?;
+
+        context.substate = Substate::for_transaction(&context)?;
+        context.substate.accessed_addresses.extend(authorities);
+        let gas_limit = context.transaction.gas_limit - intrinsic_gas;
+        // Nested frames recurse on the native stack, so execution gets a stack that fits them
+        let (frame, result) = on_execution_stack(|| match to {
+            TxKind::Call(recipient) => {
+                let checkpoint = context.checkpoint();
+                context.transfer(sender_address, recipient, value)?;
+                let mut frame = Frame::try_from(&context)?;
+                frame.gas_limit = gas_limit;
+                let (frame, result) = self.execute_with_host(frame, &mut context)?;
+                if !result.is_success() {
+                    context.revert(checkpoint)?;
+                }
+                Ok((frame, result))
+            }
+            TxKind::Create => {
+                let message = Message {
+                    kind: CallKind::Create,
+                    gas_limit,
+                    caller: sender_address,
+                    value,
+                    input: context.transaction.input.clone(),
+                    ..Default::default()
+                };
+                // Creation transfers the value and reverts its own changes on failure
+                let outcome = context.call(&message)?;
+                // The initcode ran in a frame of the host's, which is not kept
+                Ok((Frame::default(), outcome.result))
+            }
+        })?;
+        context.remove_self_destructs();
+        context.remove_touched_empty_accounts()?;
+        let mut execution = Execution {
+            context,
+            frame,
+            result,
+        };
+
+        let context = &execution.context;
+        let gas_used = intrinsic_gas + execution.result.gas_used();
+        let gas_refunded = match execution.result {
+            ExecutionResult::Success { gas_refunded, .. } => gas_refunded,
+            _ => 0,
+        };
+        let gas_refunded = (gas_refunded + authorization_refund)
+            .min(gas_used / context.gas_schedule().max_refund_quotient);
+        let gas_used = (gas_used - gas_refunded).max(floor_data_gas);
+        let refund = U256::from(context.transaction.gas_limit - gas_used) * gas_price;
+        let coinbase_fee = U256::from(gas_used) * (gas_price - base_fee);
+        execution.context.state.account_mut(sender_address)?.balance += refund;
+
+        execution.result = match execution.result {
+            ExecutionResult::Success { output, logs, .. } => ExecutionResult::Success {
+                output,
+                logs,
+                gas_used,
+                gas_refunded,
+            },
+            ExecutionResult::Revert { output, .. } => ExecutionResult::Revert { output, gas_used },
+            ExecutionResult::Halt { reason, .. } => ExecutionResult::Halt { reason, gas_used },
+        };
+        Ok((execution, coinbase_fee))
+    }
+}
+
+/// Apply the valid EIP-7702 authorizations of `context.transaction` to `context.state`, in
+/// order, returning the authorities to warm along with the gas to refund.
+///
+/// Invalid authorizations are skipped rather than invalidating the transaction.
+fn apply_authorizations(context: &mut Context) -> Result<(Vec<Address>, u64), Error> {
+    let schedule = context.gas_schedule();
+    let existing_authority_refund = schedule.authorization - schedule.authorization_base;
+    let mut authorities = vec![];
+    let mut refund = 0;
+    for authorization in &context.transaction.authorization_list {
+        if authorization.chain_id != 0 && authorization.chain_id != context.chain_id {
+            continue;
+        }
+        if authorization.nonce == u64::MAX {
+            continue;
+        }
+        let Ok(authority) = authorization.recover_authority() else {
+            continue;
+        };
+        authorities.push(authority);
+
+        let account = context.state.get(&authority)?;
+        let account = account.as_deref();
+        let code = account.map(|account| &account.code);
+        if code.is_some_and(|code| !code.is_empty() && code.delegation().is_none()) {
+            continue;
+        }
+        if account.map(|account| account.nonce).unwrap_or_default() != authorization.nonce {
+            continue;
+        }
+        if account.is_some_and(|account| !account.is_empty()) {
+            refund += existing_authority_refund;
+        }
+
+        let account = context.state.account_mut(authority)?;
+        account.code = if authorization.address.is_zero() {
+            Default::default()
+        } else {
+            Bytecode::delegation_to(authorization.address)
+        };
+        account.nonce += 1;
+    }
+    Ok((authorities, refund))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        context::{Block, State, Transaction},
+        interpreter::Account,
+        parse_hex,
+        primitives::{AccessList, AccessListItem, Signature, SignedAuthorization, B256},
+    };
+    use alloy::eips::eip7702::Authorization;
+    use k256::ecdsa::SigningKey;
+
+    const SENDER: Address = Address::repeat_byte(0x11);
+    const RECIPIENT: Address = Address::repeat_byte(0x22);
+    const COINBASE: Address = Address::repeat_byte(0x33);
+
+    fn build_context(code: &str, transaction: Transaction) -> Context {
+        let sender = Account {
+            balance: U256::from(1_000_000_000),
+            nonce: 7,
+            ..Default::default()
+        };
+        let recipient = Account {
+            code: code.try_into().unwrap(),
+            ..Default::default()
+        };
+        let mut context = Context::default();
+        context.block = Block {
+            coinbase: COINBASE,
+            gas_limit: 30_000_000,
+            ..Default::default()
+        };
+        context.transaction = Transaction {
+            sender: SENDER,
+            to: RECIPIENT.into(),
+            nonce: 7,
+            gas_limit: 50_000,
+            gas_price: U256::from(10),
+            ..transaction
+        };
+        context.state = State::from([(SENDER, sender), (RECIPIENT, recipient)].into_iter());
+        context
+    }
+
+    #[test]
+    fn test_fees_and_value_transfer() {
+        let context = build_context(
+            // PUSH1 1, PUSH1 2, ADD
+            "0x6001600201",
+            Transaction {
+                value: U256::from(1000),
+                input: vec![0, 1],
+                ..Default::default()
+            },
+        );
+        let execution = Interpreter::default().transact(context).unwrap();
+        // the calldata floor of one zero and one nonzero byte exceeds 21000 + 4 + 16 + 9
+        let gas_used = 21000 + 10 * (1 + 4);
+        assert_eq!(execution.result.gas_used(), gas_used);
+
+        let state = &execution.context.state;
+        assert_eq!(state.account(&SENDER).unwrap().nonce, 8);
+        assert_eq!(
+            state.account(&SENDER).unwrap().balance,
+            U256::from(1_000_000_000 - 1000 - gas_used * 10)
+        );
+        assert_eq!(state.account(&RECIPIENT).unwrap().balance, U256::from(1000));
+        assert_eq!(
+            state.account(&COINBASE).unwrap().balance,
+            U256::from(gas_used * 10)
+        );
+
+        // before Prague there is no floor
+        let mut context = build_context(
+            "0x6001600201",
+            Transaction {
+                input: vec![0, 1],
+                ..Default::default()
+            },
+        );
+        context.set_spec(SpecId::Cancun);
+        let execution = Interpreter::default().transact(context).unwrap();
+        assert_eq!(execution.result.gas_used(), 21000 + 4 + 16 + 9);
+    }
+
+    #[test]
+    fn test_failed_execution_keeps_fees() {
+        let context = build_context(
+            "0xfe",
+            Transaction {
+                value: U256::from(1000),
+                ..Default::default()
+            },
+        );
+        let execution = Interpreter::default().transact(context).unwrap();
+        assert_eq!(execution.result.gas_used(), 50_000);
+
+        let state = &execution.context.state;
+        assert_eq!(state.account(&SENDER).unwrap().nonce, 8);
+        assert_eq!(
+            state.account(&SENDER).unwrap().balance,
+            U256::from(1_000_000_000 - 50_000 * 10)
+        );
+        assert_eq!(state.account(&RECIPIENT).unwrap().balance, U256::ZERO);
+        assert_eq!(
+            state.account(&COINBASE).unwrap().balance,
+            U256::from(50_000 * 10)
+        );
+    }
+
+    #[test]
+    fn test_invalid_transactions() {
+        let mut context = build_context("0x", Default::default());
+        context.transaction.nonce = 6;
+        let result = Interpreter::default().transact(context);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTransaction(
+                InvalidTransaction::NonceMismatch {
+                    expected: 7,
+                    actual: 6
+                }
+            ))
+        ));
+
+        let mut context = build_context("0x", Default::default());
+        context.transaction.chain_id = Some(5);
+        let result = Interpreter::default().transact(context);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTransaction(
+                InvalidTransaction::ChainIdMismatch {
+                    expected: 0,
+                    actual: 5
+                }
+            ))
+        ));
+
+        let mut context = build_context("0x", Default::default());
+        context.block.gas_limit = 49_999;
+        let result = Interpreter::default().transact(context);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTransaction(
+                InvalidTransaction::GasLimitExceedsBlockGasLimit {
+                    gas_limit: 50_000,
+                    block_gas_limit: 49_999
+                }
+            ))
+        ));
+
+        let mut context = build_context("0x", Default::default());
+        context.transaction.gas_limit = 20_999;
+        let result = Interpreter::default().transact(context);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTransaction(
+                InvalidTransaction::IntrinsicGasTooLow { .. }
+            ))
+        ));
+
+        // 100 nonzero bytes of calldata cost 21000 + 1600 but have a floor of 21000 + 4000
+        let mut context = build_context("0x", Default::default());
+        context.transaction.input = vec![1; 100];
+        context.transaction.gas_limit = 24_000;
+        let result = Interpreter::default().transact(context);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTransaction(
+                InvalidTransaction::FloorDataGasTooLow {
+                    floor_data_gas: 25_000,
+                    gas_limit: 24_000
+                }
+            ))
+        ));
+
+        let mut context = build_context("0x", Default::default());
+        context.transaction.value = U256::from(1_000_000_000);
+        let result = Interpreter::default().transact(context);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTransaction(
+                InvalidTransaction::InsufficientFunds { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_fee_market() {
+        let mut context = build_context(
+            "0x",
+            Transaction {
+                max_fee_per_gas: Some(U256::from(10)),
+                max_priority_fee_per_gas: U256::from(2),
+                ..Default::default()
+            },
+        );
+        context.block.base_fee = U256::from(7);
+        let execution = Interpreter::default().transact(context).unwrap();
+        assert_eq!(execution.result.gas_used(), 21000);
+
+        let state = &execution.context.state;
+        assert_eq!(
+            state.account(&SENDER).unwrap().balance,
+            U256::from(1_000_000_000 - 21000 * 9)
+        );
+        assert_eq!(
+            state.account(&COINBASE).unwrap().balance,
+            U256::from(21000 * 2)
+        );
+
+        // without a priority fee the coinbase is paid nothing, and is not created
+        let mut context = build_context(
+            "0x",
+            Transaction {
+                max_fee_per_gas: Some(U256::from(10)),
+                ..Default::default()
+            },
+        );
+        context.block.base_fee = U256::from(7);
+        let execution = Interpreter::default().transact(context).unwrap();
+        assert!(execution.context.state.get(&COINBASE).unwrap().is_none());
+
+        let mut context = build_context("0x", Default::default());
+        context.block.base_fee = U256::from(11);
+        let result = Interpreter::default().transact(context);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTransaction(
+                InvalidTransaction::GasPriceLessThanBaseFee { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_contract_creation() {
+        // PUSH1 1, PUSH1 0, MSTORE8, PUSH1 1, PUSH1 0, RETURN
+        let initcode = parse_hex("0x600160005360016000f3").unwrap();
+        let mut context = build_context(
+            "0x",
+            Transaction {
+                value: U256::from(1000),
+                input: initcode.clone(),
+                ..Default::default()
+            },
+        );
+        context.transaction.to = TxKind::Create;
+        context.transaction.gas_limit = 100_000;
+        let execution = Interpreter::default().transact(context).unwrap();
+        assert!(execution.result.is_success());
+        // the creation and initcode word costs, the calldata, the initcode and the deposit
+        assert_eq!(
+            execution.result.gas_used(),
+            21000 + 32000 + 2 + (2 * 4 + 8 * 16) + (3 + 3 + 6 + 3 + 3) + 200
+        );
+
+        let state = &execution.context.state;
+        assert_eq!(state.account(&SENDER).unwrap().nonce, 8);
+        let created = state.account(&SENDER.create(7)).unwrap();
+        assert_eq!(created.code.bytes(), &[0x01]);
+        assert_eq!(created.balance, U256::from(1000));
+        assert_eq!(created.nonce, 1);
+
+        // a failed creation still increments the nonce of the sender, but keeps no value
+        let mut context = build_context(
+            "0x",
+            Transaction {
+                value: U256::from(1000),
+                input: vec![0xfe],
+                ..Default::default()
+            },
+        );
+        context.transaction.to = TxKind::Create;
+        context.transaction.gas_limit = 100_000;
+        let execution = Interpreter::default().transact(context).unwrap();
+        assert_eq!(execution.result.gas_used(), 100_000);
+        let state = &execution.context.state;
+        assert_eq!(state.account(&SENDER).unwrap().nonce, 8);
+        assert!(state.get(&SENDER.create(7)).unwrap().is_none());
+
+        // EIP-3860
+        let mut context = build_context(
+            "0x",
+            Transaction {
+                input: vec![0; MAXIMUM_INITCODE_SIZE + 1],
+                ..Default::default()
+            },
+        );
+        context.transaction.to = TxKind::Create;
+        context.transaction.gas_limit = 10_000_000;
+        let result = Interpreter::default().transact(context);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTransaction(
+                InvalidTransaction::InitcodeTooLarge { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_touched_empty_accounts_are_removed() {
+        // a call transferring no value touches the empty recipient, which EIP-161 removes
+        let context = build_context("0x", Default::default());
+        let execution = Interpreter::default().transact(context).unwrap();
+        assert!(execution.context.state.get(&RECIPIENT).unwrap().is_none());
+
+        let mut context = build_context("0x", Default::default());
+        context.set_spec(SpecId::TangerineWhistle);
+        let execution = Interpreter::default().transact(context).unwrap();
+        assert!(execution.context.state.get(&RECIPIENT).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_access_list() {
+        // PUSH1 1, SLOAD, POP, PUSH20 0x4444.., BALANCE, POP
+        let code = format!("0x6001545073{}3150", "44".repeat(20));
+        let execution = Interpreter::default()
+            .transact(build_context(&code, Default::default()))
+            .unwrap();
+        // cold SLOAD and BALANCE
+        assert_eq!(
+            execution.result.gas_used(),
+            21000 + 3 + 2100 + 2 + 3 + 2600 + 2
+        );
+
+        let access_list = AccessList::from(vec![
+            AccessListItem {
+                address: RECIPIENT,
+                storage_keys: vec![B256::with_last_byte(1)],
+            },
+            AccessListItem {
+                address: Address::repeat_byte(0x44),
+                storage_keys: vec![],
+            },
+        ]);
+        let transaction = Transaction {
+            access_list,
+            ..Default::default()
+        };
+        let execution = Interpreter::default()
+            .transact(build_context(&code, transaction))
+            .unwrap();
+        assert_eq!(
+            execution.result.gas_used(),
+            21000 + 2 * 2400 + 1900 + 3 + 100 + 2 + 3 + 100 + 2
+        );
+    }
+
+    #[test]
+    fn test_blob_gas() {
+        let mut hash = B256::repeat_byte(0x22);
+        hash[0] = VERSIONED_HASH_VERSION_KZG;
+        let transaction = Transaction {
+            blob_versioned_hashes: vec![hash; 2],
+            max_fee_per_blob_gas: Some(U256::from(1)),
+            ..Default::default()
+        };
+        let execution = Interpreter::default()
+            .transact(build_context("0x", transaction.clone()))
+            .unwrap();
+        // blob gas is burned at the blob base fee of 1
+        let state = &execution.context.state;
+        assert_eq!(
+            state.account(&SENDER).unwrap().balance,
+            U256::from(1_000_000_000 - 21000 * 10 - 2 * 131072)
+        );
+        assert_eq!(
+            state.account(&COINBASE).unwrap().balance,
+            U256::from(21000 * 10)
+        );
+
+        let mut context = build_context("0x", transaction.clone());
+        context.block.excess_blob_gas = 10_000_000;
+        let result = Interpreter::default().transact(context);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTransaction(
+                InvalidTransaction::BlobGasPriceLessThanBlobBaseFee { .. }
+            ))
+        ));
+
+        let mut context = build_context("0x", transaction.clone());
+        context.transaction.blob_versioned_hashes[1] = B256::ZERO;
+        let result = Interpreter::default().transact(context);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTransaction(
+                InvalidTransaction::InvalidBlobVersionedHash
+            ))
+        ));
+
+        let mut context = build_context("0x", transaction);
+        context.set_spec(SpecId::Shanghai);
+        let result = Interpreter::default().transact(context);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTransaction(
+                InvalidTransaction::BlobsNotSupported
+            ))
+        ));
+    }
+
+    fn sign_authorization(key: &SigningKey, authorization: Authorization) -> SignedAuthorization {
+        let (signature, recovery_id) = key
+            .sign_prehash_recoverable(authorization.signature_hash().as_slice())
+            .unwrap();
+        let signature =
+            Signature::from_signature_and_parity(signature, recovery_id.is_y_odd()).unwrap();
+        authorization.into_signed(signature)
+    }
+
+    #[test]
+    fn test_set_code_authorizations() {
+        let key = SigningKey::from_bytes(&[0x01; 32].into()).unwrap();
+        let authority = Address::from_public_key(key.verifying_key());
+        let delegate = Address::repeat_byte(0x44);
+        let authorization = |nonce| {
+            sign_authorization(
+                &key,
+                Authorization {
+                    chain_id: 0,
+                    address: delegate,
+                    nonce,
+                },
+            )
+        };
+
+        // PUSH20 authority, EXTCODESIZE, PUSH1 0, SSTORE
+        let code = format!("0x73{}3b600055", hex::encode(authority));
+        let mut context = build_context(
+            "0x",
+            Transaction {
+                transaction_type: TransactionType::Eip7702,
+                // the second authorization has a stale nonce and is skipped
+                authorization_list: vec![authorization(0), authorization(0)],
+                ..Default::default()
+            },
+        );
+        context.transaction.to = authority.into();
+        context.transaction.gas_limit = 100_000;
+        context.state.account_mut(delegate).unwrap().code = code.as_str().try_into().unwrap();
+        let execution = Interpreter::default().transact(context).unwrap();
+        assert!(execution.result.is_success());
+        // warm EXTCODESIZE of the authority and a cold SSTORE
+        assert_eq!(
+            execution.result.gas_used(),
+            21000 + 2 * 25000 + 3 + 100 + 3 + 2100 + 20000
+        );
+
+        let account = execution.context.state.account(&authority).unwrap();
+        assert_eq!(account.nonce, 1);
+        assert_eq!(account.code.delegation(), Some(delegate));
+        // the delegation designator is exposed, rather than followed
+        assert_eq!(account.storage[&U256::ZERO], U256::from(23));
+
+        // only an authority which is not empty earns the refund, even if the empty account
+        // is already present in the state, and the refund is capped at a fifth of the gas used
+        for (balance, refund) in [(0, 0), (1, (21000 + 25000) / 5)] {
+            let mut context = build_context(
+                "0x",
+                Transaction {
+                    transaction_type: TransactionType::Eip7702,
+                    authorization_list: vec![authorization(0)],
+                    ..Default::default()
+                },
+            );
+            context.transaction.gas_limit = 100_000;
+            context.state.account_mut(authority).unwrap().balance = U256::from(balance);
+            let execution = Interpreter::default().transact(context).unwrap();
+            assert_eq!(execution.result.gas_used(), 21000 + 25000 - refund);
+        }
+
+        let context = build_context(
+            "0x",
+            Transaction {
+                transaction_type: TransactionType::Eip7702,
+                ..Default::default()
+            },
+        );
+        let result = Interpreter::default().transact(context);
+        assert!(matches!(
+            result,
+            Err(Error::InvalidTransaction(
+                InvalidTransaction::EmptyAuthorizationList
+            ))
+        ));
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{
+    interpreter::Word,
+    primitives::{Address, Bytes, B256, U256},
+    trie::verify_proof,
+    Error,
+};
+use alloy::{
+    consensus::{
+        constants::{EMPTY_ROOT_HASH, KECCAK_EMPTY},
+        Account as TrieAccount,
+    },
+    primitives::keccak256,
+    rlp,
+};
+
+/// An account and some of its storage slots, with Merkle proofs of each against the state
+/// root, as returned by `eth_getProof` (EIP-1186).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountProof {
+    pub address: Address,
+    pub balance: U256,
+    pub nonce: u64,
+    pub code_hash: B256,
+    /// The root of the account's storage trie.
+    pub storage_hash: B256,
+    /// The nodes of the state trie on the path to the account, starting from the root.
+    pub account_proof: Vec<Bytes>,
+    pub storage_proof: Vec<StorageProof>,
+}
+
+/// A storage slot, with a Merkle proof of its value against the storage root of its account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageProof {
+    pub key: Word,
+    pub value: Word,
+    /// The nodes of the storage trie on the path to the slot, starting from the root.
+    pub proof: Vec<Bytes>,
+}
+
+impl AccountProof {
+    /// Check the account and each storage slot against `state_root`, failing if any of
+    /// their proofs does not hold.
+    ///
+    /// A missing account is proven by the absence of its address from the state trie, and
+    /// is expected to have the fields of an empty account.
+    pub fn verify(&self, state_root: B256) -> Result<(), Error> {
+        let account = TrieAccount {
+            nonce: self.nonce,
+            balance: self.balance,
+            storage_root: self.storage_hash,
+            code_hash: self.code_hash,
+        };
+        let proven = verify_proof(
+            state_root,
+            keccak256(self.address).as_slice(),
+            &self.account_proof,
+        )?;
+        let matches = match proven {
+            Some(proven) => proven == rlp::encode(account),
+            None => account == EMPTY_ACCOUNT,
+        };
+        if !matches {
+            return Err(Error::InvalidProof("account does not match its proof"));
+        }
+        for slot in &self.storage_proof {
+            slot.verify(self.storage_hash)?;
+        }
+        Ok(())
+    }
+}
+
+impl StorageProof {
+    /// Check the slot against `storage_root`, failing if its proof does not hold.
+    pub fn verify(&self, storage_root: B256) -> Result<(), Error> {
+        let key = keccak256(B256::from(self.key));
+        let proven = verify_proof(storage_root, key.as_slice(), &self.proof)?;
+        // Slots cleared to zero are removed from the trie
+        let expected = (!self.value.is_zero()).then(|| rlp::encode(self.value));
+        if proven != expected {
+            return Err(Error::InvalidProof("slot does not match its proof"));
+        }
+        Ok(())
+    }
+}
+
+// The fields reported for an account which does not exist
+pub(crate) const EMPTY_ACCOUNT: TrieAccount = TrieAccount {
+    nonce: 0,
+    balance: U256::ZERO,
+    storage_root: EMPTY_ROOT_HASH,
+    code_hash: KECCAK_EMPTY,
+};
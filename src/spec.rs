@@ -0,0 +1,130 @@
+/// Hard forks of the Ethereum protocol, in activation order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SpecId {
+    Frontier,
+    Homestead,
+    TangerineWhistle,
+    SpuriousDragon,
+    Byzantium,
+    Constantinople,
+    Petersburg,
+    Istanbul,
+    MuirGlacier,
+    Berlin,
+    London,
+    ArrowGlacier,
+    GrayGlacier,
+    Paris,
+    Shanghai,
+    Cancun,
+    Prague,
+    Osaka,
+    /// Rules not scheduled for any fork, such as the EVM Object Format; never in effect on
+    /// mainnet.
+    Experimental,
+}
+
+impl Default for SpecId {
+    fn default() -> Self {
+        Self::LATEST
+    }
+}
+
+// Forks activated by block number on mainnet, most recent first.
+const MAINNET_BLOCK_ACTIVATIONS: &[(u64, SpecId)] = &[
+    (15_537_394, SpecId::Paris),
+    (15_050_000, SpecId::GrayGlacier),
+    (13_773_000, SpecId::ArrowGlacier),
+    (12_965_000, SpecId::London),
+    (12_244_000, SpecId::Berlin),
+    (9_200_000, SpecId::MuirGlacier),
+    (9_069_000, SpecId::Istanbul),
+    (7_280_000, SpecId::Petersburg),
+    (4_370_000, SpecId::Byzantium),
+    (2_675_000, SpecId::SpuriousDragon),
+    (2_463_000, SpecId::TangerineWhistle),
+    (1_150_000, SpecId::Homestead),
+];
+
+// Forks activated by block timestamp on mainnet, most recent first.
+const MAINNET_TIMESTAMP_ACTIVATIONS: &[(u64, SpecId)] = &[
+    (1_764_798_551, SpecId::Osaka),
+    (1_746_612_311, SpecId::Prague),
+    (1_710_338_135, SpecId::Cancun),
+    (1_681_338_455, SpecId::Shanghai),
+];
+
+impl SpecId {
+    /// The most recent fork whose rules are supported by default.
+    pub const LATEST: Self = Self::Prague;
+
+    /// Returns `true` if the rules of `fork` apply under this spec.
+    pub fn is_enabled_in(self, fork: Self) -> bool {
+        self >= fork
+    }
+
+    /// Returns the fork in effect on mainnet for a block with the given `number` and `timestamp`.
+    pub fn for_mainnet_block(number: u64, timestamp: u64) -> Self {
+        MAINNET_TIMESTAMP_ACTIVATIONS
+            .iter()
+            .find(|(activation, _)| timestamp >= *activation)
+            .or_else(|| {
+                MAINNET_BLOCK_ACTIVATIONS
+                    .iter()
+                    .find(|(activation, _)| number >= *activation)
+            })
+            .map(|(_, spec)| *spec)
+            .unwrap_or(Self::Frontier)
+    }
+
+    /// EIP-2929: gas cost increases for state access opcodes, with warm and cold accesses.
+    pub fn has_eip2929(self) -> bool {
+        self.is_enabled_in(Self::Berlin)
+    }
+
+    /// EIP-3529: reduction in refunds and removal of the `SELFDESTRUCT` refund.
+    pub fn has_eip3529(self) -> bool {
+        self.is_enabled_in(Self::London)
+    }
+
+    /// EIP-6780: `SELFDESTRUCT` only in the same transaction as contract creation.
+    pub fn has_eip6780(self) -> bool {
+        self.is_enabled_in(Self::Cancun)
+    }
+
+    /// EIP-3540: the EVM Object Format, executed under the rules of the EIPs extending it.
+    ///
+    /// EOF was dropped from Osaka, so it is only enabled under `Experimental`.
+    pub fn has_eip3540(self) -> bool {
+        self.is_enabled_in(Self::Experimental)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mainnet_activations() {
+        assert_eq!(SpecId::for_mainnet_block(0, 0), SpecId::Frontier);
+        assert_eq!(SpecId::for_mainnet_block(4_370_000, 0), SpecId::Byzantium);
+        assert_eq!(
+            SpecId::for_mainnet_block(17_034_870, 1_681_338_455),
+            SpecId::Shanghai
+        );
+        assert_eq!(
+            SpecId::for_mainnet_block(19_426_586, 1_710_338_134),
+            SpecId::Shanghai
+        );
+        assert_eq!(
+            SpecId::for_mainnet_block(19_426_587, 1_710_338_135),
+            SpecId::Cancun
+        );
+        assert_eq!(
+            SpecId::for_mainnet_block(23_935_694, 1_764_798_551),
+            SpecId::Osaka
+        );
+        assert!(!SpecId::Osaka.has_eip3540());
+        assert!(SpecId::Experimental.has_eip3540());
+    }
+}
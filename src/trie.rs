@@ -0,0 +1,382 @@
+use crate::{primitives::B256, Error};
+use alloy::{
+    consensus::constants::EMPTY_ROOT_HASH,
+    primitives::keccak256,
+    rlp::{self, Header, EMPTY_LIST_CODE, EMPTY_STRING_CODE},
+};
+use std::collections::BTreeMap;
+
+/// A Merkle Patricia Trie, built in memory from its entries.
+///
+/// Nodes are RLP encoded with hex-prefix encoded paths, as specified in appendix D of the
+/// yellow paper. The state trie and the storage tries of accounts are "secure" tries, keyed
+/// by the keccak256 hashes of addresses and storage keys rather than by the keys themselves.
+#[derive(Debug, Default, Clone)]
+pub struct Trie {
+    // Values keyed by the nibbles of their keys
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl Trie {
+    /// Set the value at `key`, removing it if `value` is empty.
+    pub fn insert(&mut self, key: &[u8], value: Vec<u8>) {
+        let key = nibbles(key);
+        if value.is_empty() {
+            self.entries.remove(&key);
+        } else {
+            self.entries.insert(key, value);
+        }
+    }
+
+    /// Returns the hash of the root node, which commits to every entry.
+    pub fn root(&self) -> B256 {
+        keccak256(encode_node(&self.sorted_entries(), 0, None, &mut vec![]))
+    }
+
+    /// Returns the nodes on the path to `key`, starting from the root, which prove the value
+    /// at `key` or its absence against the root hash.
+    ///
+    /// Nodes short enough to be embedded in their parent are not included separately, and
+    /// the proof for an empty trie holds no nodes.
+    pub fn proof(&self, key: &[u8]) -> Vec<Vec<u8>> {
+        let entries = self.sorted_entries();
+        if entries.is_empty() {
+            return vec![];
+        }
+        let mut proof = vec![];
+        encode_node(&entries, 0, Some(&nibbles(key)), &mut proof);
+        proof.reverse();
+        proof
+    }
+
+    fn sorted_entries(&self) -> Vec<(&[u8], &[u8])> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_slice(), value.as_slice()))
+            .collect()
+    }
+}
+
+/// Returns the value at `key` in the trie with the given `root`, or `None` if it is absent,
+/// checking each node of `proof` as returned by `Trie::proof`.
+pub fn verify_proof(
+    root: B256,
+    key: &[u8],
+    proof: &[impl AsRef<[u8]>],
+) -> Result<Option<Vec<u8>>, Error> {
+    let path = nibbles(key);
+    let mut path = path.as_slice();
+    let mut proof = proof.iter().map(AsRef::as_ref);
+    let mut next = if root == EMPTY_ROOT_HASH {
+        Reference::Empty
+    } else {
+        Reference::Hash(root)
+    };
+    loop {
+        let node = match next {
+            Reference::Empty => return Ok(None),
+            Reference::Embedded(node) => node,
+            Reference::Hash(hash) => {
+                let node = proof.next().ok_or(Error::InvalidProof("missing node"))?;
+                if keccak256(node) != hash {
+                    return Err(Error::InvalidProof("node does not match its hash"));
+                }
+                node
+            }
+        };
+        match decode_list(node)?.as_slice() {
+            [children @ .., value] if children.len() == 16 => {
+                let Some((nibble, rest)) = path.split_first() else {
+                    let value = decode_bytes(value)?;
+                    return Ok((!value.is_empty()).then(|| value.to_vec()));
+                };
+                next = Reference::decode(children[*nibble as usize])?;
+                path = rest;
+            }
+            [node_path, child] => {
+                let (node_path, leaf) = decode_hex_prefix(decode_bytes(node_path)?)?;
+                if leaf {
+                    if path != node_path.as_slice() {
+                        return Ok(None);
+                    }
+                    return Ok(Some(decode_bytes(child)?.to_vec()));
+                }
+                let Some(rest) = path.strip_prefix(node_path.as_slice()) else {
+                    return Ok(None);
+                };
+                next = Reference::decode(child)?;
+                path = rest;
+            }
+            _ => {
+                return Err(Error::InvalidProof(
+                    "node is neither a branch, extension nor leaf",
+                ))
+            }
+        }
+    }
+}
+
+fn nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+// Returns the RLP encoding of the node holding `entries`, sorted by key, whose keys share
+// their first `depth` nibbles. If `path` is set, the nodes on the way to it which are not
+// embedded in their parent are added to `proof`, deepest first.
+fn encode_node(
+    entries: &[(&[u8], &[u8])],
+    depth: usize,
+    path: Option<&[u8]>,
+    proof: &mut Vec<Vec<u8>>,
+) -> Vec<u8> {
+    let node = encode_node_children(entries, depth, path, proof);
+    if path.is_some() && (depth == 0 || node.len() >= B256::len_bytes()) {
+        proof.push(node.clone());
+    }
+    node
+}
+
+fn encode_node_children(
+    entries: &[(&[u8], &[u8])],
+    depth: usize,
+    path: Option<&[u8]>,
+    proof: &mut Vec<Vec<u8>>,
+) -> Vec<u8> {
+    let (first, last) = match entries {
+        [] => return vec![EMPTY_STRING_CODE],
+        [(key, value)] => {
+            return encode_list(&[
+                rlp::encode(hex_prefix(&key[depth..], true).as_slice()),
+                rlp::encode(value),
+            ])
+        }
+        [(first, _), .., (last, _)] => (first, last),
+    };
+    // Keys are sorted, so the nibbles shared by all are those shared by the first and last
+    let shared = first[depth..]
+        .iter()
+        .zip(&last[depth..])
+        .take_while(|(a, b)| a == b)
+        .count();
+    if shared > 0 {
+        let prefix = &first[depth..depth + shared];
+        let path = path.filter(|path| path.get(depth..depth + shared) == Some(prefix));
+        let child = encode_node(entries, depth + shared, path, proof);
+        return encode_list(&[
+            rlp::encode(hex_prefix(prefix, false).as_slice()),
+            reference(child),
+        ]);
+    }
+
+    // A key ending at this node sorts first, and its value is held by the branch itself
+    let (value, mut rest) = match entries {
+        [(key, value), rest @ ..] if key.len() == depth => (rlp::encode(value), rest),
+        _ => (vec![EMPTY_STRING_CODE], entries),
+    };
+    let mut items = Vec::with_capacity(17);
+    for nibble in 0..16 {
+        let count = rest
+            .iter()
+            .take_while(|(key, _)| key[depth] == nibble)
+            .count();
+        let (children, remaining) = rest.split_at(count);
+        items.push(if children.is_empty() {
+            vec![EMPTY_STRING_CODE]
+        } else {
+            let path = path.filter(|path| path.get(depth) == Some(&nibble));
+            reference(encode_node(children, depth + 1, path, proof))
+        });
+        rest = remaining;
+    }
+    items.push(value);
+    encode_list(&items)
+}
+
+// Returns how a parent refers to `node`: nodes shorter than a hash are embedded in full
+fn reference(node: Vec<u8>) -> Vec<u8> {
+    if node.len() < B256::len_bytes() {
+        node
+    } else {
+        rlp::encode(keccak256(node))
+    }
+}
+
+// Returns the hex-prefix encoding of `nibbles`, flagged as the path of a leaf or extension
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let odd = nibbles.len() % 2 == 1;
+    let flag = (leaf as u8) << 1 | odd as u8;
+    let mut encoded = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if odd {
+        encoded.push(flag << 4 | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        encoded.push(flag << 4);
+        nibbles
+    };
+    encoded.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+    encoded
+}
+
+// How a node refers to a child
+enum Reference<'a> {
+    Empty,
+    Hash(B256),
+    Embedded(&'a [u8]),
+}
+
+impl<'a> Reference<'a> {
+    fn decode(item: &'a [u8]) -> Result<Self, Error> {
+        if item.first().is_some_and(|byte| *byte >= EMPTY_LIST_CODE) {
+            return Ok(Self::Embedded(item));
+        }
+        match decode_bytes(item)? {
+            [] => Ok(Self::Empty),
+            hash if hash.len() == B256::len_bytes() => Ok(Self::Hash(B256::from_slice(hash))),
+            _ => Err(Error::InvalidProof("child is neither a hash nor a node")),
+        }
+    }
+}
+
+// Returns the nibbles of a hex-prefix encoded path, and whether it is the path of a leaf
+fn decode_hex_prefix(encoded: &[u8]) -> Result<(Vec<u8>, bool), Error> {
+    let (first, rest) = encoded
+        .split_first()
+        .ok_or(Error::InvalidProof("empty path"))?;
+    let flag = first >> 4;
+    let mut nibbles = if flag & 1 == 1 {
+        vec![first & 0x0f]
+    } else {
+        vec![]
+    };
+    nibbles.extend(self::nibbles(rest));
+    Ok((nibbles, flag & 2 == 2))
+}
+
+// Returns the raw encodings of the items of the RLP list `node`
+fn decode_list(mut node: &[u8]) -> Result<Vec<&[u8]>, Error> {
+    let header = Header::decode(&mut node).map_err(|_| Error::InvalidProof("invalid RLP"))?;
+    if !header.list || header.payload_length != node.len() {
+        return Err(Error::InvalidProof("node is not a list"));
+    }
+    let mut items = vec![];
+    while !node.is_empty() {
+        let mut payload = node;
+        let header =
+            Header::decode(&mut payload).map_err(|_| Error::InvalidProof("invalid RLP"))?;
+        let length = node.len() - payload.len() + header.payload_length;
+        if length > node.len() {
+            return Err(Error::InvalidProof("invalid RLP"));
+        }
+        let (item, rest) = node.split_at(length);
+        items.push(item);
+        node = rest;
+    }
+    Ok(items)
+}
+
+// Returns the payload of the RLP string `item`
+fn decode_bytes(mut item: &[u8]) -> Result<&[u8], Error> {
+    Header::decode_bytes(&mut item, false).map_err(|_| Error::InvalidProof("invalid RLP"))
+}
+
+// Returns the RLP encoding of a list of already encoded `items`
+fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload_length = items.iter().map(Vec::len).sum();
+    let mut encoded = vec![];
+    Header {
+        list: true,
+        payload_length,
+    }
+    .encode(&mut encoded);
+    for item in items {
+        encoded.extend_from_slice(item);
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloy::primitives::b256;
+
+    fn trie(entries: &[(&str, &str)]) -> Trie {
+        let mut trie = Trie::default();
+        for (key, value) in entries {
+            trie.insert(key.as_bytes(), value.as_bytes().to_vec());
+        }
+        trie
+    }
+
+    #[test]
+    fn test_root() {
+        assert_eq!(Trie::default().root(), EMPTY_ROOT_HASH);
+
+        // From the `trietest.json` suite of the Ethereum tests
+        let dogs = trie(&[
+            ("doe", "reindeer"),
+            ("dog", "puppy"),
+            ("dogglesworth", "cat"),
+        ]);
+        assert_eq!(
+            dogs.root(),
+            b256!("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3")
+        );
+        let mut puppy = trie(&[
+            ("do", "verb"),
+            ("horse", "stallion"),
+            ("doge", "coin"),
+            ("dog", "puppy"),
+        ]);
+        assert_eq!(
+            puppy.root(),
+            b256!("5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84")
+        );
+
+        // Empty values remove their entry
+        puppy.insert(b"ether", b"wookiedoo".to_vec());
+        puppy.insert(b"ether", vec![]);
+        assert_eq!(
+            puppy.root(),
+            b256!("5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84")
+        );
+    }
+
+    #[test]
+    fn test_proof() {
+        let mut trie = trie(&[
+            ("do", "verb"),
+            ("horse", "stallion"),
+            ("doge", "coin"),
+            ("dog", "puppy"),
+        ]);
+        // Enough hashed keys to give branches which are not embedded in their parents
+        for i in 0..64u8 {
+            trie.insert(keccak256([i]).as_slice(), vec![i + 1; 40]);
+        }
+        let root = trie.root();
+
+        for (key, value) in [
+            (b"dog".as_slice(), Some(b"puppy".to_vec())),
+            (b"do", Some(b"verb".to_vec())),
+            (keccak256([7]).as_slice(), Some(vec![8; 40])),
+            (b"doges", None),
+            (b"cat", None),
+            (keccak256([64]).as_slice(), None),
+        ] {
+            let proof = trie.proof(key);
+            assert_eq!(verify_proof(root, key, &proof).unwrap(), value);
+        }
+
+        let mut proof = trie.proof(b"horse");
+        assert!(verify_proof(root, b"horse", &proof[..proof.len() - 1]).is_err());
+        let last = proof.last_mut().unwrap();
+        *last.last_mut().unwrap() ^= 1;
+        assert!(verify_proof(root, b"horse", &proof).is_err());
+
+        assert!(Trie::default().proof(b"dog").is_empty());
+        let empty: &[Vec<u8>] = &[];
+        assert_eq!(verify_proof(EMPTY_ROOT_HASH, b"dog", empty).unwrap(), None);
+    }
+}